@@ -1,66 +1,799 @@
+mod play;
+use crate::play::App;
 use anyhow::Context;
-use lexopt::{Arg, Parser};
+use lexopt::{Arg, Parser, ValueExt};
 use patharg::InputArg;
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
+use std::path::PathBuf;
 use std::process::ExitCode;
-use sudoku::Puzzle;
+use std::str::FromStr;
+use sudoku::analysis::{analyze, Analysis};
+use sudoku::formats::Format;
+use sudoku::game::Game;
+use sudoku::generate::Generator;
+use sudoku::logic::{explain_from, next_step, rate, Difficulty};
+use sudoku::pdf::{Booklet, PageSize};
+use sudoku::pencilmarks::Position;
+use sudoku::svg::Svg;
+use sudoku::terminal::{Style, Terminal};
+use sudoku::transform::Transform;
+use sudoku::verify::Grid;
+use sudoku::{Puzzle, Solution};
+
+/// The default maximum number of solutions counted by `sudoku count`
+const DEFAULT_COUNT_LIMIT: usize = 1000;
+
+/// The number of puzzles `sudoku generate --difficulty` tries before giving up
+const MAX_GENERATE_ATTEMPTS: usize = 1000;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum Command {
-    Run { pretty: bool, infile: InputArg },
-    Help,
+    Solve {
+        text: TextOptions,
+        output: OutputFormat,
+        booklet: BookletOptions,
+        infile: InputArg,
+    },
+    Check {
+        puzzle: InputArg,
+        answer: InputArg,
+    },
+    Count {
+        limit: usize,
+        infile: InputArg,
+    },
+    Generate {
+        seed: Option<u64>,
+        difficulty: Option<Difficulty>,
+        symmetric: bool,
+        number: usize,
+    },
+    Minimize {
+        seed: Option<u64>,
+        symmetric: bool,
+        infile: InputArg,
+    },
+    Rate {
+        infile: InputArg,
+    },
+    Hint {
+        from: Option<PositionFormat>,
+        infile: InputArg,
+    },
+    Explain {
+        from: Option<PositionFormat>,
+        infile: InputArg,
+    },
+    Analyze {
+        infile: InputArg,
+    },
+    Convert {
+        from: Option<Format>,
+        to: Format,
+        infile: InputArg,
+    },
+    Render {
+        style: Style,
+        output: OutputFormat,
+        solution: bool,
+        infile: InputArg,
+    },
+    Dedupe {
+        infile: InputArg,
+    },
+    Transform {
+        transform: Transform,
+        solution: bool,
+        infile: InputArg,
+    },
+    Play {
+        keys: Option<String>,
+        save: Option<PathBuf>,
+        resume: Option<InputArg>,
+        infile: InputArg,
+    },
+    Help(&'static str),
     Version,
 }
 
+/// The formats in which `sudoku hint` & `sudoku explain` can read their input
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum PositionFormat {
+    /// A puzzle file, solved starting from its givens
+    Puzzle(Format),
+    /// A pencil-mark grid as exported by HoDoKu or SudokuWiki
+    PencilMarks,
+    /// A HoDoKu savepoint line
+    Hodoku,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum OutputFormat {
+    #[default]
+    Text,
+    Svg,
+    Pdf,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct TextOptions {
+    style: Style,
+    brackets: bool,
+    diff: Option<InputArg>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BookletOptions {
+    page_size: PageSize,
+    per_page: usize,
+    difficulty: Option<String>,
+}
+
+impl Default for BookletOptions {
+    fn default() -> BookletOptions {
+        BookletOptions {
+            page_size: PageSize::default(),
+            per_page: 4,
+            difficulty: None,
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat, String> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "svg" => Ok(OutputFormat::Svg),
+            "pdf" => Ok(OutputFormat::Pdf),
+            _ => Err(format!("unknown output format {s:?}")),
+        }
+    }
+}
+
 impl Command {
-    fn from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
-        let mut pretty = false;
+    fn from_parser(mut parser: Parser) -> anyhow::Result<Command> {
+        let subcommand = parser
+            .raw_args()?
+            .peek()
+            .and_then(|arg| arg.to_str())
+            .and_then(|arg| {
+                [
+                    "solve",
+                    "check",
+                    "count",
+                    "generate",
+                    "minimize",
+                    "rate",
+                    "hint",
+                    "explain",
+                    "analyze",
+                    "convert",
+                    "render",
+                    "dedupe",
+                    "transform",
+                    "play",
+                ]
+                .into_iter()
+                .find(|&name| name == arg)
+            });
+        if subcommand.is_some() {
+            let _ = parser.next()?;
+        }
+        let command = match subcommand {
+            Some("check") => Command::check_from_parser(parser),
+            Some("count") => Command::count_from_parser(parser),
+            Some("generate") => Command::generate_from_parser(parser),
+            Some("minimize") => Command::minimize_from_parser(parser),
+            Some("rate") => {
+                Command::analysis_from_parser(parser, RATE_HELP, |infile| Command::Rate { infile })
+            }
+            Some("hint") => Command::position_from_parser(parser, HINT_HELP, |from, infile| {
+                Command::Hint { from, infile }
+            }),
+            Some("explain") => {
+                Command::position_from_parser(parser, EXPLAIN_HELP, |from, infile| {
+                    Command::Explain { from, infile }
+                })
+            }
+            Some("analyze") => Command::analysis_from_parser(parser, ANALYZE_HELP, |infile| {
+                Command::Analyze { infile }
+            }),
+            Some("convert") => Command::convert_from_parser(parser),
+            Some("render") => Command::render_from_parser(parser),
+            Some("dedupe") => Command::analysis_from_parser(parser, DEDUPE_HELP, |infile| {
+                Command::Dedupe { infile }
+            }),
+            Some("transform") => return Command::transform_from_parser(parser),
+            Some("play") => Command::play_from_parser(parser),
+            Some(_) => Command::solve_from_parser(parser, SOLVE_HELP),
+            None => Command::solve_from_parser(parser, MAIN_HELP),
+        };
+        Ok(command?)
+    }
+
+    /// Parse the arguments to `sudoku solve` or to the bare `sudoku` command,
+    /// the latter of which shows the overall help message for `--help`
+    fn solve_from_parser(mut parser: Parser, help: &'static str) -> Result<Command, lexopt::Error> {
+        let mut text = TextOptions::default();
+        let mut output = OutputFormat::default();
+        let mut booklet = BookletOptions::default();
         let mut infile: Option<InputArg> = None;
         while let Some(arg) = parser.next()? {
             match arg {
-                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help),
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(help)),
                 Arg::Short('V') | Arg::Long("version") => return Ok(Command::Version),
-                Arg::Short('P') | Arg::Long("pretty") => pretty = true,
+                Arg::Short('P') | Arg::Long("pretty") => text.style = Style::Ascii,
+                Arg::Short('s') | Arg::Long("style") => text.style = parser.value()?.parse()?,
+                Arg::Short('b') | Arg::Long("brackets") => text.brackets = true,
+                Arg::Short('d') | Arg::Long("diff") => {
+                    text.diff = Some(InputArg::from_arg(parser.value()?));
+                }
+                Arg::Short('f') | Arg::Long("format") => output = parser.value()?.parse()?,
+                Arg::Long("page-size") => booklet.page_size = parser.value()?.parse()?,
+                Arg::Long("per-page") => {
+                    let n = parser.value()?.parse()?;
+                    if !(1..=9).contains(&n) {
+                        return Err(lexopt::Error::Custom(
+                            "--per-page must be between 1 and 9".into(),
+                        ));
+                    }
+                    booklet.per_page = n;
+                }
+                Arg::Long("difficulty") => booklet.difficulty = Some(parser.value()?.string()?),
                 Arg::Value(val) if infile.is_none() => {
                     infile = Some(InputArg::from_arg(val));
                 }
                 _ => return Err(arg.unexpected()),
             }
         }
-        Ok(Command::Run {
-            pretty,
+        Ok(Command::Solve {
+            text,
+            output,
+            booklet,
+            infile: infile.unwrap_or_default(),
+        })
+    }
+
+    fn check_from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut puzzle: Option<InputArg> = None;
+        let mut answer: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(CHECK_HELP)),
+                Arg::Value(val) if puzzle.is_none() => puzzle = Some(InputArg::from_arg(val)),
+                Arg::Value(val) if answer.is_none() => answer = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        match (puzzle, answer) {
+            (Some(puzzle), Some(answer)) => Ok(Command::Check { puzzle, answer }),
+            _ => Err(lexopt::Error::Custom(
+                "check requires <puzzle> and <answer> arguments".into(),
+            )),
+        }
+    }
+
+    fn count_from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut limit = DEFAULT_COUNT_LIMIT;
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(COUNT_HELP)),
+                Arg::Short('l') | Arg::Long("limit") => {
+                    limit = parser.value()?.parse()?;
+                    if limit == 0 {
+                        return Err(lexopt::Error::Custom("--limit must be positive".into()));
+                    }
+                }
+                Arg::Value(val) if infile.is_none() => infile = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Count {
+            limit,
+            infile: infile.unwrap_or_default(),
+        })
+    }
+
+    fn generate_from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut seed = None;
+        let mut difficulty = None;
+        let mut symmetric = false;
+        let mut number = 1;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(GENERATE_HELP)),
+                Arg::Long("seed") => seed = Some(parser.value()?.parse()?),
+                Arg::Short('D') | Arg::Long("difficulty") => {
+                    difficulty = Some(parser.value()?.parse()?);
+                }
+                Arg::Short('S') | Arg::Long("symmetric") => symmetric = true,
+                Arg::Short('n') | Arg::Long("number") => number = parser.value()?.parse()?,
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Generate {
+            seed,
+            difficulty,
+            symmetric,
+            number,
+        })
+    }
+
+    fn minimize_from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut seed = None;
+        let mut symmetric = false;
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(MINIMIZE_HELP)),
+                Arg::Long("seed") => seed = Some(parser.value()?.parse()?),
+                Arg::Short('S') | Arg::Long("symmetric") => symmetric = true,
+                Arg::Value(val) if infile.is_none() => infile = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Minimize {
+            seed,
+            symmetric,
+            infile: infile.unwrap_or_default(),
+        })
+    }
+
+    /// Parse the arguments to a subcommand that takes only an optional input
+    /// file
+    fn analysis_from_parser<F>(
+        mut parser: Parser,
+        help: &'static str,
+        make: F,
+    ) -> Result<Command, lexopt::Error>
+    where
+        F: FnOnce(InputArg) -> Command,
+    {
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(help)),
+                Arg::Value(val) if infile.is_none() => infile = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(make(infile.unwrap_or_default()))
+    }
+
+    /// Parse the arguments to a command that reads a position to solve
+    /// logically, constructing the command with `make`
+    fn position_from_parser<F>(
+        mut parser: Parser,
+        help: &'static str,
+        make: F,
+    ) -> Result<Command, lexopt::Error>
+    where
+        F: FnOnce(Option<PositionFormat>, InputArg) -> Command,
+    {
+        let mut from = None;
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(help)),
+                Arg::Long("from") => {
+                    from = Some(parser.value()?.parse_with(parse_position_format)?);
+                }
+                Arg::Value(val) if infile.is_none() => infile = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(make(from, infile.unwrap_or_default()))
+    }
+
+    fn convert_from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut from = None;
+        let mut to = Format::Plain;
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(CONVERT_HELP)),
+                Arg::Long("from") => from = Some(parser.value()?.parse_with(parse_format)?),
+                Arg::Short('t') | Arg::Long("to") => {
+                    to = parser.value()?.parse_with(parse_format)?;
+                }
+                Arg::Value(val) if infile.is_none() => infile = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Convert {
+            from,
+            to,
+            infile: infile.unwrap_or_default(),
+        })
+    }
+
+    fn render_from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut style = Style::Ascii;
+        let mut output = OutputFormat::default();
+        let mut solution = false;
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(RENDER_HELP)),
+                Arg::Short('s') | Arg::Long("style") => style = parser.value()?.parse()?,
+                Arg::Short('f') | Arg::Long("format") => {
+                    output = parser.value()?.parse()?;
+                    if output == OutputFormat::Pdf {
+                        return Err(lexopt::Error::Custom(
+                            "render does not support PDF output; use `sudoku solve -f pdf`".into(),
+                        ));
+                    }
+                }
+                Arg::Long("solution") => solution = true,
+                Arg::Value(val) if infile.is_none() => infile = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Render {
+            style,
+            output,
+            solution,
+            infile: infile.unwrap_or_default(),
+        })
+    }
+
+    /// Parse the arguments to `sudoku transform`.  The transformations are
+    /// applied in the order in which they are given.
+    ///
+    /// Unlike the other subcommands, this returns an [`anyhow::Error`] so
+    /// that invalid transformations are reported without a redundant cause.
+    fn transform_from_parser(mut parser: Parser) -> anyhow::Result<Command> {
+        let mut transform = Transform::identity();
+        let mut solution = false;
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            let step = match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(TRANSFORM_HELP)),
+                Arg::Long("solution") => {
+                    solution = true;
+                    continue;
+                }
+                Arg::Value(val) if infile.is_none() => {
+                    infile = Some(InputArg::from_arg(val));
+                    continue;
+                }
+                Arg::Long("rotate") => Ok(Transform::rotate(parser.value()?.parse()?)),
+                Arg::Long("reflect") => match parser.value()?.string()?.as_str() {
+                    "horizontal" => Ok(Transform::reflect_horizontally()),
+                    "vertical" => Ok(Transform::reflect_vertically()),
+                    s => anyhow::bail!("unknown reflection {s:?}"),
+                },
+                Arg::Long("transpose") => Ok(Transform::transpose()),
+                Arg::Long("permute-digits") => {
+                    Transform::permute_digits(parser.value()?.parse_with(parse_digits)?)
+                }
+                Arg::Long("swap-bands") => {
+                    let (a, b) = parser.value()?.parse_with(|s| parse_pair(s, 3))?;
+                    Transform::swap_bands(a, b)
+                }
+                Arg::Long("swap-stacks") => {
+                    let (a, b) = parser.value()?.parse_with(|s| parse_pair(s, 3))?;
+                    Transform::swap_stacks(a, b)
+                }
+                Arg::Long("swap-rows") => {
+                    let (a, b) = parser.value()?.parse_with(|s| parse_pair(s, 9))?;
+                    Transform::swap_rows(a, b)
+                }
+                Arg::Long("swap-columns") => {
+                    let (a, b) = parser.value()?.parse_with(|s| parse_pair(s, 9))?;
+                    Transform::swap_columns(a, b)
+                }
+                Arg::Long("random") => Ok(Transform::random(parser.value()?.parse()?)),
+                _ => return Err(arg.unexpected().into()),
+            };
+            transform = transform.then(step?);
+        }
+        Ok(Command::Transform {
+            transform,
+            solution,
+            infile: infile.unwrap_or_default(),
+        })
+    }
+
+    fn play_from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut keys = None;
+        let mut save = None;
+        let mut resume = None;
+        let mut infile: Option<InputArg> = None;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help(PLAY_HELP)),
+                Arg::Long("keys") => keys = Some(parser.value()?.string()?),
+                Arg::Long("save") => save = Some(PathBuf::from(parser.value()?)),
+                Arg::Short('r') | Arg::Long("resume") => {
+                    resume = Some(InputArg::from_arg(parser.value()?));
+                }
+                Arg::Value(val) if infile.is_none() => infile = Some(InputArg::from_arg(val)),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Play {
+            keys,
+            save,
+            resume,
             infile: infile.unwrap_or_default(),
         })
     }
 
     fn run(self) -> anyhow::Result<ExitCode> {
         match self {
-            Command::Run { pretty, infile } => {
-                let puzzle = infile
-                    .read_to_string()
-                    .context("Error reading input")?
-                    .parse::<Puzzle>()
-                    .context("Invalid input")?;
+            Command::Solve {
+                text: opts,
+                output,
+                booklet,
+                infile,
+            } => {
+                let format = input_format(&infile);
+                let text = infile.read_to_string().context("Error reading input")?;
+                if output == OutputFormat::Pdf {
+                    return make_booklet(format, &text, booklet);
+                }
+                let puzzle = format.parse(&text).context("Invalid input")?.puzzle;
                 if let Some(s) = puzzle.solve() {
-                    if pretty {
-                        println!("{s:#}");
+                    if output == OutputFormat::Svg {
+                        print!("{}", Svg::new(&puzzle).with_solution(&s));
+                        Ok(ExitCode::SUCCESS)
                     } else {
-                        println!("{s}");
+                        show_text(&puzzle, &s, opts)
                     }
-                    Ok(ExitCode::SUCCESS)
                 } else {
                     eprintln!("No solution");
                     Ok(ExitCode::FAILURE)
                 }
             }
-            Command::Help => {
-                println!("Usage: sudoku [-P|--pretty] [<infile>]");
-                println!();
-                println!("Solve a Sudoku puzzle");
-                println!();
-                println!("Options:");
-                println!("  -P, --pretty      Output the solution with borders and spacing");
-                println!("  -h, --help        Display this help message and exit");
-                println!("  -V, --version     Show the program version and exit");
+            Command::Check { puzzle, answer } => {
+                let puzzle = read_puzzle(&puzzle).context("Invalid puzzle")?;
+                let answer = read_puzzle(&answer).context("Invalid answer")?;
+                match Grid::from(answer).verify(&puzzle) {
+                    Ok(()) => {
+                        println!("Answer is correct");
+                        Ok(ExitCode::SUCCESS)
+                    }
+                    Err(violations) => {
+                        for v in violations {
+                            println!("{v}");
+                        }
+                        Ok(ExitCode::FAILURE)
+                    }
+                }
+            }
+            Command::Count { limit, infile } => {
+                let puzzle = read_puzzle(&infile).context("Invalid input")?;
+                let count = puzzle.count_solutions(limit);
+                println!("{count}");
+                if count >= limit {
+                    eprintln!("Stopped counting after reaching the limit of {limit} solutions");
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Generate {
+                seed,
+                difficulty,
+                symmetric,
+                number,
+            } => {
+                let seed = seed.unwrap_or_else(|| RandomState::new().build_hasher().finish());
+                let mut generator = Generator::new(seed).with_symmetry(symmetric);
+                for i in 0..number {
+                    let puzzle = if let Some(d) = difficulty {
+                        let Some((puzzle, _)) = generator.generate_rated(d, MAX_GENERATE_ATTEMPTS)
+                        else {
+                            eprintln!(
+                                "Could not generate a puzzle of difficulty {d} after {MAX_GENERATE_ATTEMPTS} attempts"
+                            );
+                            return Ok(ExitCode::FAILURE);
+                        };
+                        puzzle
+                    } else {
+                        generator.generate().0
+                    };
+                    if i > 0 {
+                        println!();
+                    }
+                    println!("{puzzle}");
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Minimize {
+                seed,
+                symmetric,
+                infile,
+            } => {
+                let puzzle = read_puzzle(&infile).context("Invalid input")?;
+                let seed = seed.unwrap_or_else(|| RandomState::new().build_hasher().finish());
+                let Some(minimal) = puzzle.minimize(seed, symmetric) else {
+                    if puzzle.count_solutions(1) == 0 {
+                        eprintln!("No solution");
+                    } else {
+                        eprintln!("Puzzle has multiple solutions");
+                    }
+                    return Ok(ExitCode::FAILURE);
+                };
+                println!("{minimal}");
+                let givens = |p: &Puzzle| p.iter().flatten().filter(|&&d| d != 0).count();
+                let removed = givens(&puzzle) - givens(&minimal);
+                eprintln!(
+                    "Removed {removed} {}, leaving {}",
+                    if removed == 1 { "given" } else { "givens" },
+                    givens(&minimal)
+                );
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Rate { infile } => {
+                let puzzle = read_puzzle(&infile).context("Invalid input")?;
+                let rating = rate(&puzzle);
+                println!("Difficulty: {}", rating.difficulty);
+                match rating.hardest {
+                    Some(t) => println!("Hardest technique: {t}"),
+                    None => println!("Hardest technique: none"),
+                }
+                println!("Steps: {}", rating.steps);
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Hint { from, infile } => {
+                let position = read_position(&infile, from).context("Invalid input")?;
+                if let Some(step) = next_step(&position) {
+                    println!("{step}");
+                    Ok(ExitCode::SUCCESS)
+                } else {
+                    eprintln!("No hint available");
+                    Ok(ExitCode::FAILURE)
+                }
+            }
+            Command::Explain { from, infile } => {
+                let position = read_position(&infile, from).context("Invalid input")?;
+                let explanation = explain_from(position);
+                for (i, step) in explanation.steps.iter().enumerate() {
+                    println!("{}. {step}", i + 1);
+                }
+                if explanation.solved {
+                    Ok(ExitCode::SUCCESS)
+                } else {
+                    eprintln!(
+                        "Stuck after {} steps: no further deductions can be made",
+                        explanation.steps.len()
+                    );
+                    Ok(ExitCode::FAILURE)
+                }
+            }
+            Command::Analyze { infile } => {
+                let puzzle = read_puzzle(&infile).context("Invalid input")?;
+                let Some(analysis) = analyze(&puzzle) else {
+                    if puzzle.count_solutions(1) == 0 {
+                        eprintln!("No solution");
+                    } else {
+                        eprintln!("Puzzle has multiple solutions");
+                    }
+                    return Ok(ExitCode::FAILURE);
+                };
+                print!("{}", show_analysis(&puzzle, &analysis));
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Convert { from, to, infile } => {
+                let from = from.unwrap_or_else(|| input_format(&infile));
+                let text = infile.read_to_string().context("Error reading input")?;
+                let file = from.parse(&text).context("Invalid input")?;
+                print!("{}", to.render(&file));
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Render {
+                style,
+                output,
+                solution,
+                infile,
+            } => {
+                let puzzle = read_puzzle(&infile).context("Invalid input")?;
+                let sol = if solution {
+                    let Some(s) = puzzle.solve() else {
+                        eprintln!("No solution");
+                        return Ok(ExitCode::FAILURE);
+                    };
+                    Some(s)
+                } else {
+                    None
+                };
+                if output == OutputFormat::Svg {
+                    let mut svg = Svg::new(&puzzle);
+                    if let Some(s) = sol.as_ref() {
+                        svg = svg.with_solution(s);
+                    }
+                    print!("{svg}");
+                } else {
+                    let mut term = Terminal::new(&puzzle).with_style(style).with_auto_color();
+                    if let Some(s) = sol.as_ref() {
+                        term = term.with_solution(s);
+                    }
+                    println!("{term}");
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Dedupe { infile } => {
+                let format = input_format(&infile);
+                let text = infile.read_to_string().context("Error reading input")?;
+                let puzzles = parse_batch(format, &text)?;
+                let total = puzzles.len();
+                let mut seen = HashSet::new();
+                let mut first = true;
+                for puzzle in puzzles {
+                    if seen.insert(puzzle.canonical_form()) {
+                        if !first {
+                            println!();
+                        }
+                        println!("{puzzle}");
+                        first = false;
+                    }
+                }
+                let removed = total - seen.len();
+                eprintln!(
+                    "Removed {removed} duplicate {}",
+                    if removed == 1 { "puzzle" } else { "puzzles" }
+                );
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Transform {
+                transform,
+                solution,
+                infile,
+            } => {
+                let puzzle = read_puzzle(&infile).context("Invalid input")?;
+                println!("{}", transform.apply_to_puzzle(&puzzle));
+                if solution {
+                    let Some(s) = puzzle.solve() else {
+                        eprintln!("No solution");
+                        return Ok(ExitCode::FAILURE);
+                    };
+                    println!();
+                    println!("{}", transform.apply_to_solution(&s));
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Play {
+                keys,
+                save,
+                resume,
+                infile,
+            } => {
+                if keys.is_none() && resume.as_ref().unwrap_or(&infile).is_stdin() {
+                    anyhow::bail!("--keys is required when reading from standard input");
+                }
+                let game = if let Some(resume) = resume {
+                    let text = resume
+                        .read_to_string()
+                        .context("Error reading saved game")?;
+                    Game::load(&text).context("Invalid saved game")?
+                } else {
+                    Game::new(read_puzzle(&infile).context("Invalid input")?)
+                };
+                let Some(mut app) = App::new(game) else {
+                    eprintln!("No solution");
+                    return Ok(ExitCode::FAILURE);
+                };
+                if let Some(keys) = keys {
+                    play::run_script(&mut app, &keys).map_err(anyhow::Error::msg)?;
+                    print!("{}", app.render(false));
+                } else {
+                    play::run_interactive(&mut app).context("Error running game")?;
+                }
+                if let Some(path) = save {
+                    fs::write(&path, app.game().save()).context("Error saving game")?;
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Command::Help(text) => {
+                print!("{text}");
                 Ok(ExitCode::SUCCESS)
             }
             Command::Version => {
@@ -71,6 +804,539 @@ impl Command {
     }
 }
 
+/// Parse the name of a puzzle file format as given on the command line
+fn parse_format(s: &str) -> Result<Format, String> {
+    if s == "plain" {
+        Ok(Format::Plain)
+    } else {
+        Format::from_extension(s).ok_or_else(|| format!("unknown puzzle format {s:?}"))
+    }
+}
+
+/// Parse the input format for `sudoku hint` or `sudoku explain` as given on
+/// the command line
+fn parse_position_format(s: &str) -> Result<PositionFormat, String> {
+    match s {
+        "marks" => Ok(PositionFormat::PencilMarks),
+        "hodoku" => Ok(PositionFormat::Hodoku),
+        _ => parse_format(s).map(PositionFormat::Puzzle),
+    }
+}
+
+/// Parse a permutation of the digits 1 through 9 as given on the command line
+fn parse_digits(s: &str) -> Result<[u8; 9], String> {
+    let digits = s
+        .bytes()
+        .map(|b| b.is_ascii_digit().then(|| b - b'0'))
+        .collect::<Option<Vec<_>>>()
+        .and_then(|v| <[u8; 9]>::try_from(v).ok());
+    digits.ok_or_else(|| String::from("expected a permutation of nine digits"))
+}
+
+/// Parse a pair of comma-separated one-based indices no greater than `max` as
+/// given on the command line, returning them as zero-based indices
+fn parse_pair(s: &str, max: usize) -> Result<(usize, usize), String> {
+    let parse_index = |t: &str| {
+        t.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|i| (1..=max).contains(i))
+            .map(|i| i - 1)
+    };
+    s.split_once(',')
+        .and_then(|(a, b)| Some((parse_index(a)?, parse_index(b)?)))
+        .ok_or_else(|| format!("expected two numbers from 1 to {max} separated by a comma"))
+}
+
+/// Returns the format of the given input file as determined by its file
+/// extension, defaulting to [`Format::Plain`]
+fn input_format(infile: &InputArg) -> Format {
+    infile
+        .path_ref()
+        .and_then(|p| p.extension())
+        .and_then(|ext| ext.to_str())
+        .and_then(Format::from_extension)
+        .unwrap_or(Format::Plain)
+}
+
+/// Read & parse a puzzle or grid from `infile` in the format determined by its
+/// file extension
+fn read_puzzle(infile: &InputArg) -> anyhow::Result<Puzzle> {
+    let text = infile.read_to_string().context("Error reading input")?;
+    Ok(input_format(infile).parse(&text)?.puzzle)
+}
+
+/// Read & parse a position from `infile` in the format given by `from`, or
+/// else as a puzzle in the format determined by its file extension
+fn read_position(infile: &InputArg, from: Option<PositionFormat>) -> anyhow::Result<Position> {
+    let text = infile.read_to_string().context("Error reading input")?;
+    match from.unwrap_or_else(|| PositionFormat::Puzzle(input_format(infile))) {
+        PositionFormat::Puzzle(format) => Ok(Position::new(format.parse(&text)?.puzzle)),
+        PositionFormat::PencilMarks => Ok(Position::from_pencil_marks(&text)?),
+        PositionFormat::Hodoku => Ok(Position::from_hodoku(&text)?),
+    }
+}
+
+/// Print `solution` to standard output as text.  If a player's attempt was
+/// supplied, the attempt is printed instead with its wrong entries flagged,
+/// and the number of wrong entries is reported on standard error.
+fn show_text(puzzle: &Puzzle, solution: &Solution, opts: TextOptions) -> anyhow::Result<ExitCode> {
+    let term = Terminal::new(puzzle)
+        .with_solution(solution)
+        .with_style(opts.style)
+        .with_brackets(opts.brackets)
+        .with_auto_color();
+    let Some(diff) = opts.diff else {
+        println!("{term}");
+        return Ok(ExitCode::SUCCESS);
+    };
+    let attempt = read_puzzle(&diff).context("Invalid attempt")?;
+    let term = term.with_attempt(&attempt);
+    println!("{term}");
+    let wrong = term.wrong_cells().len();
+    if wrong == 0 {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        eprintln!(
+            "{wrong} wrong {}",
+            if wrong == 1 { "entry" } else { "entries" }
+        );
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Draw the grid of `puzzle` with the results of analyzing it overlaid:
+/// critical givens are surrounded by brackets, and backdoors are filled in
+/// with their digits from the solution and surrounded by parentheses.  The
+/// coordinates of the critical givens & backdoors are then listed below the
+/// grid.
+fn show_analysis(puzzle: &Puzzle, analysis: &Analysis) -> String {
+    const DIVIDER: &str = "+---------+---------+---------+";
+    let mut s = String::new();
+    for y in 0..9 {
+        if y % 3 == 0 {
+            s.push_str(DIVIDER);
+            s.push('\n');
+        }
+        for x in 0..9 {
+            if x % 3 == 0 {
+                s.push('|');
+            }
+            let cell = match puzzle[y][x] {
+                0 if analysis.backdoors.contains(&(y, x)) => {
+                    format!("({})", analysis.solution[y][x])
+                }
+                0 => String::from(" . "),
+                d if analysis.critical_givens.contains(&(y, x)) => format!("[{d}]"),
+                d => format!(" {d} "),
+            };
+            s.push_str(&cell);
+        }
+        s.push_str("|\n");
+    }
+    s.push_str(DIVIDER);
+    s.push('\n');
+    let cells = |cells: &[(usize, usize)]| {
+        if cells.is_empty() {
+            String::from("none")
+        } else {
+            cells
+                .iter()
+                .map(|&(y, x)| format!("r{}c{}", y + 1, x + 1))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    let _ = writeln!(
+        s,
+        "Critical givens, shown as [d]: {}",
+        cells(&analysis.critical_givens)
+    );
+    if analysis.singles {
+        s.push_str("Backdoors: none needed; the puzzle can be solved with singles alone\n");
+    } else {
+        let _ = writeln!(s, "Backdoors, shown as (d): {}", cells(&analysis.backdoors));
+    }
+    s
+}
+
+/// Parse a batch of puzzles.  Plain-format input may contain any number of
+/// puzzles, each consisting of nine non-blank lines; input in other formats
+/// contains a single puzzle.
+fn parse_batch(format: Format, text: &str) -> anyhow::Result<Vec<Puzzle>> {
+    if format == Format::Plain {
+        let lines = text
+            .lines()
+            .filter(|ln| !ln.trim().is_empty())
+            .collect::<Vec<_>>();
+        lines
+            .chunks(9)
+            .map(|chunk| chunk.join("\n").parse::<Puzzle>())
+            .collect::<Result<Vec<_>, _>>()
+            .context("Invalid input")
+    } else {
+        Ok(vec![format.parse(text).context("Invalid input")?.puzzle])
+    }
+}
+
+/// Solve every puzzle in a batch and write a PDF booklet of them to standard
+/// output
+fn make_booklet(format: Format, text: &str, opts: BookletOptions) -> anyhow::Result<ExitCode> {
+    let puzzles = parse_batch(format, text)?;
+    if puzzles.is_empty() {
+        anyhow::bail!("No puzzles in input");
+    }
+    let mut booklet = Booklet::new(opts.page_size).with_per_page(opts.per_page);
+    if let Some(label) = opts.difficulty {
+        booklet = booklet.with_difficulty(&label);
+    }
+    for (i, puzzle) in puzzles.into_iter().enumerate() {
+        let Some(solution) = puzzle.solve() else {
+            eprintln!("No solution for puzzle {}", i + 1);
+            return Ok(ExitCode::FAILURE);
+        };
+        booklet.push(puzzle, solution);
+    }
+    io::stdout()
+        .lock()
+        .write_all(&booklet.render())
+        .context("Error writing output")?;
+    Ok(ExitCode::SUCCESS)
+}
+
 fn main() -> anyhow::Result<ExitCode> {
     Command::from_parser(Parser::from_env())?.run()
 }
+
+static MAIN_HELP: &str = "\
+Usage: sudoku [<options>] [<infile>]
+       sudoku <command> [<options>] [<args>]
+
+Solve a Sudoku puzzle.  Run without a command, `sudoku` behaves the same as
+`sudoku solve`.
+
+Files with a .sdk or .ss extension are read as SadMan Software or Simple
+Sudoku files, respectively.
+
+Commands:
+  solve       Solve a puzzle (the default)
+  check       Check a player's answer to a puzzle
+  count       Count the solutions of a puzzle
+  generate    Generate new puzzles
+  minimize    Remove redundant givens from a puzzle
+  rate        Rate the difficulty of a puzzle
+  hint        Show the next logical deduction for a puzzle
+  explain     Solve a puzzle step by step, explaining each deduction
+  analyze     Find a puzzle's backdoors and critical givens
+  convert     Convert a puzzle between file formats
+  render      Draw an unsolved puzzle as text or SVG
+  dedupe      Remove equivalent puzzles from a batch
+  transform   Rearrange a puzzle into an equivalent one
+  play        Play a puzzle interactively in the terminal
+
+Run `sudoku <command> --help` for the options of each command.
+
+Options:
+  -h, --help        Display this help message and exit
+  -V, --version     Show the program version and exit
+";
+
+static SOLVE_HELP: &str = "\
+Usage: sudoku solve [<options>] [<infile>]
+
+Solve a Sudoku puzzle and output the solution
+
+Options:
+  -f, --format <text|svg|pdf>
+                    Output the solution as text (the default) or as SVG,
+                    or output a PDF booklet of all puzzles in the input
+                    with an answer key
+
+      --page-size <a4|letter>
+                    Set the page size of a PDF booklet [default: letter]
+
+      --per-page <N>
+                    Set the number of puzzles per page of a PDF booklet
+                    (1-9) [default: 4]
+
+      --difficulty <LABEL>
+                    Print the given difficulty label on each puzzle in a
+                    PDF booklet
+
+  -b, --brackets    Surround digits filled in by the solver with brackets
+
+  -d, --diff <FILE>
+                    Compare the player's attempt in FILE against the
+                    solution, showing the player's entries instead of the
+                    solution and flagging wrong entries.  Exits nonzero if
+                    any entries are wrong.
+
+  -P, --pretty      Same as `--style ascii`
+
+  -s, --style <plain|ascii|unicode>
+                    Output the solution as plain digits (the default),
+                    or with borders drawn with ASCII or Unicode
+                    box-drawing characters.  When standard output is a
+                    terminal, bordered output uses colors to distinguish
+                    the givens from the filled-in digits.
+
+  -h, --help        Display this help message and exit
+  -V, --version     Show the program version and exit
+";
+
+static CHECK_HELP: &str = "\
+Usage: sudoku check <puzzle> <answer>
+
+Verify that <answer> is a correct solution to <puzzle>, listing every problem
+found and exiting nonzero if there are any
+
+Options:
+  -h, --help        Display this help message and exit
+";
+
+static COUNT_HELP: &str = "\
+Usage: sudoku count [<options>] [<infile>]
+
+Count the solutions of a puzzle, stopping once the limit is reached
+
+Options:
+  -l, --limit <N>   Stop counting after N solutions [default: 1000]
+
+  -h, --help        Display this help message and exit
+";
+
+static GENERATE_HELP: &str = "\
+Usage: sudoku generate [<options>]
+
+Generate puzzles with unique solutions, separated by blank lines
+
+Options:
+  -D, --difficulty <easy|medium|hard|expert|extreme>
+                    Only output puzzles of the given difficulty, as
+                    determined by `sudoku rate`
+
+  -n, --number <N>  Generate N puzzles [default: 1]
+
+      --seed <SEED>
+                    Seed the random number generator with the given
+                    integer so that the same puzzles are generated each
+                    time.  By default, a random seed is used.
+
+  -S, --symmetric   Place the givens with 180° rotational symmetry
+
+  -h, --help        Display this help message and exit
+";
+
+static MINIMIZE_HELP: &str = "\
+Usage: sudoku minimize [<options>] [<infile>]
+
+Remove givens from a puzzle with a unique solution, in a random order, for as
+long as the solution stays unique, and output the resulting minimal puzzle.
+The number of givens removed is reported on standard error.
+
+Options:
+      --seed <SEED>
+                    Use the given integer as the seed for the random removal
+                    order.  By default, a random seed is used.
+
+  -S, --symmetric   Remove givens in pairs that are symmetric under 180°
+                    rotation
+
+  -h, --help        Display this help message and exit
+";
+
+static RATE_HELP: &str = "\
+Usage: sudoku rate [<infile>]
+
+Rate the difficulty of a puzzle by the hardest solving technique needed to
+solve it
+
+Options:
+  -h, --help        Display this help message and exit
+";
+
+static HINT_HELP: &str = "\
+Usage: sudoku hint [<options>] [<infile>]
+
+Show the easiest logical deduction that can be made about a puzzle, or fail
+if none can be made
+
+Options:
+      --from <plain|sdk|ss|marks|hodoku>
+                    Read the input in the given format [default: determined
+                    by the file extension, or plain].  With `marks` (a
+                    pencil-mark grid as exported by HoDoKu or SudokuWiki) or
+                    `hodoku` (a HoDoKu savepoint line), deductions start from
+                    the candidates in the input instead of the bare givens.
+
+  -h, --help        Display this help message and exit
+";
+
+static EXPLAIN_HELP: &str = "\
+Usage: sudoku explain [<options>] [<infile>]
+
+Solve a puzzle using logical deductions, describing each one in turn.  Exits
+nonzero if the puzzle cannot be solved with the supported techniques.
+
+Options:
+      --from <plain|sdk|ss|marks|hodoku>
+                    Read the input in the given format [default: determined
+                    by the file extension, or plain].  With `marks` (a
+                    pencil-mark grid as exported by HoDoKu or SudokuWiki) or
+                    `hodoku` (a HoDoKu savepoint line), deductions start from
+                    the candidates in the input instead of the bare givens.
+
+  -h, --help        Display this help message and exit
+";
+
+static ANALYZE_HELP: &str = "\
+Usage: sudoku analyze [<infile>]
+
+Analyze a puzzle with a unique solution and draw its grid marked up with:
+
+- its critical givens, the givens whose removal would give the puzzle
+  multiple solutions, shown as [d]
+
+- its backdoors, the blank cells that, when filled in from the solution, make
+  the puzzle solvable using only naked & hidden singles, shown as (d) with
+  their digits from the solution
+
+The coordinates of the critical givens and backdoors are listed below the
+grid.
+
+Options:
+  -h, --help        Display this help message and exit
+";
+
+static CONVERT_HELP: &str = "\
+Usage: sudoku convert [<options>] [<infile>]
+
+Convert a puzzle from one file format to another
+
+Options:
+      --from <plain|sdk|ss>
+                    Read the input in the given format [default: determined
+                    by the file extension, or plain]
+
+  -t, --to <plain|sdk|ss>
+                    Write the output in the given format [default: plain]
+
+  -h, --help        Display this help message and exit
+";
+
+static RENDER_HELP: &str = "\
+Usage: sudoku render [<options>] [<infile>]
+
+Draw a puzzle's grid without solving it
+
+Options:
+  -f, --format <text|svg>
+                    Output the puzzle as text (the default) or as SVG
+
+      --solution    Also fill in the puzzle's solution
+
+  -s, --style <plain|ascii|unicode>
+                    Draw the puzzle as plain digits, or with borders drawn
+                    with ASCII (the default) or Unicode box-drawing
+                    characters
+
+  -h, --help        Display this help message and exit
+";
+
+static DEDUPE_HELP: &str = "\
+Usage: sudoku dedupe [<infile>]
+
+Read a batch of puzzles, each consisting of nine non-blank lines, and output
+them separated by blank lines, omitting every puzzle that is equivalent to an
+earlier one.  Puzzles are equivalent if one can be turned into the other by
+relabeling the digits, transposing the grid, swapping bands or stacks, or
+swapping rows within a band or columns within a stack.  Even/odd shading
+moves with its cell, and puzzles with shading may only be relabeled in ways
+that map even digits to even digits.
+
+Options:
+  -h, --help        Display this help message and exit
+";
+
+static TRANSFORM_HELP: &str = "\
+Usage: sudoku transform [<options>] [<infile>]
+
+Rearrange a puzzle into an equivalent puzzle that has the same difficulty.
+The transformations given by the options are applied in the order in which
+they appear on the command line.
+
+Options:
+      --permute-digits <DIGITS>
+                    Relabel the digits, changing each digit d to the d-th
+                    digit of DIGITS, which must contain each of 1 through 9
+                    exactly once
+
+      --random <SEED>
+                    Apply a random combination of the transformations below,
+                    determined by the given integer SEED
+
+      --reflect <horizontal|vertical>
+                    Reflect the grid left-to-right or top-to-bottom
+
+      --rotate <N>  Rotate the grid clockwise by N quarter turns
+
+      --solution    Also output the transformed puzzle's solution, after a
+                    blank line
+
+      --swap-bands <A,B>
+                    Swap bands (groups of three rows) A and B, numbered from 1
+                    to 3
+
+      --swap-columns <A,B>
+                    Swap columns A and B, numbered from 1 to 9, which must be
+                    in the same stack
+
+      --swap-rows <A,B>
+                    Swap rows A and B, numbered from 1 to 9, which must be in
+                    the same band
+
+      --swap-stacks <A,B>
+                    Swap stacks (groups of three columns) A and B, numbered
+                    from 1 to 3
+
+      --transpose   Swap the rows of the grid with its columns
+
+  -h, --help        Display this help message and exit
+";
+
+static PLAY_HELP: &str = "\
+Usage: sudoku play [<options>] [<infile>]
+
+Play a puzzle in a full-screen terminal UI.  When reading the puzzle or saved
+game from standard input, the game cannot be played interactively, so --keys
+must be given.
+
+Keys:
+  Arrows, h j k l   Move the cursor
+  1-9               Enter a digit, or toggle a pencil mark in pencil mode
+  0, x, ., Backspace, Delete
+                    Erase the digit in the cell, or its pencil marks if it
+                    has no digit
+  p                 Switch between digit mode and pencil mode
+  u, Ctrl-Z         Undo
+  r, Ctrl-Y         Redo
+  ?                 Show a hint
+  c                 Check the grid against the solution
+  q, Esc            Quit
+
+Options:
+      --keys <KEYS>
+                    Instead of playing interactively, press each of the
+                    keys in KEYS in turn (using the letter, digit, and
+                    symbol bindings above; whitespace is ignored) and then
+                    print the final state of the game
+
+  -r, --resume <FILE>
+                    Resume the game saved in FILE instead of starting a new
+                    game of the puzzle in <infile>
+
+      --save <FILE>
+                    Save the game to FILE when quitting
+
+  -h, --help        Display this help message and exit
+";
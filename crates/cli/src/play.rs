@@ -0,0 +1,393 @@
+//! The interactive `sudoku play` mode
+//!
+//! The game itself is a [`Game`], which an [`App`] pairs with the state of
+//! the UI.  The `App` is updated one [`Key`] at a time and drawn as plain
+//! text, so that games can be played headlessly by feeding in a script of
+//! keys as well as interactively in a full-screen terminal UI.
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, terminal};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use sudoku::game::{Game, MoveError};
+use sudoku::logic::next_step;
+use sudoku::pencilmarks::Position;
+use sudoku::{Parity, Puzzle, Solution};
+
+/// ANSI escape sequence for displaying givens
+const GIVEN_COLOR: &str = "\x1B[1m";
+
+/// ANSI escape sequence for displaying the player's entries
+const ENTRY_COLOR: &str = "\x1B[36m";
+
+/// ANSI escape sequence for displaying conflicting digits
+const CONFLICT_COLOR: &str = "\x1B[1;31m";
+
+/// ANSI escape sequence for highlighting the cell under the cursor
+const CURSOR_COLOR: &str = "\x1B[7m";
+
+/// ANSI escape sequence for resetting the display attributes
+const RESET: &str = "\x1B[0m";
+
+const DIVIDER: &str = "+---------+---------+---------+";
+
+const KEY_HELP: &str =
+    "Keys: arrows/hjkl move, 1-9 enter, 0/x/. erase, p pencil, u undo, r redo, ? hint, c check, q quit";
+
+/// An action taken by the player
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Digit(u8),
+    Erase,
+    TogglePencil,
+    Undo,
+    Redo,
+    Hint,
+    Check,
+    Quit,
+}
+
+impl Key {
+    /// Returns the action bound to the given character, if any.  These
+    /// bindings are used both for keypresses and for scripts.
+    pub(crate) fn from_char(c: char) -> Option<Key> {
+        match c {
+            'k' => Some(Key::Up),
+            'j' => Some(Key::Down),
+            'h' => Some(Key::Left),
+            'l' => Some(Key::Right),
+            '1'..='9' => c
+                .to_digit(10)
+                .and_then(|d| u8::try_from(d).ok())
+                .map(Key::Digit),
+            '0' | 'x' | '.' => Some(Key::Erase),
+            'p' => Some(Key::TogglePencil),
+            'u' => Some(Key::Undo),
+            'r' => Some(Key::Redo),
+            '?' => Some(Key::Hint),
+            'c' => Some(Key::Check),
+            'q' => Some(Key::Quit),
+            _ => None,
+        }
+    }
+
+    fn from_event(ev: KeyEvent) -> Option<Key> {
+        if ev.modifiers.contains(KeyModifiers::CONTROL) {
+            return match ev.code {
+                KeyCode::Char('c') => Some(Key::Quit),
+                KeyCode::Char('z') => Some(Key::Undo),
+                KeyCode::Char('y') => Some(Key::Redo),
+                _ => None,
+            };
+        }
+        match ev.code {
+            KeyCode::Up => Some(Key::Up),
+            KeyCode::Down => Some(Key::Down),
+            KeyCode::Left => Some(Key::Left),
+            KeyCode::Right => Some(Key::Right),
+            KeyCode::Backspace | KeyCode::Delete => Some(Key::Erase),
+            KeyCode::Esc => Some(Key::Quit),
+            KeyCode::Char(c) => Key::from_char(c),
+            _ => None,
+        }
+    }
+}
+
+/// The state of the UI for a game in progress
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct App {
+    game: Game,
+    solution: Solution,
+    cursor: (usize, usize),
+    pencil: bool,
+    message: String,
+}
+
+impl App {
+    /// Start playing `game`.  Returns `None` if the game's puzzle has no
+    /// solution.
+    pub(crate) fn new(game: Game) -> Option<App> {
+        let solution = game.puzzle().solve()?;
+        Some(App {
+            game,
+            solution,
+            cursor: (0, 0),
+            pencil: false,
+            message: String::new(),
+        })
+    }
+
+    /// Return the game being played
+    pub(crate) fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Perform the action for `key`.  Returns `false` if the player quit.
+    pub(crate) fn handle(&mut self, key: Key) -> bool {
+        self.message.clear();
+        let (y, x) = self.cursor;
+        match key {
+            Key::Up => self.cursor = ((y + 8) % 9, x),
+            Key::Down => self.cursor = ((y + 1) % 9, x),
+            Key::Left => self.cursor = (y, (x + 8) % 9),
+            Key::Right => self.cursor = (y, (x + 1) % 9),
+            Key::Digit(d) if self.pencil => self.edit(|g| g.toggle_pencil_mark(y, x, d)),
+            Key::Digit(d) => self.edit(|g| g.enter(y, x, d)),
+            // Erase the cell's digit, or its pencil marks if it has no digit:
+            Key::Erase if self.game.cell(y, x).entry().is_some() => {
+                self.edit(|g| g.erase(y, x));
+            }
+            Key::Erase => self.edit(|g| g.clear_pencil_marks(y, x)),
+            Key::TogglePencil => self.pencil = !self.pencil,
+            Key::Undo => self.undo(),
+            Key::Redo => self.redo(),
+            Key::Hint => self.hint(),
+            Key::Check => self.check(),
+            Key::Quit => return false,
+        }
+        true
+    }
+
+    /// Make a move in the game, showing the error if it is not allowed
+    fn edit<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Game) -> Result<(), MoveError>,
+    {
+        if let Err(e) = f(&mut self.game) {
+            self.message = e.to_string();
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(mv) = self.game.undo() {
+            self.cursor = mv.cell;
+        } else {
+            self.message = String::from("Nothing to undo");
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(mv) = self.game.redo() {
+            self.cursor = mv.cell;
+        } else {
+            self.message = String::from("Nothing to redo");
+        }
+    }
+
+    /// Returns the cells whose entries disagree with the solution, in
+    /// row-major order
+    fn wrong_cells(&self) -> Vec<(usize, usize)> {
+        let mut wrong = Vec::new();
+        for y in 0..9 {
+            for x in 0..9 {
+                if self
+                    .game
+                    .cell(y, x)
+                    .entry()
+                    .is_some_and(|d| d != self.solution[y][x])
+                {
+                    wrong.push((y, x));
+                }
+            }
+        }
+        wrong
+    }
+
+    /// Describe the next logical deduction that can be made from the current
+    /// grid.  If the player has made a mistake, it is pointed out instead.
+    fn hint(&mut self) {
+        if let Some(&(y, x)) = self.wrong_cells().first() {
+            self.message = format!("r{}c{} is incorrect", y + 1, x + 1);
+            return;
+        }
+        let puzzle = self.game.puzzle();
+        let mut parities = [[None; 9]; 9];
+        for (y, row) in parities.iter_mut().enumerate() {
+            for (x, p) in row.iter_mut().enumerate() {
+                *p = puzzle.parity(y, x);
+            }
+        }
+        let grid: [[u8; 9]; 9] = self.game.grid().into();
+        self.message = Puzzle::try_from(grid)
+            .ok()
+            .and_then(|p| next_step(&Position::new(p.with_parities(parities))))
+            .map_or_else(
+                || String::from("No hint available"),
+                |step| step.to_string(),
+            );
+    }
+
+    /// Check the player's entries against the puzzle's solution
+    fn check(&mut self) {
+        let wrong = self.wrong_cells().len();
+        let remaining = self.game.remaining();
+        self.message = if wrong > 0 {
+            format!(
+                "{wrong} wrong {}",
+                if wrong == 1 { "entry" } else { "entries" }
+            )
+        } else if remaining > 0 {
+            format!(
+                "No mistakes so far; {remaining} {} left to fill",
+                if remaining == 1 { "cell" } else { "cells" }
+            )
+        } else {
+            String::from("Solved!")
+        };
+    }
+
+    /// Draw the game as text, using ANSI escape sequences if `color` is true.
+    ///
+    /// The cell under the cursor is surrounded by brackets, and digits that
+    /// conflict with another digit in the same row, column, or box are
+    /// surrounded by exclamation points.
+    pub(crate) fn render(&self, color: bool) -> String {
+        let puzzle = self.game.puzzle();
+        let conflicts = self.game.conflicts();
+        let mut s = String::new();
+        for y in 0..9 {
+            if y % 3 == 0 {
+                s.push_str(DIVIDER);
+                s.push('\n');
+            }
+            for x in 0..9 {
+                if x % 3 == 0 {
+                    s.push('|');
+                }
+                let c = match self.game.value(y, x) {
+                    0 => puzzle.parity(y, x).map_or('.', Parity::to_char),
+                    d => char::from(b'0' + d),
+                };
+                let conflict = conflicts.contains(&(y, x));
+                let is_cursor = self.cursor == (y, x);
+                let (open, close) = if is_cursor {
+                    ('[', ']')
+                } else if conflict {
+                    ('!', '!')
+                } else {
+                    (' ', ' ')
+                };
+                if color {
+                    let code = if conflict {
+                        CONFLICT_COLOR
+                    } else if puzzle[y][x] != 0 {
+                        GIVEN_COLOR
+                    } else {
+                        ENTRY_COLOR
+                    };
+                    let highlight = if is_cursor { CURSOR_COLOR } else { "" };
+                    let _ = write!(s, "{open}{highlight}{code}{c}{RESET}{close}");
+                } else {
+                    let _ = write!(s, "{open}{c}{close}");
+                }
+            }
+            s.push_str("|\n");
+        }
+        s.push_str(DIVIDER);
+        s.push('\n');
+        let (y, x) = self.cursor;
+        let _ = writeln!(
+            s,
+            "r{}c{}, {} mode",
+            y + 1,
+            x + 1,
+            if self.pencil { "pencil" } else { "digit" }
+        );
+        let marks = self
+            .game
+            .cell(y, x)
+            .pencil_marks()
+            .into_iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        if marks.is_empty() {
+            s.push_str("Pencil marks: none\n");
+        } else {
+            let _ = writeln!(s, "Pencil marks: {}", marks.join(" "));
+        }
+        s.push_str(&self.message);
+        s.push('\n');
+        s.push_str(KEY_HELP);
+        s.push('\n');
+        s
+    }
+}
+
+/// Feed the keys in `script` to `app`, stopping early if a quit key is
+/// encountered.  Whitespace in the script is ignored.
+///
+/// # Errors
+///
+/// Fails if the script contains a character that is not bound to any key.
+pub(crate) fn run_script(app: &mut App, script: &str) -> Result<(), String> {
+    for c in script.chars().filter(|c| !c.is_whitespace()) {
+        let key = Key::from_char(c).ok_or_else(|| format!("unknown key {c:?} in script"))?;
+        if !app.handle(key) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Play the game interactively in a full-screen terminal UI until the player
+/// quits
+///
+/// # Errors
+///
+/// Fails if an I/O error occurs while reading from or writing to the
+/// terminal.
+pub(crate) fn run_interactive(app: &mut App) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let _screen = Screen::enter(&mut stdout)?;
+    event_loop(app, &mut stdout)
+}
+
+/// Guard that keeps the terminal in raw mode on the alternate screen and
+/// restores it when dropped, including when unwinding from a panic
+struct Screen;
+
+impl Screen {
+    fn enter(stdout: &mut io::Stdout) -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        // Construct the guard first so that raw mode is disabled again if
+        // switching screens fails:
+        let screen = Screen;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        // There is nowhere left to report errors at this point, so restore
+        // as much of the terminal as possible.
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn event_loop(app: &mut App, stdout: &mut io::Stdout) -> io::Result<()> {
+    let color = sudoku::terminal::stdout_supports_color();
+    loop {
+        execute!(
+            stdout,
+            cursor::MoveTo(0, 0),
+            terminal::Clear(terminal::ClearType::All)
+        )?;
+        // Raw mode does not translate newlines into carriage return + newline:
+        write!(stdout, "{}", app.render(color).replace('\n', "\r\n"))?;
+        stdout.flush()?;
+        if let Event::Key(ev) = event::read()? {
+            if ev.kind != KeyEventKind::Release {
+                if let Some(key) = Key::from_event(ev) {
+                    if !app.handle(key) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
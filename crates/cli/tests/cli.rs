@@ -1,6 +1,8 @@
 use assert_cmd::Command;
 use std::fs;
-use tempfile::NamedTempFile;
+use sudoku::pencilmarks::Position;
+use sudoku::Puzzle;
+use tempfile::{Builder, NamedTempFile};
 
 static PUZZLE: &str = concat!(
     ". . 3 . 2 . 6 . .\n",
@@ -56,6 +58,19 @@ static UNSOLVABLE: &str = concat!(
     "007000000\n",
 );
 
+/// `PUZZLE` as a HoDoKu savepoint line with 5 eliminated from r1c1
+static HODOKU: &str =
+    ":0000:x:..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..:511::\n";
+
+/// Return a command for running `sudoku` with backtraces disabled, so that
+/// error messages can be compared exactly
+fn sudoku() -> Command {
+    let mut cmd = Command::cargo_bin("sudoku").expect("the sudoku binary should be built");
+    cmd.env_remove("RUST_BACKTRACE")
+        .env_remove("RUST_LIB_BACKTRACE");
+    cmd
+}
+
 #[test]
 fn test_stdin() {
     Command::cargo_bin("sudoku")
@@ -79,8 +94,7 @@ fn test_stdin_pretty() {
 
 #[test]
 fn test_unsolvable() {
-    Command::cargo_bin("sudoku")
-        .unwrap()
+    sudoku()
         .write_stdin(UNSOLVABLE)
         .assert()
         .failure()
@@ -90,8 +104,7 @@ fn test_unsolvable() {
 
 #[test]
 fn test_unsolvable_pretty() {
-    Command::cargo_bin("sudoku")
-        .unwrap()
+    sudoku()
         .arg("--pretty")
         .write_stdin(UNSOLVABLE)
         .assert()
@@ -124,3 +137,913 @@ fn test_infile_pretty() {
         .success()
         .stdout(PRETTY_SOLUTION);
 }
+
+#[test]
+fn test_style_ascii() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("--style")
+        .arg("ascii")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(PRETTY_SOLUTION);
+}
+
+#[test]
+fn test_style_unicode() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("--style=unicode")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "┏━━━┯━━━┯━━━┳━━━┯━━━┯━━━┳━━━┯━━━┯━━━┓\n",
+            "┃ 4 │ 8 │ 3 ┃ 9 │ 2 │ 1 ┃ 6 │ 5 │ 7 ┃\n",
+            "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+            "┃ 9 │ 6 │ 7 ┃ 3 │ 4 │ 5 ┃ 8 │ 2 │ 1 ┃\n",
+            "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+            "┃ 2 │ 5 │ 1 ┃ 8 │ 7 │ 6 ┃ 4 │ 9 │ 3 ┃\n",
+            "┣━━━┿━━━┿━━━╋━━━┿━━━┿━━━╋━━━┿━━━┿━━━┫\n",
+            "┃ 5 │ 4 │ 8 ┃ 1 │ 3 │ 2 ┃ 9 │ 7 │ 6 ┃\n",
+            "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+            "┃ 7 │ 2 │ 9 ┃ 5 │ 6 │ 4 ┃ 1 │ 3 │ 8 ┃\n",
+            "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+            "┃ 1 │ 3 │ 6 ┃ 7 │ 9 │ 8 ┃ 2 │ 4 │ 5 ┃\n",
+            "┣━━━┿━━━┿━━━╋━━━┿━━━┿━━━╋━━━┿━━━┿━━━┫\n",
+            "┃ 3 │ 7 │ 2 ┃ 6 │ 8 │ 9 ┃ 5 │ 1 │ 4 ┃\n",
+            "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+            "┃ 8 │ 1 │ 4 ┃ 2 │ 5 │ 3 ┃ 7 │ 6 │ 9 ┃\n",
+            "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨\n",
+            "┃ 6 │ 9 │ 5 ┃ 4 │ 1 │ 7 ┃ 3 │ 8 │ 2 ┃\n",
+            "┗━━━┷━━━┷━━━┻━━━┷━━━┷━━━┻━━━┷━━━┷━━━┛\n",
+        ));
+}
+
+#[test]
+fn test_style_unknown() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("--style=fancy")
+        .write_stdin(PUZZLE)
+        .assert()
+        .failure()
+        .stdout("");
+}
+
+#[test]
+fn test_brackets() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("--brackets")
+        .arg("-P")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "+---------+---------+---------+\n",
+            "|[4][8] 3 |[9] 2 [1]| 6 [5][7]|\n",
+            "| 9 [6][7]| 3 [4] 5 |[8][2] 1 |\n",
+            "|[2][5] 1 | 8 [7] 6 | 4 [9][3]|\n",
+            "+---------+---------+---------+\n",
+            "|[5][4] 8 | 1 [3] 2 | 9 [7][6]|\n",
+            "| 7 [2][9]|[5][6][4]|[1][3] 8 |\n",
+            "|[1][3] 6 | 7 [9] 8 | 2 [4][5]|\n",
+            "+---------+---------+---------+\n",
+            "|[3][7] 2 | 6 [8] 9 | 5 [1][4]|\n",
+            "| 8 [1][4]| 2 [5] 3 |[7][6] 9 |\n",
+            "|[6][9] 5 |[4] 1 [7]| 3 [8][2]|\n",
+            "+---------+---------+---------+\n",
+        ));
+}
+
+#[test]
+fn test_diff() {
+    let attempt = NamedTempFile::new().unwrap();
+    fs::write(
+        &attempt,
+        concat!(
+            "483521657\n",
+            "967345821\n",
+            "251876493\n",
+            "548132976\n",
+            "729564138\n",
+            "136798245\n",
+            "372689514\n",
+            "814253769\n",
+            "69541738.\n",
+        ),
+    )
+    .unwrap();
+    sudoku()
+        .arg("--diff")
+        .arg(attempt.path())
+        .arg("-b")
+        .write_stdin(PUZZLE)
+        .assert()
+        .failure()
+        .stdout(concat!(
+            "[4][8] 3 !5! 2 [1] 6 [5][7]\n",
+            " 9 [6][7] 3 [4] 5 [8][2] 1 \n",
+            "[2][5] 1  8 [7] 6  4 [9][3]\n",
+            "[5][4] 8  1 [3] 2  9 [7][6]\n",
+            " 7 [2][9][5][6][4][1][3] 8 \n",
+            "[1][3] 6  7 [9] 8  2 [4][5]\n",
+            "[3][7] 2  6 [8] 9  5 [1][4]\n",
+            " 8 [1][4] 2 [5] 3 [7][6] 9 \n",
+            "[6][9] 5 [4] 1 [7] 3 [8] 0 \n",
+        ))
+        .stderr("1 wrong entry\n");
+}
+
+#[test]
+fn test_diff_correct() {
+    let attempt = NamedTempFile::new().unwrap();
+    fs::write(&attempt, SOLUTION).unwrap();
+    sudoku()
+        .arg("--diff")
+        .arg(attempt.path())
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(SOLUTION)
+        .stderr("");
+}
+
+#[test]
+fn test_infile_sdk() {
+    let tmpfile = Builder::new().suffix(".sdk").tempfile().unwrap();
+    fs::write(
+        &tmpfile,
+        concat!(
+            "#AJohn Doe\n",
+            "#LEasy\n",
+            "..3.2.6..\n",
+            "9..3.5..1\n",
+            "..18.64..\n",
+            "..81.29..\n",
+            "7.......8\n",
+            "..67.82..\n",
+            "..26.95..\n",
+            "8..2.3..9\n",
+            "..5.1.3..\n",
+        ),
+    )
+    .unwrap();
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg(tmpfile.path())
+        .assert()
+        .success()
+        .stdout(SOLUTION);
+}
+
+#[test]
+fn test_infile_ss() {
+    let tmpfile = Builder::new().suffix(".ss").tempfile().unwrap();
+    fs::write(
+        &tmpfile,
+        concat!(
+            "..3|.2.|6..\n",
+            "9..|3.5|..1\n",
+            "..1|8.6|4..\n",
+            "-----------\n",
+            "..8|1.2|9..\n",
+            "7..|...|..8\n",
+            "..6|7.8|2..\n",
+            "-----------\n",
+            "..2|6.9|5..\n",
+            "8..|2.3|..9\n",
+            "..5|.1.|3..\n",
+        ),
+    )
+    .unwrap();
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg(tmpfile.path())
+        .assert()
+        .success()
+        .stdout(SOLUTION);
+}
+
+#[test]
+fn test_format_svg() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["--format", "svg"])
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(include_str!("data/solution.svg"));
+}
+
+#[test]
+fn test_format_pdf() {
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .args([
+            "--format",
+            "pdf",
+            "--page-size",
+            "a4",
+            "--per-page",
+            "1",
+            "--difficulty",
+            "Easy",
+        ])
+        .write_stdin(format!("{PUZZLE}\n{PUZZLE}"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let pdf = String::from_utf8(output.stdout).unwrap();
+    assert!(pdf.starts_with("%PDF-1.4\n"));
+    assert!(pdf.contains("/Count 4 >>"));
+    assert!(pdf.contains("/MediaBox [0 0 595 842]"));
+    assert!(pdf.contains("(Puzzle 2 - Easy) Tj"));
+    assert!(pdf.contains("(Solution 2) Tj"));
+}
+
+#[test]
+fn test_format_pdf_bad_per_page() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["--format", "pdf", "--per-page", "10"])
+        .write_stdin(PUZZLE)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_format_pdf_no_puzzles() {
+    sudoku()
+        .args(["--format", "pdf"])
+        .write_stdin("\n \n")
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("Error: No puzzles in input\n");
+}
+
+#[test]
+fn test_check_correct() {
+    let puzzle = NamedTempFile::new().unwrap();
+    fs::write(&puzzle, PUZZLE).unwrap();
+    let answer = NamedTempFile::new().unwrap();
+    fs::write(&answer, SOLUTION).unwrap();
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("check")
+        .arg(puzzle.path())
+        .arg(answer.path())
+        .assert()
+        .success()
+        .stdout("Answer is correct\n");
+}
+
+#[test]
+fn test_check_incorrect() {
+    let puzzle = NamedTempFile::new().unwrap();
+    fs::write(&puzzle, PUZZLE).unwrap();
+    let answer = NamedTempFile::new().unwrap();
+    fs::write(
+        &answer,
+        concat!(
+            "843921657\n",
+            "967345821\n",
+            "251876493\n",
+            "548132976\n",
+            "729564138\n",
+            "136798245\n",
+            "372689514\n",
+            "814253769\n",
+            "69541738.\n",
+        ),
+    )
+    .unwrap();
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("check")
+        .arg(puzzle.path())
+        .arg(answer.path())
+        .assert()
+        .failure()
+        .stdout(concat!(
+            "r9c9 is unfilled\n",
+            "8 is repeated in column 1 at r1c1, r8c1\n",
+            "4 is repeated in column 2 at r1c2, r4c2\n",
+        ));
+}
+
+#[test]
+fn test_check_missing_answer() {
+    let puzzle = NamedTempFile::new().unwrap();
+    fs::write(&puzzle, PUZZLE).unwrap();
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("check")
+        .arg(puzzle.path())
+        .assert()
+        .failure()
+        .stdout("");
+}
+
+#[test]
+fn test_malformed_input() {
+    sudoku()
+        .write_stdin(PUZZLE.replace("9 . . 3", "9 . 3"))
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(concat!(
+            "Error: Invalid input\n",
+            "\n",
+            "Caused by:\n",
+            "    line 2, column 16: row is 8 cells long, expected 9: \"9 . 3 . 5 . . 1\"\n",
+        ));
+}
+
+#[test]
+fn test_solve_subcommand() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["solve", "--style", "ascii"])
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(PRETTY_SOLUTION);
+}
+
+#[test]
+fn test_subcommand_help() {
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["count", "--help"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Usage: sudoku count [<options>] [<infile>]\n"));
+}
+
+#[test]
+fn test_count() {
+    sudoku()
+        .arg("count")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout("1\n")
+        .stderr("");
+}
+
+#[test]
+fn test_count_limit() {
+    sudoku()
+        .args(["count", "--limit", "5"])
+        .write_stdin(PUZZLE.replace("3 . 2 . 6", ". . . . ."))
+        .assert()
+        .success()
+        .stdout("5\n")
+        .stderr("Stopped counting after reaching the limit of 5 solutions\n");
+}
+
+#[test]
+fn test_generate_seeded() {
+    let generate = || {
+        let output = Command::cargo_bin("sudoku")
+            .unwrap()
+            .args(["generate", "--seed", "42", "-n", "2"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let first = generate();
+    assert_eq!(first, generate());
+    let puzzles = first.split("\n\n").collect::<Vec<_>>();
+    assert_eq!(puzzles.len(), 2);
+    for p in puzzles {
+        let tmpfile = NamedTempFile::new().unwrap();
+        fs::write(&tmpfile, p).unwrap();
+        Command::cargo_bin("sudoku")
+            .unwrap()
+            .arg("count")
+            .arg(tmpfile.path())
+            .assert()
+            .success()
+            .stdout("1\n");
+    }
+}
+
+#[test]
+fn test_minimize() {
+    // The exact puzzle produced for a given seed depends on the version of
+    // `fastrand` in use, so check its properties instead.
+    let run = || {
+        sudoku()
+            .args(["minimize", "--seed", "1"])
+            .write_stdin(PUZZLE)
+            .output()
+            .unwrap()
+    };
+    let output = run();
+    assert!(output.status.success());
+    assert_eq!(run().stdout, output.stdout);
+    let original = PUZZLE.parse::<Puzzle>().unwrap();
+    let minimized = String::from_utf8(output.stdout)
+        .unwrap()
+        .parse::<Puzzle>()
+        .unwrap();
+    assert!(minimized.has_unique_solution());
+    let mut givens = 0;
+    for (y, row) in minimized.iter().enumerate() {
+        for (x, &d) in row.iter().enumerate() {
+            if d != 0 {
+                givens += 1;
+                assert_eq!(d, original[y][x]);
+                let mut grid = *minimized;
+                grid[y][x] = 0;
+                assert!(!Puzzle::try_from(grid).unwrap().has_unique_solution());
+            }
+        }
+    }
+    let removed = original.iter().flatten().filter(|&&d| d != 0).count() - givens;
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("Removed {removed} givens, leaving {givens}\n")
+    );
+}
+
+#[test]
+fn test_minimize_multiple_solutions() {
+    sudoku()
+        .arg("minimize")
+        .write_stdin(
+            PUZZLE
+                .replace("9 . . 3", ". . . 3")
+                .replace("8 . . 2", ". . . 2"),
+        )
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("Puzzle has multiple solutions\n");
+}
+
+#[test]
+fn test_rate() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("rate")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "Difficulty: easy\n",
+            "Hardest technique: Naked single\n",
+            "Steps: 49\n",
+        ));
+}
+
+#[test]
+fn test_hint() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("hint")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout("Naked single: r5c6 can only contain 4, so r5c6 = 4\n");
+}
+
+#[test]
+fn test_hint_from_hodoku() {
+    // Eliminating 5 from r1c1 leaves it with only one candidate:
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["hint", "--from", "hodoku"])
+        .write_stdin(HODOKU)
+        .assert()
+        .success()
+        .stdout("Naked single: r1c1 can only contain 4, so r1c1 = 4\n");
+}
+
+#[test]
+fn test_hint_solved() {
+    sudoku()
+        .arg("hint")
+        .write_stdin(SOLUTION)
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("No hint available\n");
+}
+
+#[test]
+fn test_explain() {
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("explain")
+        .write_stdin(PUZZLE)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 49);
+    assert_eq!(
+        lines[0],
+        "1. Naked single: r5c6 can only contain 4, so r5c6 = 4"
+    );
+}
+
+#[test]
+fn test_explain_from_marks() {
+    // r1c1 has only one candidate, so the pencil-mark grid fills it in:
+    let marks = Position::from_hodoku(HODOKU).unwrap().to_pencil_marks();
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["explain", "--from", "marks"])
+        .write_stdin(marks)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().next(),
+        Some("1. Naked single: r1c4 can only contain 9, so r1c4 = 9")
+    );
+}
+
+#[test]
+fn test_analyze() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("analyze")
+        .write_stdin(concat!(
+            "001040000\n",
+            "000961030\n",
+            "300000047\n",
+            "002008694\n",
+            "010500000\n",
+            "000000200\n",
+            "004000070\n",
+            "120050000\n",
+            "078600000\n",
+        ))
+        .assert()
+        .success()
+        .stdout(concat!(
+            "+---------+---------+---------+\n",
+            "|(2) . [1]| . [4] . | . (5) . |\n",
+            "|(7) . (5)|[9][6][1]| . [3](2)|\n",
+            "|[3] .  . | .  .  . | . [4][7]|\n",
+            "+---------+---------+---------+\n",
+            "|(5)(3)[2]| . (7)[8]|[6][9][4]|\n",
+            "| . [1] . |[5] .  . | .  .  . |\n",
+            "| . (6)(7)| . (9)(3)|[2] .  . |\n",
+            "+---------+---------+---------+\n",
+            "| . (5)[4]| .  .  . | . [7] . |\n",
+            "|[1][2] . | . [5] . | .  .  . |\n",
+            "| . [7][8]|[6] .  . | . (2) . |\n",
+            "+---------+---------+---------+\n",
+            "Critical givens, shown as [d]: r1c3, r1c5, r2c4, r2c5, r2c6, r2c8, r3c1, r3c8, r3c9, r4c3, r4c6, r4c7, r4c8, r4c9, r5c2, r5c4, r6c7, r7c3, r7c8, r8c1, r8c2, r8c5, r9c2, r9c3, r9c4\n",
+            "Backdoors, shown as (d): r1c1, r1c8, r2c1, r2c3, r2c9, r4c1, r4c2, r4c5, r6c2, r6c3, r6c5, r6c6, r7c2, r9c8\n",
+        ));
+}
+
+#[test]
+fn test_analyze_singles() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("analyze")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "+---------+---------+---------+\n",
+            "| .  .  3 | .  2  . |[6] .  . |\n",
+            "|[9] .  . | 3  .  5 | .  .  1 |\n",
+            "| .  .  1 |[8] .  6 | 4  .  . |\n",
+            "+---------+---------+---------+\n",
+            "| .  .  8 | 1  . [2]| 9  .  . |\n",
+            "|[7] .  . | .  .  . | .  .  8 |\n",
+            "| .  . [6]|[7] .  8 | 2  .  . |\n",
+            "+---------+---------+---------+\n",
+            "| .  .  2 | 6  . [9]| 5  .  . |\n",
+            "|[8] .  . | 2  .  3 | .  . [9]|\n",
+            "| .  .  5 | . [1] . | 3  .  . |\n",
+            "+---------+---------+---------+\n",
+            "Critical givens, shown as [d]: r1c7, r2c1, r3c4, r4c6, r5c1, r6c3, r6c4, r7c6, r8c1, r8c9, r9c5\n",
+            "Backdoors: none needed; the puzzle can be solved with singles alone\n",
+        ));
+}
+
+#[test]
+fn test_convert() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["convert", "--to", "sdk"])
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "..3.2.6..\n",
+            "9..3.5..1\n",
+            "..18.64..\n",
+            "..81.29..\n",
+            "7.......8\n",
+            "..67.82..\n",
+            "..26.95..\n",
+            "8..2.3..9\n",
+            "..5.1.3..\n",
+        ));
+}
+
+#[test]
+fn test_render() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .arg("render")
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "+-----+-----+-----+\n",
+            "|    3|  2  |6    |\n",
+            "|9    |3   5|    1|\n",
+            "|    1|8   6|4    |\n",
+            "+-----+-----+-----+\n",
+            "|    8|1   2|9    |\n",
+            "|7    |     |    8|\n",
+            "|    6|7   8|2    |\n",
+            "+-----+-----+-----+\n",
+            "|    2|6   9|5    |\n",
+            "|8    |2   3|    9|\n",
+            "|    5|  1  |3    |\n",
+            "+-----+-----+-----+\n",
+        ));
+}
+
+#[test]
+fn test_play_script() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["play", "--keys", "4 8 l 8 p 1 2 ?"])
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "+---------+---------+---------+\n",
+            "|!8![8] 3 | .  2  . | 6  .  . |\n",
+            "| 9  .  . | 3  .  5 | .  .  1 |\n",
+            "| .  .  1 | 8  .  6 | 4  .  . |\n",
+            "+---------+---------+---------+\n",
+            "| .  .  8 | 1  .  2 | 9  .  . |\n",
+            "| 7  .  . | .  .  . | .  .  8 |\n",
+            "| .  .  6 | 7  .  8 | 2  .  . |\n",
+            "+---------+---------+---------+\n",
+            "| .  .  2 | 6  .  9 | 5  .  . |\n",
+            "|!8! .  . | 2  .  3 | .  .  9 |\n",
+            "| .  .  5 | .  1  . | 3  .  . |\n",
+            "+---------+---------+---------+\n",
+            "r1c2, pencil mode\n",
+            "Pencil marks: 1 2\n",
+            "r1c1 is incorrect\n",
+            "Keys: arrows/hjkl move, 1-9 enter, 0/x/. erase, p pencil, u undo, r redo, ? hint, c check, q quit\n",
+        ));
+}
+
+#[test]
+fn test_play_erase_and_check() {
+    // Fill in the only blank cell, erase it again with `.`, and check:
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["play", "--keys", "4 . c"])
+        .write_stdin(SOLUTION.replacen('4', ".", 1))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("+---------+---------+---------+\n|[.] 8  3 |"));
+    assert!(stdout.contains("\nNo mistakes so far; 1 cell left to fill\n"));
+}
+
+#[test]
+fn test_play_undo_redo() {
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["play", "--keys", "4 l 8 u u r c"])
+        .write_stdin(PUZZLE)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines[1], "|[4] .  3 | .  2  . | 6  .  . |");
+    assert_eq!(lines[13], "r1c1, digit mode");
+    assert_eq!(lines[15], "No mistakes so far; 48 cells left to fill");
+}
+
+#[test]
+fn test_play_hint() {
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["play", "--keys", "?"])
+        .write_stdin(PUZZLE)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().nth(15),
+        Some("Naked single: r5c6 can only contain 4, so r5c6 = 4")
+    );
+}
+
+#[test]
+fn test_play_bad_key() {
+    sudoku()
+        .args(["play", "--keys", "4z"])
+        .write_stdin(PUZZLE)
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("Error: unknown key 'z' in script\n");
+}
+
+#[test]
+fn test_play_stdin_without_keys() {
+    sudoku()
+        .arg("play")
+        .write_stdin(PUZZLE)
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("Error: --keys is required when reading from standard input\n");
+}
+
+#[test]
+fn test_play_save_resume() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let savefile = tmpdir.path().join("game.txt");
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["play", "--keys", "4 l 8 u"])
+        .arg("--save")
+        .arg(&savefile)
+        .write_stdin(PUZZLE)
+        .assert()
+        .success();
+    let saved = fs::read_to_string(&savefile).unwrap();
+    assert!(saved.starts_with("sudoku game\n"));
+    assert!(saved.ends_with("move r1c1 4 -\nmove r1c2 8 -\nundone 1\n"));
+    let output = Command::cargo_bin("sudoku")
+        .unwrap()
+        .args(["play", "--keys", "ll r"])
+        .arg("--resume")
+        .arg(&savefile)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines[1], "| 4 [8] 3 | .  2  . | 6  .  . |");
+    assert_eq!(lines[13], "r1c2, digit mode");
+}
+
+#[test]
+fn test_dedupe() {
+    // The canonical form of PUZZLE, which is equivalent to it:
+    let equivalent = concat!(
+        "000000000\n",
+        "001002003\n",
+        "240560780\n",
+        "000000000\n",
+        "002003007\n",
+        "350680940\n",
+        "000070050\n",
+        "520308406\n",
+        "710405208\n",
+    );
+    sudoku()
+        .arg("dedupe")
+        .write_stdin(format!("{PUZZLE}\n{equivalent}\n{UNSOLVABLE}"))
+        .assert()
+        .success()
+        .stdout(concat!(
+            "003020600\n",
+            "900305001\n",
+            "001806400\n",
+            "008102900\n",
+            "700000008\n",
+            "006708200\n",
+            "002609500\n",
+            "800203009\n",
+            "005010300\n",
+            "\n",
+            "200900000\n",
+            "000000060\n",
+            "000001000\n",
+            "502600407\n",
+            "000004100\n",
+            "000098023\n",
+            "000003080\n",
+            "005010000\n",
+            "007000000\n",
+        ))
+        .stderr("Removed 1 duplicate puzzle\n");
+}
+
+#[test]
+fn test_dedupe_parities() {
+    // Puzzles that differ only in their shading are not duplicates, but a
+    // shaded puzzle rotated 180° is:
+    let even = PUZZLE.replacen('.', "e", 1);
+    let odd = PUZZLE.replacen('.', "o", 1);
+    let cells = even
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .rev()
+        .collect::<Vec<_>>();
+    let rotated = cells
+        .chunks(9)
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect::<String>();
+    sudoku()
+        .arg("dedupe")
+        .write_stdin(format!("{even}\n{odd}\n{rotated}"))
+        .assert()
+        .success()
+        .stdout(concat!(
+            "e03020600\n",
+            "900305001\n",
+            "001806400\n",
+            "008102900\n",
+            "700000008\n",
+            "006708200\n",
+            "002609500\n",
+            "800203009\n",
+            "005010300\n",
+            "\n",
+            "o03020600\n",
+            "900305001\n",
+            "001806400\n",
+            "008102900\n",
+            "700000008\n",
+            "006708200\n",
+            "002609500\n",
+            "800203009\n",
+            "005010300\n",
+        ))
+        .stderr("Removed 1 duplicate puzzle\n");
+}
+
+#[test]
+fn test_transform() {
+    Command::cargo_bin("sudoku")
+        .unwrap()
+        .args([
+            "transform",
+            "--rotate",
+            "1",
+            "--swap-bands",
+            "1,3",
+            "--solution",
+        ])
+        .write_stdin(PUZZLE)
+        .assert()
+        .success()
+        .stdout(concat!(
+            "305209406\n",
+            "000000000\n",
+            "090080010\n",
+            "026701830\n",
+            "100000002\n",
+            "039802650\n",
+            "080070090\n",
+            "000000000\n",
+            "502608103\n",
+            "\n",
+            "375219486\n",
+            "861437925\n",
+            "294586317\n",
+            "426751839\n",
+            "158963742\n",
+            "739842651\n",
+            "683175294\n",
+            "917324568\n",
+            "542698173\n",
+        ));
+}
+
+#[test]
+fn test_transform_different_bands() {
+    sudoku()
+        .args(["transform", "--swap-rows", "3,4"])
+        .write_stdin(PUZZLE)
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("Error: cannot swap lines in different bands or stacks\n");
+}
@@ -0,0 +1,123 @@
+/* Generated by cbindgen from crates/ffi/src/lib.rs; do not edit by hand. */
+
+#ifndef SUDOKU_H
+#define SUDOKU_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The version of the C API provided by this library.  It is incremented
+// whenever a function is removed or changed incompatibly.
+#define SUDOKU_ABI_VERSION 1
+
+// The number of bytes in a grid buffer
+#define SUDOKU_CELLS 81
+
+// The size of a buffer large enough to hold a grid formatted by
+// `sudoku_format()`: nine lines of nine digits, eight newlines, and a
+// terminating NUL
+#define SUDOKU_TEXT_SIZE 90
+
+// Status codes returned by the library's functions
+typedef enum SudokuStatus {
+  // The operation succeeded.
+  SUDOKU_STATUS_OK = 0,
+  // A required pointer argument was null.
+  SUDOKU_STATUS_NULL_POINTER = 1,
+  // The input text was not valid UTF-8.
+  SUDOKU_STATUS_INVALID_UTF8 = 2,
+  // The input text could not be parsed as a puzzle.
+  SUDOKU_STATUS_PARSE_ERROR = 3,
+  // An input grid contained a value greater than 9.
+  SUDOKU_STATUS_INVALID_GRID = 4,
+  // The puzzle has no solution.
+  SUDOKU_STATUS_NO_SOLUTION = 5,
+  // An output buffer was too small.
+  SUDOKU_STATUS_BUFFER_TOO_SMALL = 6,
+  // An internal error occurred; this is a bug in the library.
+  SUDOKU_STATUS_INTERNAL_ERROR = 7,
+} SudokuStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Return the version of the C API provided by the library, for comparison
+// against the `SUDOKU_ABI_VERSION` that the caller was compiled with
+uint32_t sudoku_abi_version(void);
+
+// Return a static, NUL-terminated description of a status code.  Unknown
+// codes are described as such.
+const char *sudoku_status_message(int status);
+
+// Parse a puzzle from NUL-terminated `text` in any format accepted by the
+// `sudoku` library's `Puzzle` parser and write its grid to `grid_out`.  Even
+// & odd shading is discarded.
+//
+// If parsing fails with `SUDOKU_STATUS_PARSE_ERROR` and `error_out` is not
+// null, a NUL-terminated description of the problem is written to it,
+// truncated to fit in `error_size` bytes.
+//
+// # Safety
+//
+// `text` must be null or point to a NUL-terminated string.  `grid_out` must
+// be null or point to `SUDOKU_CELLS` writable bytes.  `error_out` must be
+// null or point to `error_size` writable bytes.
+enum SudokuStatus sudoku_parse(const char *text,
+                               uint8_t *grid_out,
+                               char *error_out,
+                               size_t error_size);
+
+// Solve the puzzle in `puzzle` and write its solution to `solution_out`.  If
+// the puzzle has multiple solutions, which one is written is unspecified.
+//
+// # Safety
+//
+// `puzzle` must be null or point to `SUDOKU_CELLS` readable bytes.
+// `solution_out` must be null or point to `SUDOKU_CELLS` writable bytes.
+enum SudokuStatus sudoku_solve(const uint8_t *puzzle, uint8_t *solution_out);
+
+// Count the solutions of the puzzle in `puzzle`, stopping once `limit` have
+// been found, and write the count to `count_out`.  Pass a `limit` of 2 to
+// check whether a puzzle's solution is unique.
+//
+// # Safety
+//
+// `puzzle` must be null or point to `SUDOKU_CELLS` readable bytes.
+// `count_out` must be null or point to a writable `size_t`.
+enum SudokuStatus sudoku_count_solutions(const uint8_t *puzzle, size_t limit, size_t *count_out);
+
+// Generate a minimal puzzle with a unique solution from `seed` and write it
+// to `puzzle_out`.  If `symmetric` is true, the givens have 180° rotational
+// symmetry.  If `solution_out` is not null, the puzzle's solution is written
+// to it.
+//
+// The same seed and symmetry always produce the same puzzle.
+//
+// # Safety
+//
+// `puzzle_out` must be null or point to `SUDOKU_CELLS` writable bytes.
+// `solution_out` must be null or point to `SUDOKU_CELLS` writable bytes.
+enum SudokuStatus sudoku_generate(uint64_t seed,
+                                  bool symmetric,
+                                  uint8_t *puzzle_out,
+                                  uint8_t *solution_out);
+
+// Format the grid in `grid` as nine lines of nine digits (with 0 for
+// unfilled cells) separated by newlines and write it, NUL-terminated, to
+// `text_out`.  `text_size` must be at least `SUDOKU_TEXT_SIZE`.
+//
+// # Safety
+//
+// `grid` must be null or point to `SUDOKU_CELLS` readable bytes.
+// `text_out` must be null or point to `text_size` writable bytes.
+enum SudokuStatus sudoku_format(const uint8_t *grid, char *text_out, size_t text_size);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SUDOKU_H */
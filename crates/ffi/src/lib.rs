@@ -0,0 +1,408 @@
+//! C bindings for the Sudoku solver
+//!
+//! This crate builds a shared library exposing a small C API over the
+//! [`sudoku`] crate.  The matching header is `include/sudoku.h`, which is
+//! generated from this file by [cbindgen](https://github.com/mozilla/cbindgen)
+//! and checked to be up to date by the test suite.
+//!
+//! Grids are passed as arrays of `SUDOKU_CELLS` bytes in row-major order,
+//! with 0 for an unfilled cell.  All output goes into buffers provided by the
+//! caller; the library never allocates memory that the caller must free.
+//! Every function that can fail returns a [`SudokuStatus`], and no function
+//! writes to its output buffers unless it returns [`SudokuStatus::Ok`]
+//! (except that [`sudoku_parse()`] writes an error message on failure).
+//!
+//! Functions are never removed from the API or changed incompatibly without
+//! incrementing [`SUDOKU_ABI_VERSION`].
+
+// Exposing a C ABI requires dereferencing raw pointers supplied by the
+// caller; each such use is confined to a documented `unsafe` block below.
+#![allow(unsafe_code)]
+
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use sudoku::generate::Generator;
+use sudoku::Puzzle;
+
+/// The version of the C API provided by this library.  It is incremented
+/// whenever a function is removed or changed incompatibly.
+pub const SUDOKU_ABI_VERSION: u32 = 1;
+
+/// The number of bytes in a grid buffer
+pub const SUDOKU_CELLS: usize = 81;
+
+/// The size of a buffer large enough to hold a grid formatted by
+/// `sudoku_format()`: nine lines of nine digits, eight newlines, and a
+/// terminating NUL
+pub const SUDOKU_TEXT_SIZE: usize = 90;
+
+type Grid = [[u8; 9]; 9];
+
+/// Status codes returned by the library's functions
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SudokuStatus {
+    /// The operation succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// The input text was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The input text could not be parsed as a puzzle.
+    ParseError = 3,
+    /// An input grid contained a value greater than 9.
+    InvalidGrid = 4,
+    /// The puzzle has no solution.
+    NoSolution = 5,
+    /// An output buffer was too small.
+    BufferTooSmall = 6,
+    /// An internal error occurred; this is a bug in the library.
+    InternalError = 7,
+}
+
+/// Return the version of the C API provided by the library, for comparison
+/// against the `SUDOKU_ABI_VERSION` that the caller was compiled with
+#[no_mangle]
+pub extern "C" fn sudoku_abi_version() -> u32 {
+    SUDOKU_ABI_VERSION
+}
+
+/// Return a static, NUL-terminated description of a status code.  Unknown
+/// codes are described as such.
+#[no_mangle]
+pub extern "C" fn sudoku_status_message(status: c_int) -> *const c_char {
+    let msg: &'static [u8] = match status {
+        0 => b"success\0",
+        1 => b"null pointer argument\0",
+        2 => b"input is not valid UTF-8\0",
+        3 => b"input is not a valid puzzle\0",
+        4 => b"grid contains a value greater than 9\0",
+        5 => b"puzzle has no solution\0",
+        6 => b"output buffer is too small\0",
+        7 => b"internal error\0",
+        _ => b"unknown status code\0",
+    };
+    msg.as_ptr().cast::<c_char>()
+}
+
+/// Parse a puzzle from NUL-terminated `text` in any format accepted by the
+/// `sudoku` library's `Puzzle` parser and write its grid to `grid_out`.  Even
+/// & odd shading is discarded.
+///
+/// If parsing fails with `SUDOKU_STATUS_PARSE_ERROR` and `error_out` is not
+/// null, a NUL-terminated description of the problem is written to it,
+/// truncated to fit in `error_size` bytes.
+///
+/// # Safety
+///
+/// `text` must be null or point to a NUL-terminated string.  `grid_out` must
+/// be null or point to `SUDOKU_CELLS` writable bytes.  `error_out` must be
+/// null or point to `error_size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_parse(
+    text: *const c_char,
+    grid_out: *mut u8,
+    error_out: *mut c_char,
+    error_size: usize,
+) -> SudokuStatus {
+    if text.is_null() || grid_out.is_null() {
+        return SudokuStatus::NullPointer;
+    }
+    // SAFETY: `text` is non-null, and the caller guarantees that it is
+    // NUL-terminated.
+    let text = unsafe { CStr::from_ptr(text) };
+    let Ok(text) = text.to_str() else {
+        return SudokuStatus::InvalidUtf8;
+    };
+    guard(|| match text.parse::<Puzzle>() {
+        Ok(puzzle) => {
+            // SAFETY: `grid_out` is non-null, and the caller guarantees that
+            // it points to `SUDOKU_CELLS` writable bytes.
+            unsafe { write_grid(grid_out, *puzzle) };
+            SudokuStatus::Ok
+        }
+        Err(e) => {
+            if !error_out.is_null() {
+                // SAFETY: `error_out` is non-null, and the caller guarantees
+                // that it points to `error_size` writable bytes.
+                unsafe { write_truncated(error_out, error_size, &e.to_string()) };
+            }
+            SudokuStatus::ParseError
+        }
+    })
+}
+
+/// Solve the puzzle in `puzzle` and write its solution to `solution_out`.  If
+/// the puzzle has multiple solutions, which one is written is unspecified.
+///
+/// # Safety
+///
+/// `puzzle` must be null or point to `SUDOKU_CELLS` readable bytes.
+/// `solution_out` must be null or point to `SUDOKU_CELLS` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_solve(puzzle: *const u8, solution_out: *mut u8) -> SudokuStatus {
+    if puzzle.is_null() || solution_out.is_null() {
+        return SudokuStatus::NullPointer;
+    }
+    // SAFETY: `puzzle` is non-null, and the caller guarantees that it points
+    // to `SUDOKU_CELLS` readable bytes.
+    let grid = unsafe { read_grid(puzzle) };
+    guard(|| {
+        let Ok(puzzle) = Puzzle::try_from(grid) else {
+            return SudokuStatus::InvalidGrid;
+        };
+        let Some(solution) = puzzle.solve() else {
+            return SudokuStatus::NoSolution;
+        };
+        // SAFETY: `solution_out` is non-null, and the caller guarantees that
+        // it points to `SUDOKU_CELLS` writable bytes.
+        unsafe { write_grid(solution_out, solution.into()) };
+        SudokuStatus::Ok
+    })
+}
+
+/// Count the solutions of the puzzle in `puzzle`, stopping once `limit` have
+/// been found, and write the count to `count_out`.  Pass a `limit` of 2 to
+/// check whether a puzzle's solution is unique.
+///
+/// # Safety
+///
+/// `puzzle` must be null or point to `SUDOKU_CELLS` readable bytes.
+/// `count_out` must be null or point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_count_solutions(
+    puzzle: *const u8,
+    limit: usize,
+    count_out: *mut usize,
+) -> SudokuStatus {
+    if puzzle.is_null() || count_out.is_null() {
+        return SudokuStatus::NullPointer;
+    }
+    // SAFETY: `puzzle` is non-null, and the caller guarantees that it points
+    // to `SUDOKU_CELLS` readable bytes.
+    let grid = unsafe { read_grid(puzzle) };
+    guard(|| {
+        let Ok(puzzle) = Puzzle::try_from(grid) else {
+            return SudokuStatus::InvalidGrid;
+        };
+        let count = puzzle.count_solutions(limit);
+        // SAFETY: `count_out` is non-null, and the caller guarantees that it
+        // is writable.
+        unsafe { count_out.write_unaligned(count) };
+        SudokuStatus::Ok
+    })
+}
+
+/// Generate a minimal puzzle with a unique solution from `seed` and write it
+/// to `puzzle_out`.  If `symmetric` is true, the givens have 180° rotational
+/// symmetry.  If `solution_out` is not null, the puzzle's solution is written
+/// to it.
+///
+/// The same seed and symmetry always produce the same puzzle.
+///
+/// # Safety
+///
+/// `puzzle_out` must be null or point to `SUDOKU_CELLS` writable bytes.
+/// `solution_out` must be null or point to `SUDOKU_CELLS` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_generate(
+    seed: u64,
+    symmetric: bool,
+    puzzle_out: *mut u8,
+    solution_out: *mut u8,
+) -> SudokuStatus {
+    if puzzle_out.is_null() {
+        return SudokuStatus::NullPointer;
+    }
+    guard(|| {
+        let (puzzle, solution) = Generator::new(seed).with_symmetry(symmetric).generate();
+        // SAFETY: `puzzle_out` is non-null, and the caller guarantees that it
+        // points to `SUDOKU_CELLS` writable bytes.
+        unsafe { write_grid(puzzle_out, *puzzle) };
+        if !solution_out.is_null() {
+            // SAFETY: `solution_out` is non-null, and the caller guarantees
+            // that it points to `SUDOKU_CELLS` writable bytes.
+            unsafe { write_grid(solution_out, solution.into()) };
+        }
+        SudokuStatus::Ok
+    })
+}
+
+/// Format the grid in `grid` as nine lines of nine digits (with 0 for
+/// unfilled cells) separated by newlines and write it, NUL-terminated, to
+/// `text_out`.  `text_size` must be at least `SUDOKU_TEXT_SIZE`.
+///
+/// # Safety
+///
+/// `grid` must be null or point to `SUDOKU_CELLS` readable bytes.
+/// `text_out` must be null or point to `text_size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sudoku_format(
+    grid: *const u8,
+    text_out: *mut c_char,
+    text_size: usize,
+) -> SudokuStatus {
+    if grid.is_null() || text_out.is_null() {
+        return SudokuStatus::NullPointer;
+    }
+    if text_size < SUDOKU_TEXT_SIZE {
+        return SudokuStatus::BufferTooSmall;
+    }
+    // SAFETY: `grid` is non-null, and the caller guarantees that it points to
+    // `SUDOKU_CELLS` readable bytes.
+    let grid = unsafe { read_grid(grid) };
+    guard(|| {
+        let Ok(puzzle) = Puzzle::try_from(grid) else {
+            return SudokuStatus::InvalidGrid;
+        };
+        // SAFETY: `text_out` is non-null, and the caller guarantees that it
+        // points to `text_size` writable bytes, which is enough for the
+        // formatted grid.
+        unsafe { write_truncated(text_out, text_size, &puzzle.to_string()) };
+        SudokuStatus::Ok
+    })
+}
+
+/// Run `f`, converting a panic into [`SudokuStatus::InternalError`] so that it
+/// does not unwind into the caller
+fn guard<F: FnOnce() -> SudokuStatus>(f: F) -> SudokuStatus {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(SudokuStatus::InternalError)
+}
+
+/// # Safety
+///
+/// `src` must point to [`SUDOKU_CELLS`] readable bytes.
+unsafe fn read_grid(src: *const u8) -> Grid {
+    // SAFETY: The caller guarantees that `src` points to enough bytes, and
+    // `Grid` has an alignment of 1.
+    unsafe { src.cast::<Grid>().read() }
+}
+
+/// # Safety
+///
+/// `dest` must point to [`SUDOKU_CELLS`] writable bytes.
+unsafe fn write_grid(dest: *mut u8, grid: Grid) {
+    // SAFETY: The caller guarantees that `dest` points to enough bytes, and
+    // `Grid` has an alignment of 1.
+    unsafe { dest.cast::<Grid>().write(grid) };
+}
+
+/// Write as much of `s` as fits in `size - 1` bytes (without splitting a
+/// character) to `dest`, followed by a NUL.  Nothing is written if `size` is
+/// 0.
+///
+/// # Safety
+///
+/// `dest` must point to `size` writable bytes.
+unsafe fn write_truncated(dest: *mut c_char, size: usize, s: &str) {
+    let Some(max) = size.checked_sub(1) else {
+        return;
+    };
+    let mut len = s.len().min(max);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+    // SAFETY: The caller guarantees that `dest` points to `size` bytes, and
+    // `len + 1 <= size`.
+    unsafe {
+        ptr::copy_nonoverlapping(s.as_ptr(), dest.cast::<u8>(), len);
+        dest.add(len).write(0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PUZZLE: &str = concat!(
+        "..3.2.6..\n",
+        "9..3.5..1\n",
+        "..18.64..\n",
+        "..81.29..\n",
+        "7.......8\n",
+        "..67.82..\n",
+        "..26.95..\n",
+        "8..2.3..9\n",
+        "..5.1.3..\n",
+    );
+
+    #[test]
+    fn test_parse_and_solve() {
+        let mut grid = [0u8; SUDOKU_CELLS];
+        let mut solution = [0u8; SUDOKU_CELLS];
+        let text = std::ffi::CString::new(PUZZLE).unwrap();
+        // SAFETY: All buffers are valid and of the documented sizes.
+        unsafe {
+            assert_eq!(
+                sudoku_parse(text.as_ptr(), grid.as_mut_ptr(), ptr::null_mut(), 0),
+                SudokuStatus::Ok
+            );
+            assert_eq!(
+                sudoku_solve(grid.as_ptr(), solution.as_mut_ptr()),
+                SudokuStatus::Ok
+            );
+        }
+        assert_eq!(grid[..9], [0, 0, 3, 0, 2, 0, 6, 0, 0]);
+        assert_eq!(solution[..9], [4, 8, 3, 9, 2, 1, 6, 5, 7]);
+    }
+
+    #[test]
+    fn test_parse_error_is_truncated() {
+        let mut grid = [0u8; SUDOKU_CELLS];
+        let mut error = [0x7F as c_char; 8];
+        // SAFETY: All buffers are valid and of the documented sizes.
+        let status = unsafe {
+            sudoku_parse(
+                b"123\0".as_ptr().cast::<c_char>(),
+                grid.as_mut_ptr(),
+                error.as_mut_ptr(),
+                error.len(),
+            )
+        };
+        assert_eq!(status, SudokuStatus::ParseError);
+        assert_eq!(error[7], 0);
+        assert!(error[..7].iter().all(|&c| c != 0));
+    }
+
+    #[test]
+    fn test_truncation_respects_char_boundaries() {
+        let mut buf = [0x7F as c_char; 4];
+        // SAFETY: `buf` is 4 bytes long.
+        unsafe { write_truncated(buf.as_mut_ptr(), buf.len(), "ab°") };
+        // SAFETY: `buf` was just NUL-terminated.
+        assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes(), b"ab");
+    }
+
+    #[test]
+    fn test_invalid_grid() {
+        let mut grid = [0u8; SUDOKU_CELLS];
+        grid[40] = 10;
+        let mut count = 0;
+        // SAFETY: All buffers are valid and of the documented sizes.
+        let status = unsafe { sudoku_count_solutions(grid.as_ptr(), 2, &mut count) };
+        assert_eq!(status, SudokuStatus::InvalidGrid);
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_format_needs_room() {
+        let grid = [0u8; SUDOKU_CELLS];
+        let mut text = [0 as c_char; SUDOKU_TEXT_SIZE];
+        // SAFETY: All buffers are valid and of the documented sizes.
+        unsafe {
+            assert_eq!(
+                sudoku_format(grid.as_ptr(), text.as_mut_ptr(), SUDOKU_TEXT_SIZE - 1),
+                SudokuStatus::BufferTooSmall
+            );
+            assert_eq!(
+                sudoku_format(grid.as_ptr(), text.as_mut_ptr(), SUDOKU_TEXT_SIZE),
+                SudokuStatus::Ok
+            );
+        }
+        assert_eq!(text[SUDOKU_TEXT_SIZE - 1], 0);
+        // SAFETY: `text` was just NUL-terminated.
+        let text = unsafe { CStr::from_ptr(text.as_ptr()) }.to_str().unwrap();
+        assert_eq!(text.lines().collect::<Vec<_>>(), vec!["000000000"; 9]);
+    }
+}
@@ -0,0 +1,102 @@
+/* Exercises the C API; run by tests/c_api.rs.  Exits nonzero on failure. */
+#include <stdio.h>
+#include <string.h>
+#include "sudoku.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                       \
+    do {                                                                  \
+        if (!(cond)) {                                                    \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,        \
+                    __LINE__, #cond);                                     \
+            failures++;                                                   \
+        }                                                                 \
+    } while (0)
+
+static const char *PUZZLE =
+    "..3.2.6..\n"
+    "9..3.5..1\n"
+    "..18.64..\n"
+    "..81.29..\n"
+    "7.......8\n"
+    "..67.82..\n"
+    "..26.95..\n"
+    "8..2.3..9\n"
+    "..5.1.3..\n";
+
+static const char *SOLUTION =
+    "483921657\n"
+    "967345821\n"
+    "251876493\n"
+    "548132976\n"
+    "729564138\n"
+    "136798245\n"
+    "372689514\n"
+    "814253769\n"
+    "695417382";
+
+int main(void) {
+    uint8_t grid[SUDOKU_CELLS];
+    uint8_t solution[SUDOKU_CELLS];
+    uint8_t generated[SUDOKU_CELLS];
+    uint8_t again[SUDOKU_CELLS];
+    char text[SUDOKU_TEXT_SIZE];
+    char error[128];
+    size_t count = 0;
+    int givens = 0;
+    int i;
+
+    CHECK(sudoku_abi_version() == SUDOKU_ABI_VERSION);
+    CHECK(strcmp(sudoku_status_message(SUDOKU_STATUS_NO_SOLUTION),
+                 "puzzle has no solution") == 0);
+
+    /* Parse, solve, and format a puzzle */
+    CHECK(sudoku_parse(PUZZLE, grid, error, sizeof error) == SUDOKU_STATUS_OK);
+    CHECK(grid[2] == 3 && grid[0] == 0);
+    CHECK(sudoku_solve(grid, solution) == SUDOKU_STATUS_OK);
+    CHECK(sudoku_format(solution, text, sizeof text) == SUDOKU_STATUS_OK);
+    CHECK(strcmp(text, SOLUTION) == 0);
+    CHECK(sudoku_format(solution, text, SUDOKU_TEXT_SIZE - 1)
+          == SUDOKU_STATUS_BUFFER_TOO_SMALL);
+
+    /* Parse errors are reported in the caller's buffer */
+    CHECK(sudoku_parse("12345", grid, error, sizeof error)
+          == SUDOKU_STATUS_PARSE_ERROR);
+    CHECK(strlen(error) > 0);
+    CHECK(sudoku_parse(NULL, grid, NULL, 0) == SUDOKU_STATUS_NULL_POINTER);
+
+    /* Count solutions */
+    memset(grid, 0, sizeof grid);
+    CHECK(sudoku_count_solutions(grid, 2, &count) == SUDOKU_STATUS_OK);
+    CHECK(count == 2);
+    grid[0] = 1;
+    grid[1] = 1;
+    CHECK(sudoku_count_solutions(grid, 2, &count) == SUDOKU_STATUS_OK);
+    CHECK(count == 0);
+    CHECK(sudoku_solve(grid, solution) == SUDOKU_STATUS_NO_SOLUTION);
+    grid[1] = 10;
+    CHECK(sudoku_solve(grid, solution) == SUDOKU_STATUS_INVALID_GRID);
+
+    /* Generate a puzzle, and check that it's uniquely solvable and that the
+     * same seed gives the same puzzle */
+    CHECK(sudoku_generate(42, true, generated, solution) == SUDOKU_STATUS_OK);
+    CHECK(sudoku_count_solutions(generated, 2, &count) == SUDOKU_STATUS_OK);
+    CHECK(count == 1);
+    for (i = 0; i < SUDOKU_CELLS; i++) {
+        if (generated[i] != 0) {
+            givens++;
+            CHECK(generated[i] == solution[i]);
+            CHECK(generated[SUDOKU_CELLS - 1 - i] != 0);
+        }
+    }
+    CHECK(givens >= 17);
+    CHECK(sudoku_generate(42, true, again, NULL) == SUDOKU_STATUS_OK);
+    CHECK(memcmp(generated, again, sizeof again) == 0);
+
+    if (failures == 0) {
+        printf("ok\n");
+        return 0;
+    }
+    return 1;
+}
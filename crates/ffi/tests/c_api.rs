@@ -0,0 +1,61 @@
+// Linking against the shared library is only set up for Unix-like toolchains.
+#![cfg(unix)]
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Return the directory containing the `cdylib` built alongside this test
+fn library_dir() -> PathBuf {
+    // The test binary lives in `target/<profile>/deps/`, and Cargo copies the
+    // shared library up into `target/<profile>/`.
+    let exe = std::env::current_exe().expect("test binary path should be known");
+    let deps = exe.parent().expect("test binary should be in a directory");
+    let libname = format!("{DLL_PREFIX}sudoku_ffi{DLL_SUFFIX}");
+    let dir = [
+        deps,
+        deps.parent().expect("deps directory should have a parent"),
+    ]
+    .into_iter()
+    .find(|dir| dir.join(&libname).exists())
+    .unwrap_or_else(|| panic!("could not find {libname} near {}", exe.display()));
+    dir.to_path_buf()
+}
+
+#[test]
+fn test_c_api() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let program = tmpdir.path().join("test_sudoku");
+    let libdir = library_dir();
+    let cc = std::env::var_os("CC").unwrap_or_else(|| "cc".into());
+    let status = Command::new(cc)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(crate_dir.join("tests").join("c").join("test_sudoku.c"))
+        .arg("-o")
+        .arg(&program)
+        .arg("-L")
+        .arg(&libdir)
+        .arg(format!("-Wl,-rpath,{}", libdir.display()))
+        .arg("-lsudoku_ffi")
+        .status()
+        .unwrap();
+    assert!(status.success(), "compiling the C test program failed");
+    // Cargo points the loader's search path at `target/<profile>/`, which may
+    // hold a stale copy of the library, so rely on the rpath alone.
+    let output = Command::new(&program)
+        .env_remove("LD_LIBRARY_PATH")
+        .env_remove("DYLD_LIBRARY_PATH")
+        .env_remove("DYLD_FALLBACK_LIBRARY_PATH")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "C test program failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, b"ok\n");
+}
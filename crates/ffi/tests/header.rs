@@ -0,0 +1,30 @@
+use std::path::Path;
+
+/// Regenerate the C header and check that the committed copy matches.  Run
+/// with `UPDATE_HEADER=1` set in the environment to rewrite the committed copy
+/// instead.
+#[test]
+fn test_header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src").join("lib.rs"))
+        .generate()
+        .unwrap();
+    let mut generated = Vec::new();
+    bindings.write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+    let path = crate_dir.join("include").join("sudoku.h");
+    if std::env::var_os("UPDATE_HEADER").is_some() {
+        std::fs::write(&path, generated).unwrap();
+    } else {
+        let committed = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\r\n", "\n");
+        assert!(
+            committed == generated,
+            "include/sudoku.h is out of date; rerun this test with UPDATE_HEADER=1 to regenerate it"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Analyzing which cells of a puzzle matter
+//!
+//! [`analyze()`] examines a puzzle with a unique solution for two kinds of
+//! significant cells:
+//!
+//! - *Backdoors*: blank cells that, once filled in with their digit from the
+//!   solution, make the puzzle solvable using only naked & hidden singles
+//!
+//! - *Critical givens*: givens that cannot be removed without the puzzle
+//!   gaining additional solutions
+use crate::logic::solves_with_singles;
+use crate::{Puzzle, Solution};
+
+/// The results of analyzing a puzzle with [`analyze()`]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Analysis {
+    /// The puzzle's solution
+    pub solution: Solution,
+
+    /// Whether the puzzle can already be solved using only naked & hidden
+    /// singles
+    pub singles: bool,
+
+    /// The blank cells that, when filled in with their digits from the
+    /// solution, make the puzzle solvable using only singles, in row-major
+    /// order.  This is empty if the puzzle is already solvable using only
+    /// singles.
+    pub backdoors: Vec<(usize, usize)>,
+
+    /// The givens whose removal would give the puzzle multiple solutions, in
+    /// row-major order
+    pub critical_givens: Vec<(usize, usize)>,
+}
+
+/// Find the backdoors & critical givens of `puzzle`.
+///
+/// Returns `None` if the puzzle does not have a unique solution.
+pub fn analyze(puzzle: &Puzzle) -> Option<Analysis> {
+    if !puzzle.has_unique_solution() {
+        return None;
+    }
+    let solution = puzzle.solve()?;
+    let singles = solves_with_singles(puzzle);
+    let mut backdoors = Vec::new();
+    let mut critical_givens = Vec::new();
+    for y in 0..9 {
+        for x in 0..9 {
+            let mut trial = *puzzle;
+            if puzzle.grid[y][x] == 0 {
+                if !singles {
+                    trial.grid[y][x] = solution[y][x];
+                    if solves_with_singles(&trial) {
+                        backdoors.push((y, x));
+                    }
+                }
+            } else {
+                trial.grid[y][x] = 0;
+                if !trial.has_unique_solution() {
+                    critical_givens.push((y, x));
+                }
+            }
+        }
+    }
+    Some(Analysis {
+        solution,
+        singles,
+        backdoors,
+        critical_givens,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::logic::{rate, Difficulty};
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_easy_puzzle() {
+        let puzzle = test_puzzle();
+        let analysis = analyze(&puzzle).unwrap();
+        assert!(analysis.singles);
+        assert!(analysis.backdoors.is_empty());
+        assert_eq!(Some(analysis.solution), puzzle.solve());
+        for y in 0..9 {
+            for x in 0..9 {
+                if puzzle[y][x] != 0 {
+                    let mut grid = puzzle.grid;
+                    grid[y][x] = 0;
+                    assert_eq!(
+                        analysis.critical_givens.contains(&(y, x)),
+                        !Puzzle::from_grid(grid).has_unique_solution()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_backdoors() {
+        // A minimal puzzle that needs more than singles
+        let puzzle = "..1.4....\n...961.3.\n3......47\n..2..8694\n.1.5.....\n......2..\n..4....7.\n12..5....\n.786.....\n"
+            .parse::<Puzzle>()
+            .unwrap();
+        assert_ne!(rate(&puzzle).difficulty, Difficulty::Easy);
+        let analysis = analyze(&puzzle).unwrap();
+        assert!(!analysis.singles);
+        assert_eq!(analysis.critical_givens.len(), 25);
+        assert!(analysis.backdoors.contains(&(0, 0)));
+        assert!(!analysis.backdoors.contains(&(0, 1)));
+        for y in 0..9 {
+            for x in 0..9 {
+                if puzzle[y][x] == 0 {
+                    let mut trial = puzzle;
+                    trial.grid[y][x] = analysis.solution[y][x];
+                    assert_eq!(
+                        analysis.backdoors.contains(&(y, x)),
+                        solves_with_singles(&trial)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        assert_eq!(analyze(&Puzzle::from_grid([[0; 9]; 9])), None);
+    }
+}
@@ -0,0 +1,303 @@
+//! Canonical forms of puzzles under the symmetries of Sudoku
+//!
+//! Two puzzles are equivalent if one can be turned into the other by some
+//! combination of relabeling the digits, transposing the grid, permuting the
+//! bands (groups of three rows) or stacks (groups of three columns), and
+//! permuting the rows within a band or the columns within a stack.  Even &
+//! odd shading moves with its cell, and a shaded puzzle may only be relabeled
+//! in ways that map even digits to even digits.  The canonical form of a
+//! puzzle is the equivalent puzzle that comes first when grids are compared
+//! cell by cell in row-major order, with unshaded cells sorting before even
+//! ones and even before odd, and unfilled cells sorting before digits with the
+//! same shading, and so two puzzles are equivalent if & only if their
+//! canonical forms are equal.
+use crate::{Parity, Puzzle};
+
+/// The permutations of `[0, 1, 2]`
+const PERMS3: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+impl Puzzle {
+    /// Return the canonical form of the puzzle: the equivalent puzzle that
+    /// comes first in row-major order when unfilled cells are treated as 0.
+    ///
+    /// Equivalent puzzles are those that can be obtained from each other by
+    /// relabeling the digits, transposing the grid, swapping bands or stacks,
+    /// or swapping rows within a band or columns within a stack.  Parity
+    /// shading is kept, and if the puzzle has any, only relabelings that map
+    /// even digits to even digits are allowed.
+    ///
+    /// This checks every one of the 3,359,232 rearrangements of the grid, so
+    /// it is fairly slow.
+    pub fn canonical_form(&self) -> Puzzle {
+        let orders = line_orders();
+        let shaded = self.parities.iter().flatten().any(Option::is_some);
+        // Each cell is encoded as its digit plus its shading in the high bits,
+        // so that the shading moves with the digit.
+        let mut cells = [[0; 9]; 9];
+        let mut transposed = [[0; 9]; 9];
+        for y in 0..9 {
+            for x in 0..9 {
+                let shading = match self.parities[y][x] {
+                    None => 0,
+                    Some(Parity::Even) => EVEN,
+                    Some(Parity::Odd) => ODD,
+                };
+                cells[y][x] = self.grid[y][x] | shading;
+                transposed[x][y] = cells[y][x];
+            }
+        }
+        let mut best = [u8::MAX; 81];
+        let mut candidate = [0; 81];
+        for grid in [&cells, &transposed] {
+            for rows in &orders {
+                for cols in &orders {
+                    if relabel_if_smaller(grid, rows, cols, shaded, &best, &mut candidate) {
+                        best = candidate;
+                    }
+                }
+            }
+        }
+        let mut grid = [[0; 9]; 9];
+        let mut parities = [[None; 9]; 9];
+        for (i, &c) in best.iter().enumerate() {
+            grid[i / 9][i % 9] = c & DIGIT;
+            parities[i / 9][i % 9] = match c & !DIGIT {
+                EVEN => Some(Parity::Even),
+                ODD => Some(Parity::Odd),
+                _ => None,
+            };
+        }
+        Puzzle::from_grid(grid).with_parities(parities)
+    }
+
+    /// Test whether `other` can be obtained from this puzzle by relabeling
+    /// the digits, transposing the grid, swapping bands or stacks, or
+    /// swapping rows within a band or columns within a stack, keeping any
+    /// parity shading and its constraints on the relabeling.
+    pub fn is_equivalent(&self, other: &Puzzle) -> bool {
+        // Cheap check first: equivalent puzzles have the same number of
+        // givens.
+        let count = |p: &Puzzle| p.grid.iter().flatten().filter(|&&d| d != 0).count();
+        count(self) == count(other) && self.canonical_form() == other.canonical_form()
+    }
+}
+
+/// The bits of an encoded cell holding its digit
+const DIGIT: u8 = 0x0F;
+
+/// The bit of an encoded cell marking it as shaded even
+const EVEN: u8 = 0x10;
+
+/// The bit of an encoded cell marking it as shaded odd
+const ODD: u8 = 0x20;
+
+/// Returns every order in which the rows (or columns) of a grid can be
+/// rearranged while keeping the rows of each band together: the bands are
+/// permuted, and then the rows within each band are permuted
+fn line_orders() -> Vec<[usize; 9]> {
+    let mut orders = Vec::with_capacity(6 * 6 * 6 * 6);
+    for bands in PERMS3 {
+        for p0 in PERMS3 {
+            for p1 in PERMS3 {
+                for p2 in PERMS3 {
+                    let mut order = [0; 9];
+                    for (i, within) in [p0, p1, p2].into_iter().enumerate() {
+                        for (j, &r) in within.iter().enumerate() {
+                            order[i * 3 + j] = bands[i] * 3 + r;
+                        }
+                    }
+                    orders.push(order);
+                }
+            }
+        }
+    }
+    orders
+}
+
+/// Rearrange `grid` into `candidate` by taking its rows & columns in the
+/// given orders and relabeling its digits in order of first appearance.  If
+/// `keep_parity` is true, even digits are relabeled as 2, 4, 6, 8 and odd
+/// digits as 1, 3, 5, 7, 9 instead.  Returns `true` if the result comes before `best`; the contents of
+/// `candidate` are unspecified if it does not.
+fn relabel_if_smaller(
+    grid: &[[u8; 9]; 9],
+    rows: &[usize; 9],
+    cols: &[usize; 9],
+    keep_parity: bool,
+    best: &[u8; 81],
+    candidate: &mut [u8; 81],
+) -> bool {
+    let mut labels = [0u8; 10];
+    // The next unused label for odd and even digits, respectively
+    let mut next = if keep_parity { [1, 2] } else { [1, 1] };
+    let step = if keep_parity { 2 } else { 1 };
+    let mut smaller = false;
+    for (i, c) in candidate.iter_mut().enumerate() {
+        let cell = grid[rows[i / 9]][cols[i % 9]];
+        let d = cell & DIGIT;
+        let label = if d == 0 {
+            0
+        } else {
+            let slot = &mut labels[usize::from(d)];
+            if *slot == 0 {
+                let class = if keep_parity {
+                    usize::from(d % 2 == 0)
+                } else {
+                    0
+                };
+                *slot = next[class];
+                next[class] += step;
+            }
+            *slot
+        };
+        *c = label | (cell & !DIGIT);
+        if !smaller {
+            match (*c).cmp(&best[i]) {
+                std::cmp::Ordering::Less => smaller = true,
+                std::cmp::Ordering::Equal => (),
+                std::cmp::Ordering::Greater => return false,
+            }
+        }
+    }
+    smaller
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_puzzle, TEST_SOLUTION};
+
+    /// Transpose the puzzle, swap its first two bands, swap the last two
+    /// columns of its middle stack, and relabel each digit d as 10 - d, which
+    /// preserves parity
+    fn scramble(puzzle: &Puzzle) -> Puzzle {
+        let mut grid = [[0; 9]; 9];
+        let mut parities = [[None; 9]; 9];
+        for y in 0..9 {
+            for x in 0..9 {
+                let sy = [3, 4, 5, 0, 1, 2, 6, 7, 8][y];
+                let sx = [0, 1, 2, 3, 5, 4, 6, 7, 8][x];
+                grid[y][x] = match puzzle[sx][sy] {
+                    0 => 0,
+                    d => 10 - d,
+                };
+                parities[y][x] = puzzle.parity(sx, sy);
+            }
+        }
+        Puzzle::try_from(grid).unwrap().with_parities(parities)
+    }
+
+    /// The test puzzle with a few unfilled cells shaded to match its solution
+    fn shaded_puzzle() -> Puzzle {
+        let mut parities = [[None; 9]; 9];
+        for (y, x) in [(0, 0), (0, 1), (4, 4)] {
+            parities[y][x] = Some(if TEST_SOLUTION[y][x] % 2 == 0 {
+                Parity::Even
+            } else {
+                Parity::Odd
+            });
+        }
+        test_puzzle().with_parities(parities)
+    }
+
+    #[test]
+    fn test_line_orders() {
+        let orders = line_orders();
+        assert_eq!(orders.len(), 1296);
+        assert_eq!(orders[0], [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert!(orders.contains(&[8, 6, 7, 0, 2, 1, 4, 3, 5]));
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let puzzle = test_puzzle();
+        let canon = puzzle.canonical_form();
+        assert_eq!(canon.canonical_form(), canon);
+        assert_eq!(scramble(&puzzle).canonical_form(), canon);
+        assert_eq!(
+            canon.to_string(),
+            concat!(
+                "000000000\n",
+                "001002003\n",
+                "240560780\n",
+                "000000000\n",
+                "002003007\n",
+                "350680940\n",
+                "000070050\n",
+                "520308406\n",
+                "710405208",
+            )
+        );
+    }
+
+    #[test]
+    fn test_is_equivalent() {
+        let puzzle = test_puzzle();
+        assert!(puzzle.is_equivalent(&scramble(&puzzle)));
+        let mut grid = puzzle.grid;
+        grid[0][0] = 4;
+        assert!(!puzzle.is_equivalent(&Puzzle::try_from(grid).unwrap()));
+        grid[0][2] = 0;
+        assert!(!puzzle.is_equivalent(&Puzzle::try_from(grid).unwrap()));
+    }
+
+    #[test]
+    fn test_canonical_form_shaded() {
+        let puzzle = shaded_puzzle();
+        let canon = puzzle.canonical_form();
+        assert_eq!(canon.canonical_form(), canon);
+        assert_eq!(scramble(&puzzle).canonical_form(), canon);
+        let shading = |p: &Puzzle| {
+            let mut counts = [0; 2];
+            for y in 0..9 {
+                for x in 0..9 {
+                    match p.parity(y, x) {
+                        Some(Parity::Even) => counts[0] += 1,
+                        Some(Parity::Odd) => counts[1] += 1,
+                        None => (),
+                    }
+                }
+            }
+            counts
+        };
+        assert_eq!(shading(&canon), shading(&puzzle));
+        for y in 0..9 {
+            for x in 0..9 {
+                if let Some(p) = canon.parity(y, x) {
+                    assert_eq!(canon[y][x], 0, "shaded cells should stay unfilled");
+                    assert!(
+                        canon.solve().is_some_and(|s| p.allows(s[y][x])),
+                        "shading should still agree with the solution"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_equivalent_shaded() {
+        let puzzle = shaded_puzzle();
+        assert!(puzzle.is_equivalent(&scramble(&puzzle)));
+        assert!(!puzzle.is_equivalent(&test_puzzle()));
+        // Swapping 1 and 2 would be allowed without the shading, but does not
+        // preserve parity
+        let mut grid = puzzle.grid;
+        for d in grid.iter_mut().flatten() {
+            *d = match *d {
+                1 => 2,
+                2 => 1,
+                d => d,
+            };
+        }
+        let swapped = Puzzle::try_from(grid).unwrap();
+        assert!(test_puzzle().is_equivalent(&swapped));
+        assert!(!puzzle.is_equivalent(&swapped.with_parities(puzzle.parities)));
+    }
+}
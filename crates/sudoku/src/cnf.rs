@@ -0,0 +1,453 @@
+//! Encoding puzzles as SAT problems in DIMACS CNF
+//!
+//! A [`Cnf`] is a Boolean formula in conjunctive normal form whose satisfying
+//! assignments correspond to the solutions of a puzzle.  Variable
+//! `81 * y + 9 * x + d` (for zero-based row `y` & column `x` and digit `d`)
+//! is true if & only if the cell at `(y, x)` contains `d`; variables beyond
+//! 729 are auxiliaries introduced by the encodings of variant constraints.
+//!
+//! The formula can be written out in the DIMACS CNF format via its
+//! [`Display`](std::fmt::Display) implementation and handed to any SAT
+//! solver, and the model printed by the solver can be turned back into a
+//! [`Solution`] with [`parse_model()`].
+use crate::constraints::Constraint;
+use crate::{Puzzle, Solution, TryIntoSolutionError};
+use std::fmt;
+use thiserror::Error;
+
+/// The number of variables standing for placements of digits in cells
+const CELL_VARIABLES: usize = 729;
+
+const DIGITS: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+/// A formula in conjunctive normal form encoding a puzzle
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Cnf {
+    variables: usize,
+    clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    /// Encode the rules of Sudoku together with the givens & parity shading
+    /// of `puzzle`
+    pub fn new(puzzle: &Puzzle) -> Cnf {
+        let mut cnf = Cnf {
+            variables: CELL_VARIABLES,
+            clauses: Vec::new(),
+        };
+        for y in 0..9 {
+            for x in 0..9 {
+                // Every cell contains exactly one digit:
+                cnf.add_clause((1..=9).map(|d| Cnf::variable(y, x, d)));
+                for d in 1..=9 {
+                    for e in (d + 1)..=9 {
+                        cnf.add_clause([-Cnf::variable(y, x, d), -Cnf::variable(y, x, e)]);
+                    }
+                }
+                match puzzle[y][x] {
+                    0 => {
+                        if let Some(p) = puzzle.parity(y, x) {
+                            for d in (1..=9).filter(|&d| !p.allows(d)) {
+                                cnf.add_clause([-Cnf::variable(y, x, d)]);
+                            }
+                        }
+                    }
+                    d => cnf.add_clause([Cnf::variable(y, x, d)]),
+                }
+            }
+        }
+        for i in 0..9 {
+            let row = (0..9).map(|x| (i, x)).collect::<Vec<_>>();
+            let column = (0..9).map(|y| (y, i)).collect::<Vec<_>>();
+            let bx = (0..9)
+                .map(|j| ((i / 3) * 3 + j / 3, (i % 3) * 3 + j % 3))
+                .collect::<Vec<_>>();
+            for unit in [row, column, bx] {
+                // Every digit appears in every unit exactly once:
+                for d in 1..=9 {
+                    cnf.add_clause(unit.iter().map(|&(y, x)| Cnf::variable(y, x, d)));
+                }
+                cnf.add_distinct(&unit);
+            }
+        }
+        cnf
+    }
+
+    /// Add clauses encoding the given variant constraints to the formula
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the constraints do not support being encoded in CNF.
+    pub fn with_constraints(
+        mut self,
+        constraints: &[Box<dyn Constraint>],
+    ) -> Result<Cnf, UnsupportedConstraint> {
+        for c in constraints {
+            c.encode(&mut self)?;
+        }
+        Ok(self)
+    }
+
+    /// Return the variable that is true if & only if the cell at `(y, x)`
+    /// contains `d`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9 or if `d` is not in `1..=9`.
+    pub fn variable(y: usize, x: usize, d: u8) -> i32 {
+        assert!(
+            y < 9 && x < 9 && (1..=9).contains(&d),
+            "cell and digit should be within the grid"
+        );
+        i32::try_from(81 * y + 9 * x + usize::from(d))
+            .expect("cell variables should be at most 729")
+    }
+
+    /// Allocate a new auxiliary variable and return it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of variables would exceed [`i32::MAX`].
+    pub fn new_variable(&mut self) -> i32 {
+        self.variables += 1;
+        i32::try_from(self.variables).expect("number of variables should fit in an i32")
+    }
+
+    /// Add a clause — a disjunction of literals, where a positive literal `v`
+    /// means that variable `v` is true and a negative literal `-v` means that
+    /// it is false — to the formula.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any literal is 0 or refers to a variable that has not been
+    /// allocated.
+    pub fn add_clause<I: IntoIterator<Item = i32>>(&mut self, clause: I) {
+        let clause = clause.into_iter().collect::<Vec<_>>();
+        assert!(
+            clause.iter().all(|&lit| lit != 0
+                && usize::try_from(lit.unsigned_abs()).is_ok_and(|v| v <= self.variables)),
+            "literals should refer to allocated variables"
+        );
+        self.clauses.push(clause);
+    }
+
+    /// Add clauses forbidding any digit from appearing more than once among
+    /// `cells`
+    pub fn add_distinct(&mut self, cells: &[(usize, usize)]) {
+        for (i, &(ay, ax)) in cells.iter().enumerate() {
+            for &(by, bx) in &cells[(i + 1)..] {
+                for d in 1..=9 {
+                    self.add_clause([-Cnf::variable(ay, ax, d), -Cnf::variable(by, bx, d)]);
+                }
+            }
+        }
+    }
+
+    /// Add clauses forbidding every pair of digits in cells `a` and `b` for
+    /// which `allowed` returns `false`
+    pub fn add_pair_rule<F: Fn(u8, u8) -> bool>(
+        &mut self,
+        a: (usize, usize),
+        b: (usize, usize),
+        allowed: F,
+    ) {
+        for d in 1..=9 {
+            for e in 1..=9 {
+                if !allowed(d, e) {
+                    self.add_clause([-Cnf::variable(a.0, a.1, d), -Cnf::variable(b.0, b.1, e)]);
+                }
+            }
+        }
+    }
+
+    /// Add clauses requiring at least one of `options` to hold in full, where
+    /// each option is a list of cells paired with the sets of digits (as
+    /// bitmasks with bit `d` set for each digit `d`) that they may contain
+    /// under that option.  An auxiliary variable is allocated for each
+    /// option.
+    pub fn add_options(&mut self, options: &[Vec<((usize, usize), u16)>]) {
+        let mut chosen = Vec::with_capacity(options.len());
+        for option in options {
+            let z = self.new_variable();
+            chosen.push(z);
+            for &((y, x), mask) in option {
+                let allowed = (1..=9)
+                    .filter(|&d| mask & (1 << d) != 0)
+                    .map(|d| Cnf::variable(y, x, d));
+                self.add_clause(std::iter::once(-z).chain(allowed));
+            }
+        }
+        self.add_clause(chosen);
+    }
+
+    /// Return the number of variables in the formula
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// Return the clauses of the formula
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+}
+
+/// The DIMACS CNF representation of the formula
+impl fmt::Display for Cnf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "c Variable 81*(r-1) + 9*(c-1) + d: row r, column c contains d"
+        )?;
+        writeln!(f, "p cnf {} {}", self.variables, self.clauses.len())?;
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(f, "{lit} ")?;
+            }
+            writeln!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when trying to encode a [`Constraint`] that does not
+/// support being encoded in CNF
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+#[error("constraint cannot be encoded in CNF")]
+pub struct UnsupportedConstraint;
+
+/// Parse the model output by a SAT solver for a [`Cnf`] and return the
+/// solution that it represents.
+///
+/// Both the SAT competition output format (`s SATISFIABLE` followed by lines
+/// of literals starting with `v`) and the MiniSat output format (`SAT`
+/// followed by a line of literals) are accepted.  Comment lines starting with
+/// `c` are ignored, as are auxiliary variables.
+///
+/// # Errors
+///
+/// Fails if the solver reported the formula as unsatisfiable, if the model
+/// contains anything other than integer literals, or if the model does not
+/// describe a valid solution.
+pub fn parse_model(s: &str) -> Result<Solution, ParseModelError> {
+    let mut literals = Vec::new();
+    for line in s.lines() {
+        let mut words = line.split_whitespace().peekable();
+        match words.peek() {
+            None | Some(&"c") => continue,
+            Some(&"s") => {
+                words.next();
+                if words.next() == Some("UNSATISFIABLE") {
+                    return Err(ParseModelError::Unsatisfiable);
+                }
+                continue;
+            }
+            Some(&"UNSAT") => return Err(ParseModelError::Unsatisfiable),
+            Some(&"SAT") => continue,
+            Some(&"v") => {
+                words.next();
+            }
+            Some(_) => (),
+        }
+        for w in words {
+            let lit = w
+                .parse::<i32>()
+                .map_err(|_| ParseModelError::BadLiteral(w.to_owned()))?;
+            literals.push(lit);
+        }
+    }
+    decode_model(&literals)
+}
+
+/// Return the solution represented by a satisfying assignment for a [`Cnf`],
+/// given as a list of literals that are true.  Literals for auxiliary
+/// variables and zeroes are ignored.
+///
+/// # Errors
+///
+/// Fails if some cell is not assigned exactly one digit or if the resulting
+/// grid is not a valid solution.
+pub fn decode_model(literals: &[i32]) -> Result<Solution, ParseModelError> {
+    let mut grid = [[0; 9]; 9];
+    for &lit in literals {
+        let Ok(v) = usize::try_from(lit) else {
+            continue;
+        };
+        if !(1..=CELL_VARIABLES).contains(&v) {
+            continue;
+        }
+        let (cell, d) = ((v - 1) / 9, DIGITS[(v - 1) % 9]);
+        let (y, x) = (cell / 9, cell % 9);
+        if grid[y][x] != 0 {
+            return Err(ParseModelError::MultipleDigits(y, x));
+        }
+        grid[y][x] = d;
+    }
+    for (y, row) in grid.iter().enumerate() {
+        if let Some(x) = row.iter().position(|&d| d == 0) {
+            return Err(ParseModelError::MissingDigit(y, x));
+        }
+    }
+    Ok(Solution::try_from(grid)?)
+}
+
+/// Error returned when a SAT solver's model cannot be turned into a
+/// [`Solution`]
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ParseModelError {
+    /// Returned when the solver reported that the formula has no solution
+    #[error("formula is unsatisfiable")]
+    Unsatisfiable,
+
+    /// Returned when the model contains something other than an integer
+    /// literal
+    #[error("invalid literal {0:?} in model")]
+    BadLiteral(String),
+
+    /// Returned when the model places more than one digit in a cell.  The
+    /// arguments are the row & column of the cell.
+    #[error("model places more than one digit in r{}c{}", .0 + 1, .1 + 1)]
+    MultipleDigits(usize, usize),
+
+    /// Returned when the model does not place a digit in a cell.  The
+    /// arguments are the row & column of the cell.
+    #[error("model does not place a digit in r{}c{}", .0 + 1, .1 + 1)]
+    MissingDigit(usize, usize),
+
+    /// Returned when the grid described by the model breaks the rules of
+    /// Sudoku
+    #[error(transparent)]
+    Invalid(#[from] TryIntoSolutionError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constraints::{Inequality, Thermometer};
+    use crate::test_puzzle;
+
+    /// Return the literals describing a filled grid
+    fn model(grid: &[[u8; 9]; 9]) -> Vec<i32> {
+        let mut model = Vec::new();
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                for d in 1..=9 {
+                    let v = Cnf::variable(y, x, d);
+                    model.push(if c == d { v } else { -v });
+                }
+            }
+        }
+        model
+    }
+
+    fn satisfies(cnf: &Cnf, model: &[i32]) -> bool {
+        cnf.clauses()
+            .iter()
+            .all(|clause| clause.iter().any(|lit| model.contains(lit)))
+    }
+
+    #[test]
+    fn test_variable() {
+        assert_eq!(Cnf::variable(0, 0, 1), 1);
+        assert_eq!(Cnf::variable(0, 1, 1), 10);
+        assert_eq!(Cnf::variable(1, 0, 1), 82);
+        assert_eq!(Cnf::variable(8, 8, 9), 729);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let cnf = Cnf::new(&puzzle);
+        assert_eq!(cnf.variables(), 729);
+        assert!(satisfies(&cnf, &model(&solution)));
+        // Swapping two digits in a row breaks the column rule:
+        let mut grid = *solution;
+        grid[0].swap(0, 1);
+        assert!(!satisfies(&cnf, &model(&grid)));
+        let text = cnf.to_string();
+        assert!(text.starts_with(&format!(
+            "c Variable 81*(r-1) + 9*(c-1) + d: row r, column c contains d\np cnf 729 {}\n",
+            cnf.clauses().len()
+        )));
+        // The given 3 in r1c3:
+        assert!(text.contains("\n21 0\n"));
+    }
+
+    #[test]
+    fn test_constraints() {
+        let solution = test_puzzle().solve().unwrap();
+        // r1c1 = 4, r1c2 = 8, r1c3 = 3
+        let holds: Vec<Box<dyn Constraint>> = vec![
+            Box::new(Thermometer::new(vec![(0, 0), (0, 1)]).unwrap()),
+            Box::new(Inequality::new((0, 2), (0, 1)).unwrap()),
+        ];
+        let cnf = Cnf::new(&test_puzzle()).with_constraints(&holds).unwrap();
+        assert!(satisfies(&cnf, &model(&solution)));
+        let fails: Vec<Box<dyn Constraint>> =
+            vec![Box::new(Thermometer::new(vec![(0, 1), (0, 2)]).unwrap())];
+        let cnf = Cnf::new(&test_puzzle()).with_constraints(&fails).unwrap();
+        assert!(!satisfies(&cnf, &model(&solution)));
+    }
+
+    #[test]
+    fn test_unsupported_constraint() {
+        #[derive(Debug)]
+        struct Custom;
+
+        impl Constraint for Custom {
+            fn check(&self, _grid: &[[u8; 9]; 9]) -> bool {
+                true
+            }
+        }
+
+        let constraints: Vec<Box<dyn Constraint>> = vec![Box::new(Custom)];
+        assert_eq!(
+            Cnf::new(&test_puzzle()).with_constraints(&constraints),
+            Err(UnsupportedConstraint)
+        );
+    }
+
+    #[test]
+    fn test_parse_model() {
+        let solution = test_puzzle().solve().unwrap();
+        let literals = model(&solution)
+            .into_iter()
+            .map(|lit| lit.to_string())
+            .collect::<Vec<_>>();
+        let competition = format!(
+            "c solved\ns SATISFIABLE\nv {}\nv {} 730 -731 0\n",
+            literals[..400].join(" "),
+            literals[400..].join(" ")
+        );
+        assert_eq!(parse_model(&competition), Ok(solution));
+        let minisat = format!("SAT\n{} 0\n", literals.join(" "));
+        assert_eq!(parse_model(&minisat), Ok(solution));
+    }
+
+    #[test]
+    fn test_parse_model_errors() {
+        assert_eq!(
+            parse_model("s UNSATISFIABLE\n"),
+            Err(ParseModelError::Unsatisfiable)
+        );
+        assert_eq!(parse_model("UNSAT\n"), Err(ParseModelError::Unsatisfiable));
+        assert_eq!(
+            parse_model("v 1 -2 x 0\n"),
+            Err(ParseModelError::BadLiteral(String::from("x")))
+        );
+        assert_eq!(
+            parse_model("v 1 2 0\n"),
+            Err(ParseModelError::MultipleDigits(0, 0))
+        );
+        assert_eq!(
+            parse_model("v 1 0\n"),
+            Err(ParseModelError::MissingDigit(0, 1))
+        );
+        let mut grid = *test_puzzle().solve().unwrap();
+        grid[0].swap(0, 1);
+        assert!(matches!(
+            decode_model(&model(&grid)),
+            Err(ParseModelError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,540 @@
+//! Greater-than (comparison) Sudoku puzzles
+//!
+//! In a comparison puzzle, inequality markers are drawn on the borders
+//! between some pairs of adjacent cells, each stating which of the two cells
+//! holds the greater digit.
+use crate::constraints::{Constraint, Inequality};
+use crate::{ParsePuzzleError, Puzzle, Solution};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An unsolved greater-than Sudoku puzzle: a [`Puzzle`] together with a set
+/// of [`Inequality`] markers.
+///
+/// `ComparisonPuzzle` instances can be constructed with
+/// [`ComparisonPuzzle::new()`] or from a string using
+/// [`FromStr`]/[`str::parse()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ComparisonPuzzle {
+    puzzle: Puzzle,
+    inequalities: Vec<Inequality>,
+}
+
+impl ComparisonPuzzle {
+    /// Construct a comparison puzzle from a grid and its inequality markers
+    pub fn new(puzzle: Puzzle, inequalities: Vec<Inequality>) -> ComparisonPuzzle {
+        ComparisonPuzzle {
+            puzzle,
+            inequalities,
+        }
+    }
+
+    /// Return the puzzle's grid
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    /// Return the puzzle's inequality markers
+    pub fn inequalities(&self) -> &[Inequality] {
+        &self.inequalities
+    }
+
+    /// Solve the puzzle.
+    ///
+    /// If the puzzle has multiple solutions, one of them is returned, but
+    /// which one is unspecified.
+    ///
+    /// If the puzzle has no solutions, `None` is returned.
+    pub fn solve(&self) -> Option<Solution> {
+        let mut constraints: Vec<Box<dyn Constraint>> = Vec::with_capacity(self.inequalities.len());
+        for &i in &self.inequalities {
+            constraints.push(Box::new(i));
+        }
+        self.puzzle.solve_with(&constraints)
+    }
+
+    /// Returns the marker to draw between cell `(y, x)` and the cell to its
+    /// right
+    fn horizontal(&self, y: usize, x: usize) -> Option<char> {
+        self.marker((y, x), (y, x + 1), '<', '>')
+    }
+
+    /// Returns the marker to draw between cell `(y, x)` and the cell below it
+    fn vertical(&self, y: usize, x: usize) -> Option<char> {
+        self.marker((y, x), (y + 1, x), '^', 'v')
+    }
+
+    fn marker(
+        &self,
+        first: (usize, usize),
+        second: (usize, usize),
+        less: char,
+        greater: char,
+    ) -> Option<char> {
+        self.inequalities.iter().find_map(|i| {
+            if (i.lesser(), i.greater()) == (first, second) {
+                Some(less)
+            } else if (i.lesser(), i.greater()) == (second, first) {
+                Some(greater)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Parse a [`ComparisonPuzzle`] from a string.
+///
+/// The format extends that accepted by [`Puzzle`]'s [`FromStr`]
+/// implementation: nine lines of nine cells each, where each cell is either a
+/// digit in `0..=9` (0 representing an "unfilled" cell) or any other
+/// non-space character (also representing an "unfilled" cell), with the
+/// following additions:
+///
+/// - A `<` or `>` between two cells of a row marks the left cell as less than
+///   or greater than the right cell, respectively.
+///
+/// - A line between two rows of cells may contain `^` and `v` characters
+///   placed in the same column as a cell of the row above, marking that cell
+///   as less than (`^`) or greater than (`v`) the cell below it.  These
+///   characters may not appear on a line of cells.
+///
+/// - The characters `|`, `+`, and `-` may be used to draw borders; `|` is
+///   ignored in rows of cells, and lines made up of only border characters,
+///   `^`, and `v` are not treated as rows of cells.
+///
+/// Horizontal whitespace and blank lines are ignored.
+///
+/// For example:
+///
+/// ```text
+/// . < . > .   . . .   . . .
+/// ^       v
+/// . . .   . . .   . . .
+/// ```
+///
+/// marks cell (0, 0) as less than both cell (0, 1) and cell (1, 0), and cell
+/// (0, 1) as greater than cell (0, 2), which is in turn greater than cell
+/// (1, 2).
+///
+/// # Errors
+///
+/// Fails if the grid of cells is not exactly 9×9 or if a marker is not placed
+/// between two cells.  The error reports the line & column at which the
+/// problem was found.
+impl FromStr for ComparisonPuzzle {
+    type Err = ParseComparisonError;
+
+    fn from_str(s: &str) -> Result<ComparisonPuzzle, ParseComparisonError> {
+        // The cells are handed to the `Puzzle` parser with markers & borders
+        // replaced by whitespace and marker lines left blank, so that any
+        // errors it reports point into the original text.
+        let mut text = String::with_capacity(s.len());
+        let mut columns: Vec<Vec<usize>> = Vec::with_capacity(9);
+        // (line number, column number, marker, lesser, greater)
+        let mut pending = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let lineno = i + 1;
+            let misplaced = |col: usize, marker: char| ParseComparisonError::MisplacedMarker {
+                line: lineno,
+                column: col + 1,
+                marker,
+            };
+            if line
+                .chars()
+                .all(|c| c.is_whitespace() || "|+-^v".contains(c))
+            {
+                for (col, c) in line.chars().enumerate() {
+                    if c != '^' && c != 'v' {
+                        continue;
+                    }
+                    let Some(y) = columns.len().checked_sub(1) else {
+                        return Err(misplaced(col, c));
+                    };
+                    let Some(x) = columns[y].iter().position(|&cc| cc == col) else {
+                        return Err(misplaced(col, c));
+                    };
+                    let (upper, lower) = ((y, x), (y + 1, x));
+                    if c == '^' {
+                        pending.push((lineno, col + 1, c, upper, lower));
+                    } else {
+                        pending.push((lineno, col + 1, c, lower, upper));
+                    }
+                }
+                text.push('\n');
+                continue;
+            }
+            let y = columns.len();
+            let mut cols = Vec::with_capacity(9);
+            let mut marker = None;
+            for (col, c) in line.chars().enumerate() {
+                if c.is_whitespace() || c == '|' {
+                    text.push(' ');
+                    continue;
+                }
+                if c == '^' || c == 'v' {
+                    // Vertical markers belong on lines of their own.
+                    return Err(misplaced(col, c));
+                }
+                if c == '<' || c == '>' {
+                    if cols.is_empty() || marker.is_some() {
+                        return Err(misplaced(col, c));
+                    }
+                    marker = Some((col, c));
+                    text.push(' ');
+                    continue;
+                }
+                if let Some((mcol, m)) = marker.take() {
+                    let (left, right) = ((y, cols.len() - 1), (y, cols.len()));
+                    if m == '<' {
+                        pending.push((lineno, mcol + 1, m, left, right));
+                    } else {
+                        pending.push((lineno, mcol + 1, m, right, left));
+                    }
+                }
+                text.push(c);
+                cols.push(col);
+            }
+            if let Some((mcol, m)) = marker {
+                return Err(misplaced(mcol, m));
+            }
+            text.push('\n');
+            columns.push(cols);
+        }
+        let puzzle = text.parse::<Puzzle>().map_err(|e| e.with_snippet_from(s))?;
+        let inequalities = pending
+            .into_iter()
+            .map(|(line, column, marker, lesser, greater)| {
+                Inequality::new(lesser, greater).map_err(|_| {
+                    ParseComparisonError::MisplacedMarker {
+                        line,
+                        column,
+                        marker,
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ComparisonPuzzle {
+            puzzle,
+            inequalities,
+        })
+    }
+}
+
+/// Display a [`ComparisonPuzzle`] as nine lines of nine cells with its
+/// inequality markers.
+///
+/// In the default representation, "unfilled" cells are represented by `0`
+/// (or by `e` or `o` if shaded; see [`Puzzle::parity()`]), adjacent cells in
+/// a row are separated by a space or a `<`/`>` marker, and
+/// vertical markers are written on lines of their own between the rows of
+/// cells, in the format accepted by [`ComparisonPuzzle`]'s [`FromStr`]
+/// implementation, e.g.:
+///
+/// ```text
+/// 0<0>3 0 2 0 6 0 0
+/// ^   v
+/// 9 0 0 3 0 5 0 0 1
+/// ...
+/// ```
+///
+/// In the alternate representation (selected with the `#` modifier), the grid
+/// is drawn in the same style as the alternate representation of [`Puzzle`],
+/// but with a line between each pair of rows, and with each marker drawn in
+/// place of the space or border character between the two cells, e.g.:
+///
+/// ```text
+/// +-----+-----+-----+
+/// | < >3|  2  |6    |
+/// |^   v|     |     |
+/// |9    |3   5|    1|
+/// ...
+/// ```
+///
+/// Both forms lack a final terminating newline.
+impl fmt::Display for ComparisonPuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            for y in 0..9_usize {
+                if y % 3 == 0 {
+                    let mut divider = String::from("+");
+                    for x in 0..9 {
+                        let m = y.checked_sub(1).and_then(|y| self.vertical(y, x));
+                        divider.push(m.unwrap_or('-'));
+                        divider.push(if x % 3 == 2 { '+' } else { '-' });
+                    }
+                    writeln!(f, "{divider}")?;
+                } else {
+                    let mut line = String::from("|");
+                    for x in 0..9 {
+                        line.push(self.vertical(y - 1, x).unwrap_or(' '));
+                        line.push(if x % 3 == 2 { '|' } else { ' ' });
+                    }
+                    writeln!(f, "{line}")?;
+                }
+                write!(f, "|")?;
+                for x in 0..9 {
+                    match (self.puzzle[y][x], self.puzzle.parity(y, x)) {
+                        (0, Some(p)) => write!(f, "{}", p.to_char())?,
+                        (0, None) => write!(f, " ")?,
+                        (c, _) => write!(f, "{c}")?,
+                    }
+                    let sep = if x % 3 == 2 { '|' } else { ' ' };
+                    let m = if x < 8 { self.horizontal(y, x) } else { None };
+                    write!(f, "{}", m.unwrap_or(sep))?;
+                }
+                writeln!(f)?;
+            }
+            write!(f, "+-----+-----+-----+")?;
+        } else {
+            for y in 0..9 {
+                if y > 0 {
+                    writeln!(f)?;
+                    let mut line = String::new();
+                    for x in 0..9 {
+                        line.push(self.vertical(y - 1, x).unwrap_or(' '));
+                        line.push(' ');
+                    }
+                    if !line.trim_end().is_empty() {
+                        writeln!(f, "{}", line.trim_end())?;
+                    }
+                }
+                for x in 0..9 {
+                    if x > 0 {
+                        write!(f, "{}", self.horizontal(y, x - 1).unwrap_or(' '))?;
+                    }
+                    match (self.puzzle[y][x], self.puzzle.parity(y, x)) {
+                        (0, Some(p)) => write!(f, "{}", p.to_char())?,
+                        (c, _) => write!(f, "{c}")?,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error type returned when parsing an invalid [`ComparisonPuzzle`]
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ParseComparisonError {
+    /// Returned when the grid of cells is invalid
+    #[error(transparent)]
+    Grid(#[from] ParsePuzzleError),
+
+    /// Returned when an inequality marker is not placed between two adjacent
+    /// cells, or when a `^` or `v` marker appears on a line of cells
+    #[error("line {line}, column {column}: marker {marker:?} is not between two cells")]
+    MisplacedMarker {
+        /// The (1-based) line number on which the marker appears
+        line: usize,
+        /// The (1-based) column, counted in characters, at which the marker
+        /// appears
+        column: usize,
+        /// The marker character
+        marker: char,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_puzzle, Parity, ParsePuzzleErrorKind, TEST_SOLUTION};
+
+    fn ordered(a: (usize, usize), b: (usize, usize)) -> Inequality {
+        if TEST_SOLUTION[a.0][a.1] < TEST_SOLUTION[b.0][b.1] {
+            Inequality::new(a, b).unwrap()
+        } else {
+            Inequality::new(b, a).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_parse_comparison() {
+        let s = concat!(
+            ". < . > 3   . 2 .   6 . .\n",
+            "^       v\n",
+            "9 . .   3 . 5   . . 1\n",
+            ". . 1   8 . 6   4 . .\n",
+            "\n",
+            ". . 8   1 . 2   9 . .\n",
+            "7 . .   . . .   . . 8\n",
+            ". . 6   7 . 8   2 . .\n",
+            "\n",
+            ". . 2   6 . 9   5 . .\n",
+            "8 . .   2 . 3   . . 9\n",
+            ". . 5   . 1 .   3 . .\n",
+        );
+        let puzzle = s.parse::<ComparisonPuzzle>().unwrap();
+        assert_eq!(puzzle.puzzle()[0], [0, 0, 3, 0, 2, 0, 6, 0, 0]);
+        assert_eq!(
+            puzzle.inequalities(),
+            [
+                Inequality::new((0, 0), (0, 1)).unwrap(),
+                Inequality::new((0, 2), (0, 1)).unwrap(),
+                Inequality::new((0, 0), (1, 0)).unwrap(),
+                Inequality::new((1, 2), (0, 2)).unwrap(),
+            ]
+        );
+        assert_eq!(
+            puzzle.to_string(),
+            concat!(
+                "0<0>3 0 2 0 6 0 0\n",
+                "^   v\n",
+                "9 0 0 3 0 5 0 0 1\n",
+                "0 0 1 8 0 6 4 0 0\n",
+                "0 0 8 1 0 2 9 0 0\n",
+                "7 0 0 0 0 0 0 0 8\n",
+                "0 0 6 7 0 8 2 0 0\n",
+                "0 0 2 6 0 9 5 0 0\n",
+                "8 0 0 2 0 3 0 0 9\n",
+                "0 0 5 0 1 0 3 0 0",
+            )
+        );
+        assert_eq!(puzzle.to_string().parse::<ComparisonPuzzle>(), Ok(puzzle));
+    }
+
+    #[test]
+    fn test_parity_round_trip() {
+        let s = format!("e<0 0 0 0 0 0 0 o\n{}", "0 0 0 0 0 0 0 0 0\n".repeat(8));
+        let puzzle = s.parse::<ComparisonPuzzle>().unwrap();
+        assert_eq!(puzzle.puzzle().parity(0, 0), Some(Parity::Even));
+        assert_eq!(puzzle.puzzle().parity(0, 8), Some(Parity::Odd));
+        assert!(puzzle.to_string().starts_with("e<0 0 0 0 0 0 0 o\n"));
+        assert_eq!(
+            puzzle.to_string().parse::<ComparisonPuzzle>(),
+            Ok(puzzle.clone())
+        );
+        assert!(format!("{puzzle:#}").contains("\n|e<   |     |    o|\n"));
+    }
+
+    #[test]
+    fn test_parse_misplaced_marker() {
+        let s = format!("<{}", "000000000\n".repeat(9));
+        assert_eq!(
+            s.parse::<ComparisonPuzzle>(),
+            Err(ParseComparisonError::MisplacedMarker {
+                line: 1,
+                column: 1,
+                marker: '<'
+            })
+        );
+        let s = format!("{}   v\n", "0 0 0 0 0 0 0 0 0\n".repeat(9));
+        assert_eq!(
+            s.parse::<ComparisonPuzzle>(),
+            Err(ParseComparisonError::MisplacedMarker {
+                line: 10,
+                column: 4,
+                marker: 'v'
+            })
+        );
+        let s = format!(
+            "0 0 0 0 0 0 0 0 0\n   ^\n{}",
+            "0 0 0 0 0 0 0 0 0\n".repeat(8)
+        );
+        let e = s.parse::<ComparisonPuzzle>().unwrap_err();
+        assert_eq!(
+            e,
+            ParseComparisonError::MisplacedMarker {
+                line: 2,
+                column: 4,
+                marker: '^'
+            }
+        );
+        assert_eq!(
+            e.to_string(),
+            "line 2, column 4: marker '^' is not between two cells"
+        );
+    }
+
+    #[test]
+    fn test_parse_marker_on_cell_line() {
+        let s = format!("0 0 ^ 0 0 0 0 0 0\n{}", "0 0 0 0 0 0 0 0 0\n".repeat(8));
+        assert_eq!(
+            s.parse::<ComparisonPuzzle>(),
+            Err(ParseComparisonError::MisplacedMarker {
+                line: 1,
+                column: 5,
+                marker: '^'
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_comparison_bad_grid() {
+        let s = concat!("0<0 0 0 0 0 0 0 0\n", "^\n", "0 0 0 | 0 0 0 | 0 0\n",);
+        let Err(ParseComparisonError::Grid(e)) = s.parse::<ComparisonPuzzle>() else {
+            panic!("short row should be a grid error");
+        };
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::BadRowSize(8));
+        assert_eq!((e.line(), e.column()), (3, 20));
+        assert_eq!(e.snippet(), "0 0 0 | 0 0 0 | 0 0");
+        assert_eq!(
+            "000000000\n"
+                .parse::<ComparisonPuzzle>()
+                .unwrap_err()
+                .to_string(),
+            "line 2, column 1: grid is only 1 rows long, expected 9"
+        );
+    }
+
+    #[test]
+    fn test_display_pretty_comparison() {
+        let puzzle = ComparisonPuzzle::new(
+            test_puzzle(),
+            vec![
+                Inequality::new((0, 0), (0, 1)).unwrap(),
+                Inequality::new((0, 3), (0, 2)).unwrap(),
+                Inequality::new((0, 0), (1, 0)).unwrap(),
+                Inequality::new((3, 8), (2, 8)).unwrap(),
+            ],
+        );
+        assert_eq!(
+            format!("{puzzle:#}"),
+            concat!(
+                "+-----+-----+-----+\n",
+                "| <  3>  2  |6    |\n",
+                "|^    |     |     |\n",
+                "|9    |3   5|    1|\n",
+                "|     |     |     |\n",
+                "|    1|8   6|4    |\n",
+                "+-----+-----+----v+\n",
+                "|    8|1   2|9    |\n",
+                "|     |     |     |\n",
+                "|7    |     |    8|\n",
+                "|     |     |     |\n",
+                "|    6|7   8|2    |\n",
+                "+-----+-----+-----+\n",
+                "|    2|6   9|5    |\n",
+                "|     |     |     |\n",
+                "|8    |2   3|    9|\n",
+                "|     |     |     |\n",
+                "|    5|  1  |3    |\n",
+                "+-----+-----+-----+",
+            )
+        );
+    }
+
+    #[test]
+    fn test_solve_comparison() {
+        // A full set of markers within each box, as in a classic greater-than
+        // Sudoku, with no givens at all
+        let mut inequalities = Vec::new();
+        for y in 0..9 {
+            for x in 0..9 {
+                if x % 3 != 2 {
+                    inequalities.push(ordered((y, x), (y, x + 1)));
+                }
+                if y % 3 != 2 {
+                    inequalities.push(ordered((y, x), (y + 1, x)));
+                }
+            }
+        }
+        let puzzle = ComparisonPuzzle::new(Puzzle::try_from([[0; 9]; 9]).unwrap(), inequalities);
+        let solution = puzzle.solve().unwrap();
+        for i in puzzle.inequalities() {
+            let (l, g) = (i.lesser(), i.greater());
+            assert!(solution[l.0][l.1] < solution[g.0][g.1]);
+        }
+    }
+}
@@ -0,0 +1,990 @@
+//! Additional rules for variant Sudoku puzzles
+//!
+//! A variant puzzle is an ordinary [`Puzzle`][crate::Puzzle] combined with one
+//! or more values implementing [`Constraint`]; such a puzzle is solved by
+//! passing the constraints to [`Puzzle::solve_with()`][crate::Puzzle::solve_with].
+//!
+//! Cells are identified throughout by `(y, x)` pairs of zero-based row and
+//! column indices.
+use crate::cnf::{Cnf, UnsupportedConstraint};
+use std::collections::HashSet;
+use std::fmt;
+use thiserror::Error;
+
+/// An additional rule that the solution to a variant Sudoku puzzle must obey
+/// on top of the standard row, column, & box rules.
+///
+/// The solver consults each constraint after every placement so that it can
+/// abandon a partial grid as soon as the constraint can no longer be
+/// satisfied, rather than only checking completed grids.
+pub trait Constraint: fmt::Debug {
+    /// Test whether the given partially-filled grid could still be completed
+    /// in a way that satisfies the constraint.  Unfilled cells are
+    /// represented by 0.
+    ///
+    /// Implementations must return `true` for any partial grid that can be
+    /// extended to a satisfying solution and `false` for any complete grid
+    /// that violates the constraint; in between, returning `false` as early
+    /// as possible makes for faster solving.
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool;
+
+    /// Add clauses to `cnf` that are satisfied exactly by the grids obeying
+    /// the constraint, so that the puzzle can be handed to a SAT solver.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation fails with [`UnsupportedConstraint`], as
+    /// not every constraint can be reasonably expressed in CNF.
+    fn encode(&self, _cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        Err(UnsupportedConstraint)
+    }
+}
+
+/// A thermometer: the digits along the path strictly increase from the bulb
+/// (the first cell) to the tip (the last cell).
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Thermometer(Vec<(usize, usize)>);
+
+impl Thermometer {
+    /// Construct a thermometer running through the given cells, starting at
+    /// the bulb.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any cell lies outside the grid or if the path is longer than
+    /// 9 cells.
+    pub fn new(cells: Vec<(usize, usize)>) -> Result<Thermometer, ConstraintError> {
+        validate_cells(&cells)?;
+        if cells.len() > 9 {
+            return Err(ConstraintError::TooLong(cells.len()));
+        }
+        Ok(Thermometer(cells))
+    }
+
+    /// Return the cells of the thermometer, starting at the bulb
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.0
+    }
+}
+
+impl Constraint for Thermometer {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        let len = self.0.len();
+        let mut prev: Option<(usize, u8)> = None;
+        for (k, &(y, x)) in self.0.iter().enumerate() {
+            let d = grid[y][x];
+            if d == 0 {
+                continue;
+            }
+            // The digit at position `k` needs room for `k` smaller digits
+            // before it and `len - 1 - k` larger digits after it.
+            if usize::from(d) < k + 1 || usize::from(d) + (len - 1 - k) > 9 {
+                return false;
+            }
+            if let Some((pk, pd)) = prev {
+                if d <= pd || usize::from(d - pd) < k - pk {
+                    return false;
+                }
+            }
+            prev = Some((k, d));
+        }
+        true
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        for pair in self.0.windows(2) {
+            if let &[a, b] = pair {
+                cnf.add_pair_rule(a, b, |d, e| d < e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An arrow: the digit in the circle equals the sum of the digits along the
+/// arrow.
+///
+/// Digits may repeat along an arrow where the standard rules allow it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Arrow {
+    circle: (usize, usize),
+    arrow: Vec<(usize, usize)>,
+}
+
+impl Arrow {
+    /// Construct an arrow with the given circle cell and arrow cells
+    ///
+    /// # Errors
+    ///
+    /// Fails if any cell lies outside the grid or if the arrow is empty.
+    pub fn new(
+        circle: (usize, usize),
+        arrow: Vec<(usize, usize)>,
+    ) -> Result<Arrow, ConstraintError> {
+        validate_cells(std::slice::from_ref(&circle))?;
+        validate_cells(&arrow)?;
+        if arrow.is_empty() {
+            return Err(ConstraintError::Empty);
+        }
+        Ok(Arrow { circle, arrow })
+    }
+
+    /// Return the cell containing the circle
+    pub fn circle(&self) -> (usize, usize) {
+        self.circle
+    }
+
+    /// Return the cells along the arrow, not including the circle
+    pub fn arrow(&self) -> &[(usize, usize)] {
+        &self.arrow
+    }
+}
+
+impl Constraint for Arrow {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        let (sum, unfilled) = sum_cells(grid, &self.arrow);
+        let min = sum + unfilled;
+        let max = sum + 9 * unfilled;
+        let (cy, cx) = self.circle;
+        match grid[cy][cx] {
+            0 => min <= 9,
+            c => (min..=max).contains(&u32::from(c)),
+        }
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        // One option for each possible sequence of digits along the arrow:
+        let options = small_sums(self.arrow.len())
+            .into_iter()
+            .map(|seq| {
+                let sum = seq.iter().sum::<u8>();
+                let mut option = vec![(self.circle, 1 << sum)];
+                option.extend(self.arrow.iter().zip(seq).map(|(&c, d)| (c, 1 << d)));
+                option
+            })
+            .collect::<Vec<_>>();
+        cnf.add_options(&options);
+        Ok(())
+    }
+}
+
+/// A sandwich clue: the digits lying between the 1 and the 9 in a given row or
+/// column add up to the given sum.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Sandwich {
+    line: Line,
+    sum: u32,
+}
+
+impl Sandwich {
+    /// Construct a sandwich clue for the row with the given index
+    ///
+    /// # Errors
+    ///
+    /// Fails if `y` is not less than 9.
+    pub fn row(y: usize, sum: u32) -> Result<Sandwich, ConstraintError> {
+        if y >= 9 {
+            return Err(ConstraintError::OutOfRange(y, 0));
+        }
+        Ok(Sandwich {
+            line: Line::Row(y),
+            sum,
+        })
+    }
+
+    /// Construct a sandwich clue for the column with the given index
+    ///
+    /// # Errors
+    ///
+    /// Fails if `x` is not less than 9.
+    pub fn column(x: usize, sum: u32) -> Result<Sandwich, ConstraintError> {
+        if x >= 9 {
+            return Err(ConstraintError::OutOfRange(0, x));
+        }
+        Ok(Sandwich {
+            line: Line::Column(x),
+            sum,
+        })
+    }
+
+    /// Return the sum given by the clue
+    pub fn sum(&self) -> u32 {
+        self.sum
+    }
+}
+
+impl Constraint for Sandwich {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        let cells = self.line.cells();
+        let digits = cells.map(|(y, x)| grid[y][x]);
+        let (Some(p1), Some(p9)) = (
+            digits.iter().position(|&d| d == 1),
+            digits.iter().position(|&d| d == 9),
+        ) else {
+            return true;
+        };
+        let between = &cells[p1.min(p9) + 1..p1.max(p9)];
+        let (sum, unfilled) = sum_cells(grid, between);
+        // The digits strictly between the 1 and the 9 are all in 2..=8.
+        (sum + 2 * unfilled..=sum + 8 * unfilled).contains(&self.sum)
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        // One option for each placement of the 1 & 9 and set of digits
+        // between them:
+        let cells = self.line.cells();
+        let mut options = Vec::new();
+        for p1 in 0..9 {
+            for p9 in (0..9).filter(|&p9| p9 != p1) {
+                let between = &cells[p1.min(p9) + 1..p1.max(p9)];
+                for mask in digit_sets(&[2, 3, 4, 5, 6, 7, 8], between.len(), self.sum) {
+                    let mut option = vec![(cells[p1], 1 << 1), (cells[p9], 1 << 9)];
+                    option.extend(between.iter().map(|&c| (c, mask)));
+                    options.push(option);
+                }
+            }
+        }
+        cnf.add_options(&options);
+        Ok(())
+    }
+}
+
+/// The kinds of markers that can be drawn on the border between two
+/// orthogonally adjacent cells
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum MarkerKind {
+    /// A white Kropki dot: the two digits are consecutive
+    WhiteDot,
+
+    /// A black Kropki dot: one digit is double the other
+    BlackDot,
+
+    /// An X: the two digits sum to 10
+    X,
+
+    /// A V: the two digits sum to 5
+    V,
+}
+
+impl MarkerKind {
+    /// Test whether the given pair of digits satisfies the relationship
+    /// indicated by this kind of marker
+    pub fn holds(self, a: u8, b: u8) -> bool {
+        match self {
+            MarkerKind::WhiteDot => a.abs_diff(b) == 1,
+            MarkerKind::BlackDot => a == 2 * b || b == 2 * a,
+            MarkerKind::X => a + b == 10,
+            MarkerKind::V => a + b == 5,
+        }
+    }
+}
+
+/// A marker drawn on the border between two orthogonally adjacent cells,
+/// requiring their digits to be related as indicated by the [`MarkerKind`]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Marker {
+    kind: MarkerKind,
+    cells: ((usize, usize), (usize, usize)),
+}
+
+impl Marker {
+    /// Construct a marker of the given kind between cells `a` and `b`
+    ///
+    /// # Errors
+    ///
+    /// Fails if either cell lies outside the grid or if the cells are not
+    /// orthogonally adjacent.
+    pub fn new(
+        kind: MarkerKind,
+        a: (usize, usize),
+        b: (usize, usize),
+    ) -> Result<Marker, ConstraintError> {
+        validate_cells(&[a, b])?;
+        if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) != 1 {
+            return Err(ConstraintError::NotAdjacent(a, b));
+        }
+        Ok(Marker {
+            kind,
+            cells: (a.min(b), a.max(b)),
+        })
+    }
+
+    /// Return the kind of marker
+    pub fn kind(&self) -> MarkerKind {
+        self.kind
+    }
+
+    /// Return the two cells on either side of the marker, in reading order
+    pub fn cells(&self) -> ((usize, usize), (usize, usize)) {
+        self.cells
+    }
+}
+
+impl Constraint for Marker {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        let ((ay, ax), (by, bx)) = self.cells;
+        match (grid[ay][ax], grid[by][bx]) {
+            (0, 0) => true,
+            // Adjacent cells always share a row or column, so the other digit
+            // must differ from this one.
+            (0, d) | (d, 0) => (1..=9).any(|e| e != d && self.kind.holds(d, e)),
+            (a, b) => self.kind.holds(a, b),
+        }
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        let (a, b) = self.cells;
+        cnf.add_pair_rule(a, b, |d, e| self.kind.holds(d, e));
+        Ok(())
+    }
+}
+
+/// The "negative constraint" for a set of marker kinds: every pair of
+/// orthogonally adjacent cells that does not have a marker drawn between them
+/// must not satisfy the relationship of any of the given kinds.
+///
+/// For example, a negative constraint for [`MarkerKind::WhiteDot`] and
+/// [`MarkerKind::BlackDot`] states that "all Kropki dots are given."
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NegativeConstraint {
+    kinds: Vec<MarkerKind>,
+    marked: HashSet<((usize, usize), (usize, usize))>,
+}
+
+impl NegativeConstraint {
+    /// Construct a negative constraint for the given kinds of markers, where
+    /// `markers` are all of the markers drawn in the puzzle.  Borders with a
+    /// marker of any kind are exempt from the constraint.
+    pub fn new(kinds: &[MarkerKind], markers: &[Marker]) -> NegativeConstraint {
+        NegativeConstraint {
+            kinds: kinds.to_vec(),
+            marked: markers.iter().map(Marker::cells).collect(),
+        }
+    }
+}
+
+impl Constraint for NegativeConstraint {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        for y in 0..9 {
+            for x in 0..9 {
+                let a = grid[y][x];
+                if a == 0 {
+                    continue;
+                }
+                for (by, bx) in [(y, x + 1), (y + 1, x)] {
+                    if by >= 9 || bx >= 9 || self.marked.contains(&((y, x), (by, bx))) {
+                        continue;
+                    }
+                    let b = grid[by][bx];
+                    if b != 0 && self.kinds.iter().any(|k| k.holds(a, b)) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        for y in 0..9 {
+            for x in 0..9 {
+                for b in [(y, x + 1), (y + 1, x)] {
+                    if b.0 < 9 && b.1 < 9 && !self.marked.contains(&((y, x), b)) {
+                        cnf.add_pair_rule((y, x), b, |d, e| {
+                            !self.kinds.iter().any(|k| k.holds(d, e))
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An inequality marker between two orthogonally adjacent cells: the digit in
+/// one cell is less than the digit in the other.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Inequality {
+    lesser: (usize, usize),
+    greater: (usize, usize),
+}
+
+impl Inequality {
+    /// Construct an inequality requiring the digit in `lesser` to be less
+    /// than the digit in `greater`
+    ///
+    /// # Errors
+    ///
+    /// Fails if either cell lies outside the grid or if the cells are not
+    /// orthogonally adjacent.
+    pub fn new(
+        lesser: (usize, usize),
+        greater: (usize, usize),
+    ) -> Result<Inequality, ConstraintError> {
+        validate_cells(&[lesser, greater])?;
+        if lesser.0.abs_diff(greater.0) + lesser.1.abs_diff(greater.1) != 1 {
+            return Err(ConstraintError::NotAdjacent(lesser, greater));
+        }
+        Ok(Inequality { lesser, greater })
+    }
+
+    /// Return the cell whose digit is the lesser of the two
+    pub fn lesser(&self) -> (usize, usize) {
+        self.lesser
+    }
+
+    /// Return the cell whose digit is the greater of the two
+    pub fn greater(&self) -> (usize, usize) {
+        self.greater
+    }
+}
+
+impl Constraint for Inequality {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        let (ly, lx) = self.lesser;
+        let (gy, gx) = self.greater;
+        match (grid[ly][lx], grid[gy][gx]) {
+            (0, 0) => true,
+            (0, g) => g > 1,
+            (l, 0) => l < 9,
+            (l, g) => l < g,
+        }
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        cnf.add_pair_rule(self.lesser, self.greater, |l, g| l < g);
+        Ok(())
+    }
+}
+
+/// A killer cage: the digits in the cage do not repeat and, if the cage has
+/// a clue, add up to the given sum.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct KillerCage {
+    cells: Vec<(usize, usize)>,
+    sum: Option<u32>,
+}
+
+impl KillerCage {
+    /// Construct a cage containing the given cells with an optional sum
+    ///
+    /// # Errors
+    ///
+    /// Fails if any cell lies outside the grid, if the cage is empty, or if
+    /// the cage contains more than 9 cells.
+    pub fn new(
+        cells: Vec<(usize, usize)>,
+        sum: Option<u32>,
+    ) -> Result<KillerCage, ConstraintError> {
+        validate_cells(&cells)?;
+        if cells.is_empty() {
+            return Err(ConstraintError::Empty);
+        }
+        if cells.len() > 9 {
+            return Err(ConstraintError::TooLong(cells.len()));
+        }
+        Ok(KillerCage { cells, sum })
+    }
+
+    /// Return the cells of the cage
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// Return the sum given by the cage's clue, if any
+    pub fn sum(&self) -> Option<u32> {
+        self.sum
+    }
+}
+
+impl Constraint for KillerCage {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        let mut seen = [false; 10];
+        for &(y, x) in &self.cells {
+            let d = usize::from(grid[y][x]);
+            if d != 0 {
+                if seen[d] {
+                    return false;
+                }
+                seen[d] = true;
+            }
+        }
+        let Some(target) = self.sum else {
+            return true;
+        };
+        let (sum, _) = sum_cells(grid, &self.cells);
+        // The unfilled cells must be filled with distinct unused digits, the
+        // smallest & largest possible totals of which bound the sum.
+        let k = self.cells.len() - seen.iter().filter(|&&b| b).count();
+        let unused = (1..=9u8).filter(|&d| !seen[usize::from(d)]).map(u32::from);
+        let min = sum + unused.clone().take(k).sum::<u32>();
+        let max = sum + unused.rev().take(k).sum::<u32>();
+        (min..=max).contains(&target)
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        cnf.add_distinct(&self.cells);
+        if let Some(sum) = self.sum {
+            // As the digits are distinct, restricting every cell to the same
+            // set of as many digits as there are cells forces the cage to
+            // contain exactly that set.
+            let options = digit_sets(&[1, 2, 3, 4, 5, 6, 7, 8, 9], self.cells.len(), sum)
+                .into_iter()
+                .map(|mask| self.cells.iter().map(|&c| (c, mask)).collect())
+                .collect::<Vec<_>>();
+            cnf.add_options(&options);
+        }
+        Ok(())
+    }
+}
+
+/// A diagonal of the grid along which digits may not repeat
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Diagonal {
+    /// The diagonal running from the bottom-left corner to the top-right
+    /// corner
+    Positive,
+
+    /// The diagonal running from the top-left corner to the bottom-right
+    /// corner
+    Negative,
+}
+
+impl Diagonal {
+    /// Return the cells of the diagonal, from top to bottom
+    pub fn cells(self) -> [(usize, usize); 9] {
+        let mut cells = [(0, 0); 9];
+        for (i, c) in cells.iter_mut().enumerate() {
+            *c = match self {
+                Diagonal::Positive => (i, 8 - i),
+                Diagonal::Negative => (i, i),
+            };
+        }
+        cells
+    }
+}
+
+impl Constraint for Diagonal {
+    fn check(&self, grid: &[[u8; 9]; 9]) -> bool {
+        let mut seen = [false; 10];
+        for (y, x) in self.cells() {
+            let d = usize::from(grid[y][x]);
+            if d != 0 {
+                if seen[d] {
+                    return false;
+                }
+                seen[d] = true;
+            }
+        }
+        true
+    }
+
+    fn encode(&self, cnf: &mut Cnf) -> Result<(), UnsupportedConstraint> {
+        cnf.add_distinct(&self.cells());
+        Ok(())
+    }
+}
+
+/// A row or column of the grid
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+impl Line {
+    fn cells(self) -> [(usize, usize); 9] {
+        let mut cells = [(0, 0); 9];
+        for (i, c) in cells.iter_mut().enumerate() {
+            *c = match self {
+                Line::Row(y) => (y, i),
+                Line::Column(x) => (i, x),
+            };
+        }
+        cells
+    }
+}
+
+/// Returns the sum of the filled cells among `cells` and the number of
+/// unfilled cells
+fn sum_cells(grid: &[[u8; 9]; 9], cells: &[(usize, usize)]) -> (u32, u32) {
+    let mut sum = 0;
+    let mut unfilled = 0;
+    for &(y, x) in cells {
+        match grid[y][x] {
+            0 => unfilled += 1,
+            d => sum += u32::from(d),
+        }
+    }
+    (sum, unfilled)
+}
+
+/// Returns the bitmasks (with bit `d` set for each digit `d`) of every set of
+/// `count` distinct digits drawn from `digits` that add up to `sum`
+fn digit_sets(digits: &[u8], count: usize, sum: u32) -> Vec<u16> {
+    (0u16..(1 << digits.len()))
+        .filter(|bits| bits.count_ones() as usize == count)
+        .filter_map(|bits| {
+            let chosen = digits
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| bits & (1 << i) != 0)
+                .map(|(_, &d)| d);
+            (chosen.clone().map(u32::from).sum::<u32>() == sum)
+                .then(|| chosen.fold(0, |mask, d| mask | (1 << d)))
+        })
+        .collect()
+}
+
+/// Returns every sequence of `len` digits that add up to at most 9
+fn small_sums(len: usize) -> Vec<Vec<u8>> {
+    let mut seqs = vec![Vec::new()];
+    for _ in 0..len {
+        seqs = seqs
+            .into_iter()
+            .flat_map(|seq: Vec<u8>| {
+                let total = seq.iter().sum::<u8>();
+                (1..=9 - total).map(move |d| {
+                    let mut next = seq.clone();
+                    next.push(d);
+                    next
+                })
+            })
+            .collect();
+    }
+    seqs
+}
+
+fn validate_cells(cells: &[(usize, usize)]) -> Result<(), ConstraintError> {
+    match cells.iter().find(|&&(y, x)| y >= 9 || x >= 9) {
+        Some(&(y, x)) => Err(ConstraintError::OutOfRange(y, x)),
+        None => Ok(()),
+    }
+}
+
+/// Error type returned when trying to construct a [`Constraint`] from invalid
+/// input
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ConstraintError {
+    /// Returned when a cell coordinate is not less than 9.  The arguments are
+    /// the row and column of the cell in question.
+    #[error("cell ({0}, {1}) is outside the grid")]
+    OutOfRange(usize, usize),
+
+    /// Returned when a thermometer or killer cage is more than 9 cells long.
+    /// The argument is the number of cells.
+    #[error("constraint of length {0} is longer than 9 cells")]
+    TooLong(usize),
+
+    /// Returned when a constraint requires at least one cell but none were
+    /// given
+    #[error("constraint has no cells")]
+    Empty,
+
+    /// Returned when a marker is placed between two cells that are not
+    /// orthogonally adjacent.  The arguments are the cells in question.
+    #[error("cells {0:?} and {1:?} are not orthogonally adjacent")]
+    NotAdjacent((usize, usize), (usize, usize)),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Puzzle, TEST_SOLUTION};
+
+    #[test]
+    fn test_thermometer_check() {
+        let thermo = Thermometer::new(vec![(0, 0), (0, 1), (0, 2)]).unwrap();
+        let mut grid = [[0; 9]; 9];
+        assert!(thermo.check(&grid));
+        grid[0][1] = 1;
+        assert!(!thermo.check(&grid));
+        grid[0][1] = 8;
+        assert!(thermo.check(&grid));
+        grid[0][1] = 9;
+        assert!(!thermo.check(&grid));
+        grid[0][1] = 0;
+        grid[0][0] = 4;
+        grid[0][2] = 5;
+        assert!(!thermo.check(&grid));
+        grid[0][2] = 6;
+        assert!(thermo.check(&grid));
+        grid[0][1] = 5;
+        assert!(thermo.check(&grid));
+    }
+
+    #[test]
+    fn test_thermometer_invalid() {
+        assert_eq!(
+            Thermometer::new(vec![(0, 0), (9, 0)]),
+            Err(ConstraintError::OutOfRange(9, 0))
+        );
+        assert_eq!(
+            Thermometer::new((0..9).map(|x| (0, x)).chain([(1, 8)]).collect()),
+            Err(ConstraintError::TooLong(10))
+        );
+    }
+
+    #[test]
+    fn test_solve_thermometer() {
+        let thermo = Thermometer::new((0..9).rev().map(|x| (0, x)).collect()).unwrap();
+        let constraints: Vec<Box<dyn Constraint>> = vec![Box::new(thermo.clone())];
+        let solution = Puzzle::try_from([[0; 9]; 9])
+            .unwrap()
+            .solve_with(&constraints)
+            .unwrap();
+        assert_eq!(solution[0], [9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert!(thermo.check(&solution));
+    }
+
+    #[test]
+    fn test_arrow_check() {
+        let arrow = Arrow::new((0, 0), vec![(1, 1), (2, 2)]).unwrap();
+        let mut grid = [[0; 9]; 9];
+        assert!(arrow.check(&grid));
+        grid[0][0] = 1;
+        assert!(!arrow.check(&grid));
+        grid[0][0] = 7;
+        grid[1][1] = 6;
+        assert!(arrow.check(&grid));
+        grid[1][1] = 7;
+        assert!(!arrow.check(&grid));
+        grid[0][0] = 0;
+        assert!(arrow.check(&grid));
+        grid[2][2] = 3;
+        assert!(!arrow.check(&grid));
+    }
+
+    #[test]
+    fn test_solve_arrow() {
+        let arrow = Arrow::new((0, 6), vec![(0, 5), (1, 5)]).unwrap();
+        let mut grid = TEST_SOLUTION;
+        for row in &mut grid[..3] {
+            *row = [0; 9];
+        }
+        let constraints: Vec<Box<dyn Constraint>> = vec![Box::new(arrow.clone())];
+        let solution = Puzzle::try_from(grid)
+            .unwrap()
+            .solve_with(&constraints)
+            .unwrap();
+        assert!(arrow.check(&solution));
+        assert_eq!(solution[0][6], solution[0][5] + solution[1][5]);
+    }
+
+    #[test]
+    fn test_sandwich_check() {
+        let sandwich = Sandwich::row(0, 12).unwrap();
+        let mut grid = [[0; 9]; 9];
+        assert!(sandwich.check(&grid));
+        grid[0][0] = 1;
+        grid[0][1] = 9;
+        assert!(!sandwich.check(&grid));
+        grid[0][1] = 0;
+        grid[0][3] = 9;
+        assert!(sandwich.check(&grid));
+        grid[0][1] = 8;
+        assert!(sandwich.check(&grid));
+        grid[0][2] = 5;
+        assert!(!sandwich.check(&grid));
+        grid[0][1] = 5;
+        grid[0][2] = 7;
+        assert!(sandwich.check(&grid));
+        assert_eq!(
+            Sandwich::column(9, 0),
+            Err(ConstraintError::OutOfRange(0, 9))
+        );
+    }
+
+    #[test]
+    fn test_solve_sandwich() {
+        // Column 0 of TEST_SOLUTION is 4 9 2 5 7 1 3 8 6: the sandwich is 2+5+7.
+        let sandwich = Sandwich::column(0, 14).unwrap();
+        let mut grid = TEST_SOLUTION;
+        for row in &mut grid[..6] {
+            *row = [0; 9];
+        }
+        let constraints: Vec<Box<dyn Constraint>> = vec![Box::new(sandwich)];
+        let solution = Puzzle::try_from(grid)
+            .unwrap()
+            .solve_with(&constraints)
+            .unwrap();
+        assert!(sandwich.check(&solution));
+    }
+
+    #[test]
+    fn test_inequality_check() {
+        let ineq = Inequality::new((3, 4), (2, 4)).unwrap();
+        let mut grid = [[0; 9]; 9];
+        assert!(ineq.check(&grid));
+        grid[3][4] = 9;
+        assert!(!ineq.check(&grid));
+        grid[3][4] = 4;
+        assert!(ineq.check(&grid));
+        grid[2][4] = 3;
+        assert!(!ineq.check(&grid));
+        grid[2][4] = 5;
+        assert!(ineq.check(&grid));
+        grid[3][4] = 0;
+        grid[2][4] = 1;
+        assert!(!ineq.check(&grid));
+        assert_eq!(
+            Inequality::new((3, 4), (4, 5)),
+            Err(ConstraintError::NotAdjacent((3, 4), (4, 5)))
+        );
+    }
+
+    #[test]
+    fn test_solve_unsatisfiable_givens() {
+        let thermo = Thermometer::new(vec![(0, 1), (0, 2)]).unwrap();
+        let constraints: Vec<Box<dyn Constraint>> = vec![Box::new(thermo)];
+        let puzzle = Puzzle::try_from(TEST_SOLUTION).unwrap();
+        assert_eq!(puzzle.solve_with(&constraints), None);
+    }
+
+    #[test]
+    fn test_marker_check() {
+        let white = Marker::new(MarkerKind::WhiteDot, (0, 1), (0, 0)).unwrap();
+        let black = Marker::new(MarkerKind::BlackDot, (0, 0), (1, 0)).unwrap();
+        let x = Marker::new(MarkerKind::X, (4, 4), (4, 5)).unwrap();
+        let v = Marker::new(MarkerKind::V, (4, 4), (5, 4)).unwrap();
+        assert_eq!(white.cells(), ((0, 0), (0, 1)));
+        let mut grid = [[0; 9]; 9];
+        grid[0][0] = 5;
+        assert!(white.check(&grid));
+        assert!(!black.check(&grid));
+        grid[0][1] = 4;
+        assert!(white.check(&grid));
+        grid[0][1] = 3;
+        assert!(!white.check(&grid));
+        grid[4][4] = 5;
+        assert!(!x.check(&grid));
+        assert!(!v.check(&grid));
+        grid[4][4] = 3;
+        grid[4][5] = 7;
+        grid[5][4] = 2;
+        assert!(x.check(&grid));
+        assert!(v.check(&grid));
+    }
+
+    #[test]
+    fn test_marker_not_adjacent() {
+        assert_eq!(
+            Marker::new(MarkerKind::X, (0, 0), (1, 1)),
+            Err(ConstraintError::NotAdjacent((0, 0), (1, 1)))
+        );
+        assert_eq!(
+            Marker::new(MarkerKind::V, (0, 0), (0, 0)),
+            Err(ConstraintError::NotAdjacent((0, 0), (0, 0)))
+        );
+    }
+
+    #[test]
+    fn test_negative_constraint_check() {
+        let dot = Marker::new(MarkerKind::WhiteDot, (0, 0), (0, 1)).unwrap();
+        let negative = NegativeConstraint::new(&[MarkerKind::WhiteDot], &[dot]);
+        let mut grid = [[0; 9]; 9];
+        grid[0][0] = 4;
+        grid[0][1] = 5;
+        assert!(negative.check(&grid));
+        grid[1][0] = 3;
+        assert!(!negative.check(&grid));
+        grid[1][0] = 6;
+        assert!(negative.check(&grid));
+        grid[0][2] = 6;
+        assert!(!negative.check(&grid));
+    }
+
+    #[test]
+    fn test_killer_cage_check() {
+        let cage = KillerCage::new(vec![(0, 0), (0, 1), (1, 0)], Some(6)).unwrap();
+        let mut grid = [[0; 9]; 9];
+        assert!(cage.check(&grid));
+        grid[0][0] = 4;
+        assert!(!cage.check(&grid));
+        grid[0][0] = 3;
+        assert!(cage.check(&grid));
+        grid[0][1] = 3;
+        assert!(!cage.check(&grid));
+        grid[0][1] = 2;
+        assert!(cage.check(&grid));
+        grid[1][0] = 1;
+        assert!(cage.check(&grid));
+        let unclued = KillerCage::new(vec![(0, 0), (0, 1)], None).unwrap();
+        assert!(unclued.check(&grid));
+        assert_eq!(
+            KillerCage::new(Vec::new(), Some(1)),
+            Err(ConstraintError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_diagonal_check() {
+        assert_eq!(Diagonal::Positive.cells()[0], (0, 8));
+        let mut grid = [[0; 9]; 9];
+        grid[0][0] = 5;
+        grid[8][8] = 5;
+        assert!(!Diagonal::Negative.check(&grid));
+        assert!(Diagonal::Positive.check(&grid));
+        assert!(!Diagonal::Negative.check(&TEST_SOLUTION));
+    }
+
+    #[test]
+    fn test_solve_killer_cage() {
+        let mut grid = TEST_SOLUTION;
+        grid[0] = [0; 9];
+        grid[1] = [0; 9];
+        let puzzle = Puzzle::try_from(grid).unwrap();
+        let mut constraints: Vec<Box<dyn Constraint>> = Vec::new();
+        for (cells, sum) in [
+            (vec![(0, 0), (1, 0)], 13),
+            (vec![(0, 1), (1, 1)], 14),
+            (vec![(0, 2), (0, 3)], 12),
+            (vec![(0, 4), (1, 4)], 6),
+            (vec![(0, 5), (0, 6), (1, 5)], 12),
+        ] {
+            constraints.push(Box::new(KillerCage::new(cells, Some(sum)).unwrap()));
+        }
+        assert_eq!(*puzzle.solve_with(&constraints).unwrap(), TEST_SOLUTION);
+    }
+
+    #[test]
+    fn test_solve_markers() {
+        // Place every marker that holds in TEST_SOLUTION, then clear its first
+        // three rows and require that no other markers apply.
+        let kinds = [
+            MarkerKind::WhiteDot,
+            MarkerKind::BlackDot,
+            MarkerKind::X,
+            MarkerKind::V,
+        ];
+        let mut markers = Vec::new();
+        for (y, row) in TEST_SOLUTION.iter().enumerate() {
+            for (x, &a) in row.iter().enumerate() {
+                for (by, bx) in [(y, x + 1), (y + 1, x)] {
+                    if by >= 9 || bx >= 9 {
+                        continue;
+                    }
+                    for kind in kinds {
+                        if kind.holds(a, TEST_SOLUTION[by][bx]) {
+                            markers.push(Marker::new(kind, (y, x), (by, bx)).unwrap());
+                        }
+                    }
+                }
+            }
+        }
+        let mut constraints: Vec<Box<dyn Constraint>> =
+            vec![Box::new(NegativeConstraint::new(&kinds, &markers))];
+        for m in markers {
+            constraints.push(Box::new(m));
+        }
+        let mut grid = TEST_SOLUTION;
+        for row in &mut grid[..3] {
+            *row = [0; 9];
+        }
+        let solution = Puzzle::try_from(grid)
+            .unwrap()
+            .solve_with(&constraints)
+            .unwrap();
+        assert!(constraints.iter().all(|c| c.check(&solution)));
+    }
+}
@@ -0,0 +1,372 @@
+//! Reading & writing puzzle files saved by other Sudoku programs
+//!
+//! The following file formats are supported:
+//!
+//! - [`Format::Plain`]: the format accepted by [`Puzzle`]'s [`FromStr`]
+//!   implementation and produced by its [`Display`](std::fmt::Display)
+//!   implementation
+//!
+//! - [`Format::Sdk`]: the `.sdk` format of SadMan Software Sudoku, in which
+//!   the grid may be preceded by `#` header lines giving metadata about the
+//!   puzzle
+//!
+//! - [`Format::SimpleSudoku`]: the `.ss` format of Simple Sudoku, in which
+//!   boxes are separated by `|` and `-` characters
+//!
+//! [`FromStr`]: std::str::FromStr
+use crate::{ParsePuzzleError, Puzzle};
+use std::fmt::Write;
+
+/// A supported puzzle file format
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Format {
+    /// The format used by [`Puzzle`]'s `FromStr` & `Display` implementations
+    Plain,
+
+    /// SadMan Software's `.sdk` format
+    Sdk,
+
+    /// Simple Sudoku's `.ss` format
+    SimpleSudoku,
+}
+
+impl Format {
+    /// Determine the format of a file from its extension (without the leading
+    /// period), ignoring case.  Returns `None` if the extension is not
+    /// recognized.
+    pub fn from_extension(ext: &str) -> Option<Format> {
+        match ext.to_ascii_lowercase().as_str() {
+            "txt" => Some(Format::Plain),
+            "sdk" => Some(Format::Sdk),
+            "ss" => Some(Format::SimpleSudoku),
+            _ => None,
+        }
+    }
+
+    /// Parse the contents of a file in this format
+    ///
+    /// # Errors
+    ///
+    /// Fails if the grid in the file is not exactly 9×9.
+    pub fn parse(self, s: &str) -> Result<PuzzleFile, ParsePuzzleError> {
+        match self {
+            Format::Plain => Ok(PuzzleFile::new(s.parse()?)),
+            Format::Sdk => parse_sdk(s),
+            Format::SimpleSudoku => parse_ss(s),
+        }
+    }
+
+    /// Render a puzzle file in this format.  The output ends with a newline.
+    ///
+    /// Formats without support for metadata discard it, and only the plain
+    /// format preserves the parities of shaded cells.
+    pub fn render(self, file: &PuzzleFile) -> String {
+        match self {
+            Format::Plain => format!("{}\n", file.puzzle),
+            Format::Sdk => render_sdk(file),
+            Format::SimpleSudoku => render_ss(&file.puzzle),
+        }
+    }
+}
+
+/// A [`Puzzle`] together with the metadata stored alongside it in a file
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PuzzleFile {
+    /// The puzzle itself
+    pub puzzle: Puzzle,
+
+    /// Metadata about the puzzle
+    pub metadata: Metadata,
+}
+
+impl PuzzleFile {
+    /// Construct a `PuzzleFile` for the given puzzle with empty metadata
+    pub fn new(puzzle: Puzzle) -> PuzzleFile {
+        PuzzleFile {
+            puzzle,
+            metadata: Metadata::default(),
+        }
+    }
+}
+
+/// Metadata about a puzzle, as stored in the header lines of an `.sdk` file
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Metadata {
+    /// The puzzle's author (`#A`)
+    pub author: Option<String>,
+
+    /// A description of the puzzle (`#D`)
+    pub description: Option<String>,
+
+    /// Comments on the puzzle (`#C`), one per line
+    pub comment: Option<String>,
+
+    /// The date on which the puzzle was published (`#B`)
+    pub date: Option<String>,
+
+    /// The name of the puzzle's source, such as a newspaper (`#S`)
+    pub source: Option<String>,
+
+    /// The puzzle's difficulty level (`#L`)
+    pub difficulty: Option<String>,
+
+    /// The URL of the puzzle's source (`#U`)
+    pub url: Option<String>,
+}
+
+impl Metadata {
+    /// Returns the header codes & corresponding fields, in the order in which
+    /// they are written
+    fn fields(&self) -> [(char, &Option<String>); 7] {
+        [
+            ('A', &self.author),
+            ('D', &self.description),
+            ('C', &self.comment),
+            ('B', &self.date),
+            ('S', &self.source),
+            ('L', &self.difficulty),
+            ('U', &self.url),
+        ]
+    }
+
+    fn field_mut(&mut self, code: char) -> Option<&mut Option<String>> {
+        match code {
+            'A' => Some(&mut self.author),
+            'D' => Some(&mut self.description),
+            'C' => Some(&mut self.comment),
+            'B' => Some(&mut self.date),
+            'S' => Some(&mut self.source),
+            'L' => Some(&mut self.difficulty),
+            'U' => Some(&mut self.url),
+            _ => None,
+        }
+    }
+}
+
+/// Parse an `.sdk` file.  Header lines consist of a `#`, a single-character
+/// code, and a value; repeated codes have their values joined by newlines,
+/// and unknown codes are ignored.  Section headers like `[Puzzle]` are
+/// skipped, and any sections after the first grid (such as a saved
+/// `[State]`) are ignored.
+///
+/// Skipped lines are replaced with blank lines in the text passed to the grid
+/// parser so that any errors report line numbers within the original file.
+fn parse_sdk(s: &str) -> Result<PuzzleFile, ParsePuzzleError> {
+    let mut metadata = Metadata::default();
+    let mut grid = String::new();
+    for raw in s.lines() {
+        let line = raw.trim();
+        if let Some(header) = line.strip_prefix('#') {
+            let mut chars = header.chars();
+            if let Some(field) = chars.next().and_then(|c| metadata.field_mut(c)) {
+                let value = chars.as_str().trim();
+                match field {
+                    Some(v) => {
+                        v.push('\n');
+                        v.push_str(value);
+                    }
+                    None => *field = Some(value.to_owned()),
+                }
+            }
+        } else if line.starts_with('[') {
+            if !grid.trim().is_empty() {
+                break;
+            }
+        } else {
+            grid.push_str(raw);
+        }
+        grid.push('\n');
+    }
+    Ok(PuzzleFile {
+        puzzle: grid.parse()?,
+        metadata,
+    })
+}
+
+fn render_sdk(file: &PuzzleFile) -> String {
+    let mut s = String::new();
+    for (code, value) in file.metadata.fields() {
+        if let Some(value) = value {
+            for line in value.lines() {
+                let _ = writeln!(s, "#{code}{line}");
+            }
+        }
+    }
+    for row in file.puzzle.iter() {
+        for &c in row {
+            s.push(cell_char(c));
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Parse an `.ss` file.  `|` characters are ignored, as are lines made up of
+/// only `-`, `*`, and `+` characters.
+///
+/// Ignored characters & lines are replaced with whitespace in the text passed
+/// to the grid parser so that any errors report positions within the original
+/// file.
+fn parse_ss(s: &str) -> Result<PuzzleFile, ParsePuzzleError> {
+    let mut grid = String::new();
+    for line in s.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || !trimmed.chars().all(|c| "-*+".contains(c)) {
+            grid.extend(line.chars().map(|c| if c == '|' { ' ' } else { c }));
+        }
+        grid.push('\n');
+    }
+    let puzzle = grid
+        .parse()
+        .map_err(|e: ParsePuzzleError| e.with_snippet_from(s))?;
+    Ok(PuzzleFile::new(puzzle))
+}
+
+fn render_ss(puzzle: &Puzzle) -> String {
+    let mut s = String::new();
+    for (y, row) in puzzle.iter().enumerate() {
+        if y == 3 || y == 6 {
+            s.push_str("-----------\n");
+        }
+        for (x, &c) in row.iter().enumerate() {
+            if x == 3 || x == 6 {
+                s.push('|');
+            }
+            s.push(cell_char(c));
+        }
+        s.push('\n');
+    }
+    s
+}
+
+fn cell_char(c: u8) -> char {
+    if c == 0 {
+        '.'
+    } else {
+        char::from(b'0' + c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+    use crate::ParsePuzzleErrorKind;
+
+    static SDK: &str = concat!(
+        "#AJohn Doe\n",
+        "#DA gentle warm-up\n",
+        "#CFirst line of comments\n",
+        "#CSecond line of comments\n",
+        "#B2024-01-01\n",
+        "#SThe Daily Grid\n",
+        "#LEasy\n",
+        "#Uhttps://example.com/puzzles/1\n",
+        "..3.2.6..\n",
+        "9..3.5..1\n",
+        "..18.64..\n",
+        "..81.29..\n",
+        "7.......8\n",
+        "..67.82..\n",
+        "..26.95..\n",
+        "8..2.3..9\n",
+        "..5.1.3..\n",
+    );
+
+    static SS: &str = concat!(
+        "..3|.2.|6..\n",
+        "9..|3.5|..1\n",
+        "..1|8.6|4..\n",
+        "-----------\n",
+        "..8|1.2|9..\n",
+        "7..|...|..8\n",
+        "..6|7.8|2..\n",
+        "-----------\n",
+        "..2|6.9|5..\n",
+        "8..|2.3|..9\n",
+        "..5|.1.|3..\n",
+    );
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(Format::from_extension("sdk"), Some(Format::Sdk));
+        assert_eq!(Format::from_extension("SS"), Some(Format::SimpleSudoku));
+        assert_eq!(Format::from_extension("txt"), Some(Format::Plain));
+        assert_eq!(Format::from_extension("pdf"), None);
+    }
+
+    #[test]
+    fn test_parse_sdk() {
+        let file = Format::Sdk.parse(SDK).unwrap();
+        assert_eq!(file.puzzle, test_puzzle());
+        assert_eq!(
+            file.metadata,
+            Metadata {
+                author: Some("John Doe".into()),
+                description: Some("A gentle warm-up".into()),
+                comment: Some("First line of comments\nSecond line of comments".into()),
+                date: Some("2024-01-01".into()),
+                source: Some("The Daily Grid".into()),
+                difficulty: Some("Easy".into()),
+                url: Some("https://example.com/puzzles/1".into()),
+            }
+        );
+        assert_eq!(Format::Sdk.render(&file), SDK);
+    }
+
+    #[test]
+    fn test_parse_sdk_sections() {
+        let s = format!(
+            "[Puzzle]\n{}[State]\n{}",
+            SDK.replace("#L", "#XUnknown\n#L"),
+            "483921657\n".repeat(9)
+        );
+        let file = Format::Sdk.parse(&s).unwrap();
+        assert_eq!(file.puzzle, test_puzzle());
+        assert_eq!(file.metadata.difficulty.as_deref(), Some("Easy"));
+    }
+
+    #[test]
+    fn test_parse_ss() {
+        let file = Format::SimpleSudoku.parse(SS).unwrap();
+        assert_eq!(file, PuzzleFile::new(test_puzzle()));
+        assert_eq!(Format::SimpleSudoku.render(&file), SS);
+        let framed = format!("*-----------*\n{SS}*-----------*\n");
+        assert_eq!(Format::SimpleSudoku.parse(&framed), Ok(file));
+    }
+
+    #[test]
+    fn test_convert_ss_to_sdk() {
+        let file = Format::SimpleSudoku.parse(SS).unwrap();
+        let sdk = Format::Sdk.render(&file);
+        assert_eq!(
+            sdk,
+            SDK.split_once("..3.2.6..")
+                .map(|(_, g)| format!("..3.2.6..{g}"))
+                .unwrap()
+        );
+        assert_eq!(
+            Format::Sdk.parse("..3.2.6..\n"),
+            Err(ParsePuzzleError::new(
+                ParsePuzzleErrorKind::TooFewRows(1),
+                2,
+                1,
+                ""
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_error_positions() {
+        let sdk = SDK.replace("9..3.5..1\n", "9..3.5..1.\n");
+        let e = Format::Sdk.parse(&sdk).unwrap_err();
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::BadRowSize(10));
+        assert_eq!((e.line(), e.column()), (10, 10));
+        assert_eq!(e.snippet(), "9..3.5..1.");
+        let ss = SS.replace("9..|3.5|..1", "9..|3.5|..");
+        let e = Format::SimpleSudoku.parse(&ss).unwrap_err();
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::BadRowSize(8));
+        assert_eq!((e.line(), e.column()), (2, 11));
+        assert_eq!(e.snippet(), "9..|3.5|..");
+    }
+}
@@ -0,0 +1,547 @@
+//! Importing variant puzzles from f-puzzles & SudokuPad
+//!
+//! [f-puzzles](https://www.f-puzzles.com) stores puzzles as JSON objects,
+//! which are usually shared compressed with lz-string and embedded in a URL,
+//! e.g. `https://www.f-puzzles.com/?load=N4IgzglgXgpiBcBOAN...`.  SudokuPad
+//! accepts the same data in URLs of the form
+//! `https://sudokupad.app/fpuzzlesN4IgzglgXgpiBcBOAN...`.
+//!
+//! This module is only available when the `fpuzzles` feature is enabled.
+use crate::constraints::{
+    Arrow, Constraint, ConstraintError, Diagonal, KillerCage, Marker, MarkerKind,
+    NegativeConstraint, Sandwich, Thermometer,
+};
+use crate::{Parity, Puzzle, Solution};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Keys of an f-puzzles object that carry no rules and are thus skipped
+/// without being reported as unsupported
+static IGNORED_KEYS: [&str; 8] = [
+    "size",
+    "grid",
+    "title",
+    "author",
+    "ruleset",
+    "solution",
+    "highlightConflicts",
+    "disabledlogic",
+];
+
+/// A variant puzzle imported from f-puzzles: a [`Puzzle`] together with the
+/// [`Constraint`]s that the crate supports.
+///
+/// Any constraints in the source that the crate cannot handle are not
+/// silently dropped but are listed by [`FPuzzle::unsupported()`]; solving a
+/// puzzle with unsupported constraints may yield a solution that violates
+/// them.
+///
+/// `FPuzzle` instances are constructed from a string using
+/// [`FromStr`]/[`str::parse()`].
+#[derive(Debug)]
+pub struct FPuzzle {
+    puzzle: Puzzle,
+    constraints: Vec<Box<dyn Constraint>>,
+    title: Option<String>,
+    author: Option<String>,
+    unsupported: Vec<String>,
+}
+
+impl FPuzzle {
+    /// Return the puzzle's grid of givens, including any odd/even shading
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    /// Return the supported constraints of the puzzle
+    pub fn constraints(&self) -> &[Box<dyn Constraint>] {
+        &self.constraints
+    }
+
+    /// Return the puzzle's title, if any
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Return the puzzle's author, if any
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Return descriptions of the constraints in the source that could not be
+    /// imported.  The puzzle is only faithfully represented if this is empty.
+    pub fn unsupported(&self) -> &[String] {
+        &self.unsupported
+    }
+
+    /// Solve the puzzle subject to its supported constraints.
+    ///
+    /// If the puzzle has multiple solutions, one of them is returned, but
+    /// which one is unspecified.
+    ///
+    /// If the puzzle has no solutions, `None` is returned.
+    pub fn solve(&self) -> Option<Solution> {
+        self.puzzle.solve_with(&self.constraints)
+    }
+}
+
+/// Parse an [`FPuzzle`] from f-puzzles data.  The input may be a plain JSON
+/// object, the lz-string-compressed form of one (as produced by f-puzzles'
+/// export), or an f-puzzles or SudokuPad URL containing such compressed data.
+///
+/// The following constraints are supported:
+///
+/// - givens (cells with `"given": true`) & odd/even cells
+/// - positive & negative diagonals
+/// - killer cages, with or without sums
+/// - thermometers
+/// - arrows with single-cell circles
+/// - sandwich sums
+/// - Kropki dots with the default values of 1 & 2, XV markers, and their
+///   negative constraints
+///
+/// Anything else is recorded in [`FPuzzle::unsupported()`].
+///
+/// # Errors
+///
+/// Fails if the data cannot be decompressed or is not valid JSON, if the grid
+/// is not 9×9, or if a supported constraint is malformed.
+impl FromStr for FPuzzle {
+    type Err = FPuzzlesError;
+
+    fn from_str(s: &str) -> Result<FPuzzle, FPuzzlesError> {
+        let data: Value = serde_json::from_str(&decompress(s)?)?;
+        let obj = data
+            .as_object()
+            .ok_or_else(|| FPuzzlesError::Malformed("puzzle".into()))?;
+        let mut builder = Builder {
+            grid: [[0; 9]; 9],
+            parities: [[None; 9]; 9],
+            constraints: Vec::new(),
+            markers: Vec::new(),
+            unsupported: Vec::new(),
+        };
+        builder.read(obj)?;
+        let string = |key| obj.get(key).and_then(Value::as_str).map(String::from);
+        Ok(FPuzzle {
+            puzzle: Puzzle::from_grid(builder.grid).with_parities(builder.parities),
+            constraints: builder.constraints,
+            title: string("title"),
+            author: string("author"),
+            unsupported: builder.unsupported,
+        })
+    }
+}
+
+/// Extract & decompress the f-puzzles JSON from a URL or compressed string.
+/// Input that already looks like JSON is returned unchanged.
+fn decompress(s: &str) -> Result<String, FPuzzlesError> {
+    let s = s.trim();
+    if s.starts_with('{') {
+        return Ok(s.to_owned());
+    }
+    let data = if let Some(i) = s.rfind("load=") {
+        &s[i + 5..]
+    } else if let Some(i) = s.rfind("/fpuzzles") {
+        &s[i + 9..]
+    } else {
+        s
+    };
+    let data = data.split(['&', '#']).next().unwrap_or(data);
+    let data = data
+        .replace("%2B", "+")
+        .replace("%2F", "/")
+        .replace("%3D", "=");
+    let utf16 = lz_str::decompress_from_base64(&data)
+        .filter(|d| !d.is_empty())
+        .ok_or(FPuzzlesError::Decompress)?;
+    String::from_utf16(&utf16).map_err(|_| FPuzzlesError::Decompress)
+}
+
+/// Accumulates the pieces of an [`FPuzzle`] while reading the JSON
+struct Builder {
+    grid: [[u8; 9]; 9],
+    parities: [[Option<Parity>; 9]; 9],
+    constraints: Vec<Box<dyn Constraint>>,
+    markers: Vec<Marker>,
+    unsupported: Vec<String>,
+}
+
+impl Builder {
+    fn read(&mut self, obj: &Map<String, Value>) -> Result<(), FPuzzlesError> {
+        let size = obj.get("size").and_then(Value::as_u64).unwrap_or(9);
+        if size != 9 {
+            return Err(FPuzzlesError::UnsupportedSize(size));
+        }
+        if let Some(grid) = obj.get("grid") {
+            self.read_grid(grid)?;
+        }
+        for (key, value) in obj {
+            if IGNORED_KEYS.contains(&key.as_str()) || is_empty(value) {
+                continue;
+            }
+            match key.as_str() {
+                "diagonal+" => self.constraints.push(Box::new(Diagonal::Positive)),
+                "diagonal-" => self.constraints.push(Box::new(Diagonal::Negative)),
+                "killercage" => {
+                    for cage in entries(key, value)? {
+                        let sum = cage.get("value").map(|v| number(key, v)).transpose()?;
+                        let cage = KillerCage::new(cells(key, cage.get("cells"))?, sum)?;
+                        self.constraints.push(Box::new(cage));
+                    }
+                }
+                "thermometer" => {
+                    for thermo in entries(key, value)? {
+                        for line in lines(key, thermo)? {
+                            self.constraints.push(Box::new(Thermometer::new(line)?));
+                        }
+                    }
+                }
+                "arrow" => {
+                    for arrow in entries(key, value)? {
+                        let circle = cells(key, arrow.get("cells"))?;
+                        let &[circle] = &circle[..] else {
+                            self.unsupported
+                                .push("arrow with a multi-cell circle".into());
+                            continue;
+                        };
+                        for mut line in lines(key, arrow)? {
+                            line.retain(|&c| c != circle);
+                            self.constraints.push(Box::new(Arrow::new(circle, line)?));
+                        }
+                    }
+                }
+                "sandwichsum" => {
+                    for clue in entries(key, value)? {
+                        let (y, x) = clue
+                            .get("cell")
+                            .and_then(Value::as_str)
+                            .and_then(parse_cell)
+                            .ok_or_else(|| FPuzzlesError::Malformed(key.to_owned()))?;
+                        let sum = number(key, clue.get("value").unwrap_or(&Value::Null))?;
+                        let sandwich = match (y, x) {
+                            (0 | 10, 1..=9) => Sandwich::column(x - 1, sum)?,
+                            (1..=9, 0 | 10) => Sandwich::row(y - 1, sum)?,
+                            _ => return Err(FPuzzlesError::Malformed(key.to_owned())),
+                        };
+                        self.constraints.push(Box::new(sandwich));
+                    }
+                }
+                "difference" | "ratio" | "xv" => self.read_markers(key, value)?,
+                "odd" | "even" => {
+                    let parity = if key == "odd" {
+                        Parity::Odd
+                    } else {
+                        Parity::Even
+                    };
+                    for entry in entries(key, value)? {
+                        let (y, x) = cell(key, entry.get("cell"))?;
+                        self.parities[y][x] = Some(parity);
+                    }
+                }
+                // Handled after all of the markers have been read
+                "negative" => (),
+                _ => self.unsupported.push(key.clone()),
+            }
+        }
+        if let Some(negative) = obj.get("negative").and_then(Value::as_array) {
+            let mut kinds = Vec::new();
+            for n in negative {
+                match n.as_str() {
+                    Some("difference") => kinds.push(MarkerKind::WhiteDot),
+                    Some("ratio") => kinds.push(MarkerKind::BlackDot),
+                    Some("xv") => kinds.extend([MarkerKind::X, MarkerKind::V]),
+                    _ => self.unsupported.push(format!("negative {n}")),
+                }
+            }
+            if !kinds.is_empty() {
+                let negative = NegativeConstraint::new(&kinds, &self.markers);
+                self.constraints.push(Box::new(negative));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_grid(&mut self, grid: &Value) -> Result<(), FPuzzlesError> {
+        let malformed = || FPuzzlesError::Malformed("grid".into());
+        let rows = grid.as_array().ok_or_else(malformed)?;
+        if rows.len() != 9 {
+            return Err(malformed());
+        }
+        let mut irregular = false;
+        for (y, row) in rows.iter().enumerate() {
+            let row = row
+                .as_array()
+                .filter(|r| r.len() == 9)
+                .ok_or_else(malformed)?;
+            for (x, cell) in row.iter().enumerate() {
+                let given = cell.get("given").and_then(Value::as_bool) == Some(true);
+                if let Some(v) = cell.get("value").filter(|_| given) {
+                    self.grid[y][x] = v
+                        .as_u64()
+                        .filter(|v| (1..=9).contains(v))
+                        .and_then(|v| u8::try_from(v).ok())
+                        .ok_or_else(malformed)?;
+                }
+                irregular |= cell.get("region").is_some_and(|r| !r.is_null());
+            }
+        }
+        if irregular {
+            self.unsupported.push("irregular regions".into());
+        }
+        Ok(())
+    }
+
+    fn read_markers(&mut self, key: &str, value: &Value) -> Result<(), FPuzzlesError> {
+        for entry in entries(key, value)? {
+            let value = entry.get("value").and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            });
+            let kind = match (key, value.as_deref()) {
+                ("difference", None | Some("1")) => MarkerKind::WhiteDot,
+                ("ratio", None | Some("2")) => MarkerKind::BlackDot,
+                ("xv", Some("X" | "x")) => MarkerKind::X,
+                ("xv", Some("V" | "v")) => MarkerKind::V,
+                (_, value) => {
+                    self.unsupported
+                        .push(format!("{key} of {}", value.unwrap_or("unknown value")));
+                    continue;
+                }
+            };
+            let &[a, b] = &cells(key, entry.get("cells"))?[..] else {
+                return Err(FPuzzlesError::Malformed(key.to_owned()));
+            };
+            let marker = Marker::new(kind, a, b)?;
+            self.markers.push(marker);
+            self.constraints.push(Box::new(marker));
+        }
+        Ok(())
+    }
+}
+
+/// Test whether a value is `false`, `null`, or an empty array, object, or
+/// string, as f-puzzles leaves unused keys in such states
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        Value::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+/// Return the objects in the array stored under `key`
+fn entries<'a>(
+    key: &str,
+    value: &'a Value,
+) -> Result<impl Iterator<Item = &'a Map<String, Value>>, FPuzzlesError> {
+    let malformed = || FPuzzlesError::Malformed(key.to_owned());
+    let array = value.as_array().ok_or_else(malformed)?;
+    if array.iter().any(|v| !v.is_object()) {
+        return Err(malformed());
+    }
+    Ok(array.iter().filter_map(Value::as_object))
+}
+
+/// Return the cell lists in an entry's `"lines"` field
+fn lines(key: &str, entry: &Map<String, Value>) -> Result<Vec<Vec<(usize, usize)>>, FPuzzlesError> {
+    entry
+        .get("lines")
+        .and_then(Value::as_array)
+        .ok_or_else(|| FPuzzlesError::Malformed(key.to_owned()))?
+        .iter()
+        .map(|line| cells(key, Some(line)))
+        .collect()
+}
+
+/// Convert an array of cell references into zero-based coordinates
+fn cells(key: &str, value: Option<&Value>) -> Result<Vec<(usize, usize)>, FPuzzlesError> {
+    value
+        .and_then(Value::as_array)
+        .ok_or_else(|| FPuzzlesError::Malformed(key.to_owned()))?
+        .iter()
+        .map(|c| cell(key, Some(c)))
+        .collect()
+}
+
+/// Convert a cell reference inside the grid into zero-based coordinates
+fn cell(key: &str, value: Option<&Value>) -> Result<(usize, usize), FPuzzlesError> {
+    let s = value
+        .and_then(Value::as_str)
+        .ok_or_else(|| FPuzzlesError::Malformed(key.to_owned()))?;
+    match parse_cell(s) {
+        Some((y @ 1..=9, x @ 1..=9)) => Ok((y - 1, x - 1)),
+        _ => Err(FPuzzlesError::BadCell(s.to_owned())),
+    }
+}
+
+/// Parse a one-based cell reference of the form `R<row>C<column>`
+fn parse_cell(s: &str) -> Option<(usize, usize)> {
+    let (y, x) = s.strip_prefix('R')?.split_once('C')?;
+    Some((y.parse().ok()?, x.parse().ok()?))
+}
+
+/// Parse a numeric clue, which f-puzzles may store as a string or a number
+fn number(key: &str, value: &Value) -> Result<u32, FPuzzlesError> {
+    match value {
+        Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| FPuzzlesError::Malformed(key.to_owned()))
+}
+
+/// Error type returned when trying to import an invalid f-puzzles puzzle
+#[derive(Debug, Error)]
+pub enum FPuzzlesError {
+    /// Returned when compressed data could not be decompressed
+    #[error("could not decompress puzzle data")]
+    Decompress,
+
+    /// Returned when the data is not valid JSON
+    #[error("puzzle data is not valid JSON")]
+    Json(#[from] serde_json::Error),
+
+    /// Returned when the grid is not 9×9.  The argument is the size of the
+    /// grid.
+    #[error("grid size {0} is not supported")]
+    UnsupportedSize(u64),
+
+    /// Returned when an entry in the data does not have the expected
+    /// structure.  The argument is the key of the entry.
+    #[error("malformed {0:?} entry")]
+    Malformed(String),
+
+    /// Returned when a cell reference is not of the form `R<row>C<column>`
+    /// with a row & column in `1..=9`
+    #[error("invalid cell reference {0:?}")]
+    BadCell(String),
+
+    /// Returned when a supported constraint is invalid
+    #[error(transparent)]
+    Constraint(#[from] ConstraintError),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TEST_SOLUTION;
+
+    /// Build f-puzzles JSON for `TEST_SOLUTION` with the first two rows blanked,
+    /// merging in the given extra keys
+    fn json(extra: &str) -> String {
+        let rows = TEST_SOLUTION
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let cells = row
+                    .iter()
+                    .map(|&d| {
+                        if y < 2 {
+                            String::from("{}")
+                        } else {
+                            format!(r#"{{"value":{d},"given":true}}"#)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                format!("[{}]", cells.join(","))
+            })
+            .collect::<Vec<_>>();
+        format!(
+            r#"{{"size":9,"title":"Test","author":"Someone","grid":[{}]{extra}}}"#,
+            rows.join(",")
+        )
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let puzzle = json(concat!(
+            r#","killercage":[{"cells":["R1C1","R2C1"],"value":"13"},"#,
+            r#"{"cells":["R1C2","R2C2"]}],"#,
+            r#""thermometer":[{"lines":[["R1C3","R1C4"]]}],"#,
+            r#""arrow":[{"cells":["R1C7"],"lines":[["R1C7","R1C5","R2C5"]]}],"#,
+            r#""odd":[{"cell":"R1C3"}],"even":[{"cell":"R1C1"}],"#,
+            r#""xv":[{"cells":["R2C3","R2C4"],"value":"X"}],"#,
+            r#""difference":[{"cells":["R2C8","R2C9"]}],"#,
+            r#""sandwichsum":[{"cell":"R0C1","value":"14"}],"#,
+            r#""diagonal+":false,"antiking":false,"cage":[]"#,
+        ))
+        .parse::<FPuzzle>()
+        .unwrap();
+        assert_eq!(puzzle.title(), Some("Test"));
+        assert_eq!(puzzle.author(), Some("Someone"));
+        assert_eq!(puzzle.puzzle()[0], [0; 9]);
+        assert_eq!(puzzle.puzzle()[2], TEST_SOLUTION[2]);
+        assert_eq!(puzzle.puzzle().parity(0, 2), Some(Parity::Odd));
+        assert_eq!(puzzle.puzzle().parity(0, 0), Some(Parity::Even));
+        assert_eq!(puzzle.constraints().len(), 7);
+        assert!(puzzle.unsupported().is_empty());
+        assert_eq!(*puzzle.solve().unwrap(), TEST_SOLUTION);
+    }
+
+    #[test]
+    fn test_parse_compressed_url() {
+        let data = lz_str::compress_to_base64(&json(r#","diagonal-":true"#));
+        for s in [
+            data.clone(),
+            format!("https://www.f-puzzles.com/?load={data}"),
+            format!("https://sudokupad.app/fpuzzles{}", data.replace('+', "%2B")),
+        ] {
+            let puzzle = s.parse::<FPuzzle>().unwrap();
+            assert_eq!(puzzle.title(), Some("Test"));
+            assert_eq!(puzzle.constraints().len(), 1);
+            assert_eq!(puzzle.solve(), None);
+        }
+        assert!(matches!(
+            "https://www.f-puzzles.com/?load=!!!".parse::<FPuzzle>(),
+            Err(FPuzzlesError::Decompress)
+        ));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let puzzle = json(concat!(
+            r#","antiknight":true,"renban":[{"lines":[["R1C1","R1C2"]]}],"#,
+            r#""ratio":[{"cells":["R1C1","R1C2"],"value":"3"}],"#,
+            r#""arrow":[{"cells":["R1C1","R1C2"],"lines":[["R1C2","R1C3"]]}]"#,
+        ))
+        .parse::<FPuzzle>()
+        .unwrap();
+        let mut unsupported = puzzle.unsupported().to_vec();
+        unsupported.sort();
+        assert_eq!(
+            unsupported,
+            [
+                "antiknight",
+                "arrow with a multi-cell circle",
+                "ratio of 3",
+                "renban"
+            ]
+        );
+        assert!(puzzle.constraints().is_empty());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            r#"{"size":6,"grid":[]}"#.parse::<FPuzzle>(),
+            Err(FPuzzlesError::UnsupportedSize(6))
+        ));
+        assert!(matches!(
+            json(r#","killercage":[{"cells":["R1C10"]}]"#).parse::<FPuzzle>(),
+            Err(FPuzzlesError::BadCell(c)) if c == "R1C10"
+        ));
+        assert!(matches!(
+            json(r#","thermometer":[{"lines":[["R1C1","R1C2","R1C3","R1C4","R1C5","R1C6","R1C7","R1C8","R1C9","R2C9"]]}]"#).parse::<FPuzzle>(),
+            Err(FPuzzlesError::Constraint(ConstraintError::TooLong(10)))
+        ));
+        assert!(matches!(
+            "{".parse::<FPuzzle>(),
+            Err(FPuzzlesError::Json(_))
+        ));
+    }
+}
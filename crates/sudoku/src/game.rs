@@ -0,0 +1,588 @@
+//! Tracking a player's progress through a puzzle
+//!
+//! A [`Game`] holds a puzzle's givens along with the digits & pencil marks
+//! that the player has entered in its other cells.  Every change is recorded
+//! as a [`Move`] so that it can be undone & redone, and games can be saved to
+//! & loaded from a simple text format with [`Game::save()`] and
+//! [`Game::load()`]:
+//!
+//! ```text
+//! sudoku game
+//! 003020600
+//! 900305001
+//! 001806400
+//! 008102900
+//! 700000008
+//! 006708200
+//! 002609500
+//! 800203009
+//! 005010300
+//! move r1c1 4 -
+//! move r1c2 . 58
+//! undone 1
+//! ```
+//!
+//! After the header line come the givens, in the format accepted by
+//! [`Puzzle`]'s `FromStr` implementation, followed by every move made in
+//! order, including those that have since been undone.  Each move gives the
+//! cell's contents after the move: the entered digit (or `.` for none) and
+//! the pencil marks (or `-` for none).  The final line gives the number of
+//! moves at the end of the list that have been undone and can be redone.
+use crate::verify::{Grid, Rc, Violation};
+use crate::{ParsePuzzleError, Puzzle};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// The header line of a saved game
+const HEADER: &str = "sudoku game";
+
+/// A puzzle in progress
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Game {
+    puzzle: Puzzle,
+    cells: [[CellState; 9]; 9],
+    history: Vec<Move>,
+    undone: Vec<Move>,
+}
+
+impl Game {
+    /// Start a new game of `puzzle` with no entries or pencil marks
+    pub fn new(puzzle: Puzzle) -> Game {
+        Game {
+            puzzle,
+            cells: [[CellState::default(); 9]; 9],
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Return the puzzle being played
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    /// Return the player's contents of the given cell.  Cells containing
+    /// givens are always empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn cell(&self, y: usize, x: usize) -> CellState {
+        self.cells[y][x]
+    }
+
+    /// Return the digit in the given cell, whether a given or entered by the
+    /// player, or 0 if there is none
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn value(&self, y: usize, x: usize) -> u8 {
+        match self.puzzle[y][x] {
+            0 => self.cells[y][x].entry,
+            d => d,
+        }
+    }
+
+    /// Return the grid of givens & entered digits
+    pub fn grid(&self) -> Grid {
+        let mut grid = [[0; 9]; 9];
+        for (y, row) in grid.iter_mut().enumerate() {
+            for (x, v) in row.iter_mut().enumerate() {
+                *v = self.value(y, x);
+            }
+        }
+        Grid::from(grid)
+    }
+
+    /// Enter `digit` in the given cell, replacing any digit already there.
+    /// The cell's pencil marks are kept.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cell contains a given or if `digit` is not from 1
+    /// through 9.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn enter(&mut self, y: usize, x: usize, digit: u8) -> Result<(), MoveError> {
+        check_digit(digit)?;
+        let after = CellState {
+            entry: digit,
+            ..self.cells[y][x]
+        };
+        self.set(y, x, after)
+    }
+
+    /// Erase the digit in the given cell, if any
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cell contains a given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn erase(&mut self, y: usize, x: usize) -> Result<(), MoveError> {
+        let after = CellState {
+            entry: 0,
+            ..self.cells[y][x]
+        };
+        self.set(y, x, after)
+    }
+
+    /// Add `digit` to the given cell's pencil marks if it is not there, or
+    /// remove it if it is
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cell contains a given or if `digit` is not from 1
+    /// through 9.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn toggle_pencil_mark(&mut self, y: usize, x: usize, digit: u8) -> Result<(), MoveError> {
+        check_digit(digit)?;
+        let mut after = self.cells[y][x];
+        after.marks ^= 1 << digit;
+        self.set(y, x, after)
+    }
+
+    /// Remove all of the given cell's pencil marks
+    ///
+    /// # Errors
+    ///
+    /// Fails if the cell contains a given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn clear_pencil_marks(&mut self, y: usize, x: usize) -> Result<(), MoveError> {
+        let after = CellState {
+            marks: 0,
+            ..self.cells[y][x]
+        };
+        self.set(y, x, after)
+    }
+
+    /// Set the contents of a cell, recording the change in the history and
+    /// discarding any undone moves.  Changes that leave the cell as it was
+    /// are not recorded.
+    fn set(&mut self, y: usize, x: usize, after: CellState) -> Result<(), MoveError> {
+        if self.puzzle[y][x] != 0 {
+            return Err(MoveError::Given { cell: (y, x) });
+        }
+        let before = self.cells[y][x];
+        if before != after {
+            self.cells[y][x] = after;
+            self.history.push(Move {
+                cell: (y, x),
+                before,
+                after,
+            });
+            self.undone.clear();
+        }
+        Ok(())
+    }
+
+    /// Undo the most recent move, returning it, or return `None` if there are
+    /// no moves to undo
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.history.pop()?;
+        let (y, x) = mv.cell;
+        self.cells[y][x] = mv.before;
+        self.undone.push(mv);
+        Some(mv)
+    }
+
+    /// Redo the most recently undone move, returning it, or return `None` if
+    /// there are no moves to redo.  Making a new move discards all undone
+    /// moves.
+    pub fn redo(&mut self) -> Option<Move> {
+        let mv = self.undone.pop()?;
+        let (y, x) = mv.cell;
+        self.cells[y][x] = mv.after;
+        self.history.push(mv);
+        Some(mv)
+    }
+
+    /// Return the moves made so far that have not been undone, in order
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Returns `true` if there are moves that can be undone
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    /// Returns `true` if there are undone moves that can be redone
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Return the number of cells that do not yet contain a digit
+    pub fn remaining(&self) -> usize {
+        self.grid().iter().flatten().filter(|&&d| d == 0).count()
+    }
+
+    /// Return the cells whose digits are repeated elsewhere in their row,
+    /// column, or box, in row-major order
+    pub fn conflicts(&self) -> Vec<(usize, usize)> {
+        let mut conflicts = Vec::new();
+        if let Err(violations) = self.grid().is_valid_solution() {
+            for v in violations {
+                if let Violation::Repeated { cells, .. } = v {
+                    conflicts.extend(cells);
+                }
+            }
+        }
+        conflicts.sort_unstable();
+        conflicts.dedup();
+        conflicts
+    }
+
+    /// Returns `true` if every cell is filled in and the grid is a valid
+    /// solution to the puzzle
+    pub fn is_solved(&self) -> bool {
+        self.grid().verify(&self.puzzle).is_ok()
+    }
+
+    /// Serialize the game in the format described in the [module
+    /// documentation](self).  The output ends with a newline.
+    pub fn save(&self) -> String {
+        let mut s = format!("{HEADER}\n{}\n", self.puzzle);
+        for mv in self.history.iter().chain(self.undone.iter().rev()) {
+            let (y, x) = mv.cell;
+            let _ = write!(s, "move r{}c{} ", y + 1, x + 1);
+            match mv.after.entry() {
+                Some(d) => s.push(char::from(b'0' + d)),
+                None => s.push('.'),
+            }
+            s.push(' ');
+            let marks = mv.after.pencil_marks();
+            if marks.is_empty() {
+                s.push('-');
+            }
+            for d in marks {
+                s.push(char::from(b'0' + d));
+            }
+            s.push('\n');
+        }
+        let _ = writeln!(s, "undone {}", self.undone.len());
+        s
+    }
+
+    /// Deserialize a game saved with [`Game::save()`]
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input is not in the format described in the [module
+    /// documentation](self) or if a move changes a given.
+    pub fn load(s: &str) -> Result<Game, LoadGameError> {
+        let lines = s.lines().collect::<Vec<_>>();
+        if lines.first().map(|ln| ln.trim()) != Some(HEADER) {
+            return Err(LoadGameError::BadHeader);
+        }
+        if lines.len() < 10 {
+            return Err(LoadGameError::Truncated);
+        }
+        let puzzle = lines[1..10].join("\n").parse::<Puzzle>()?;
+        let mut game = Game::new(puzzle);
+        let mut undone = None;
+        for (i, ln) in lines.iter().enumerate().skip(10) {
+            let line = i + 1;
+            let words = ln.split_whitespace().collect::<Vec<_>>();
+            match words.as_slice() {
+                [] => (),
+                _ if undone.is_some() => return Err(LoadGameError::Unexpected { line }),
+                ["move", cell, entry, marks] => {
+                    let ((y, x), after) =
+                        parse_move(cell, entry, marks).ok_or(LoadGameError::BadMove { line })?;
+                    game.set(y, x, after)
+                        .map_err(|source| LoadGameError::Move { line, source })?;
+                }
+                ["undone", n] => {
+                    let n = n
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&n| n <= game.history.len())
+                        .ok_or(LoadGameError::BadUndone { line })?;
+                    undone = Some(n);
+                }
+                _ => return Err(LoadGameError::Unexpected { line }),
+            }
+        }
+        for _ in 0..undone.unwrap_or(0) {
+            game.undo();
+        }
+        Ok(game)
+    }
+}
+
+impl From<Puzzle> for Game {
+    fn from(puzzle: Puzzle) -> Game {
+        Game::new(puzzle)
+    }
+}
+
+/// The player's contents of a single cell
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CellState {
+    entry: u8,
+    marks: u16,
+}
+
+impl CellState {
+    /// Return the digit entered in the cell, if any
+    pub fn entry(self) -> Option<u8> {
+        (self.entry != 0).then_some(self.entry)
+    }
+
+    /// Test whether `digit` is one of the cell's pencil marks
+    pub fn has_pencil_mark(self, digit: u8) -> bool {
+        (1..=9).contains(&digit) && self.marks & (1 << digit) != 0
+    }
+
+    /// Return the cell's pencil marks in ascending order
+    pub fn pencil_marks(self) -> Vec<u8> {
+        (1..=9).filter(|&d| self.has_pencil_mark(d)).collect()
+    }
+}
+
+/// A change made to a cell by the player
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Move {
+    /// The zero-based `(y, x)` coordinates of the cell
+    pub cell: (usize, usize),
+
+    /// The contents of the cell before the move
+    pub before: CellState,
+
+    /// The contents of the cell after the move
+    pub after: CellState,
+}
+
+/// Error returned when a move is not allowed
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum MoveError {
+    /// Returned when trying to change a cell that contains a given
+    #[error("{} contains a given", Rc(*.cell))]
+    Given { cell: (usize, usize) },
+
+    /// Returned when trying to enter a value other than 1 through 9
+    #[error("invalid digit {0}")]
+    InvalidDigit(u8),
+}
+
+/// Error returned by [`Game::load()`]
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum LoadGameError {
+    /// Returned when the input does not start with the header line
+    #[error("input is not a saved game")]
+    BadHeader,
+
+    /// Returned when the input ends before the end of the givens
+    #[error("saved game is missing its givens")]
+    Truncated,
+
+    /// Returned when the givens could not be parsed.  Line numbers in the
+    /// inner error are relative to the start of the givens.
+    #[error("invalid givens")]
+    Givens(#[from] ParsePuzzleError),
+
+    /// Returned when a move line is malformed
+    #[error("line {line}: invalid move")]
+    BadMove { line: usize },
+
+    /// Returned when a move is not allowed
+    #[error("line {line}: {source}")]
+    Move { line: usize, source: MoveError },
+
+    /// Returned when the number of undone moves is malformed or greater than
+    /// the number of moves
+    #[error("line {line}: invalid number of undone moves")]
+    BadUndone { line: usize },
+
+    /// Returned when a line is neither a move nor the number of undone moves,
+    /// or when anything follows the number of undone moves
+    #[error("line {line}: unexpected content")]
+    Unexpected { line: usize },
+}
+
+fn check_digit(digit: u8) -> Result<(), MoveError> {
+    if (1..=9).contains(&digit) {
+        Ok(())
+    } else {
+        Err(MoveError::InvalidDigit(digit))
+    }
+}
+
+/// Parse the fields of a move line into a cell and its contents after the
+/// move
+fn parse_move(cell: &str, entry: &str, marks: &str) -> Option<((usize, usize), CellState)> {
+    let (y, x) = cell.strip_prefix('r')?.split_once('c')?;
+    let y = y.parse::<usize>().ok().filter(|y| (1..=9).contains(y))?;
+    let x = x.parse::<usize>().ok().filter(|x| (1..=9).contains(x))?;
+    let entry = match entry {
+        "." => 0,
+        _ => entry.parse::<u8>().ok().filter(|d| (1..=9).contains(d))?,
+    };
+    let mut mask = 0;
+    if marks != "-" {
+        for c in marks.chars() {
+            let d = c.to_digit(10).filter(|&d| d != 0)?;
+            mask |= 1 << d;
+        }
+    }
+    Some(((y - 1, x - 1), CellState { entry, marks: mask }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_enter_undo_redo() {
+        let mut game = Game::new(test_puzzle());
+        assert_eq!(game.remaining(), 49);
+        game.enter(0, 0, 4).unwrap();
+        game.toggle_pencil_mark(0, 1, 8).unwrap();
+        game.toggle_pencil_mark(0, 1, 5).unwrap();
+        assert_eq!(game.value(0, 0), 4);
+        assert_eq!(game.cell(0, 1).pencil_marks(), vec![5, 8]);
+        assert_eq!(game.history().len(), 3);
+        assert_eq!(game.remaining(), 48);
+
+        let mv = game.undo().unwrap();
+        assert_eq!(mv.cell, (0, 1));
+        assert_eq!(game.cell(0, 1).pencil_marks(), vec![8]);
+        game.undo().unwrap();
+        assert!(game.can_redo());
+        game.redo().unwrap();
+        assert_eq!(game.cell(0, 1).pencil_marks(), vec![8]);
+
+        game.erase(0, 0).unwrap();
+        assert!(!game.can_redo());
+        assert_eq!(game.cell(0, 0).entry(), None);
+        assert_eq!(game.redo(), None);
+        game.undo().unwrap();
+        assert_eq!(game.value(0, 0), 4);
+    }
+
+    #[test]
+    fn test_no_op_move_not_recorded() {
+        let mut game = Game::new(test_puzzle());
+        game.erase(0, 0).unwrap();
+        game.clear_pencil_marks(0, 0).unwrap();
+        assert!(!game.can_undo());
+    }
+
+    #[test]
+    fn test_bad_moves() {
+        let mut game = Game::new(test_puzzle());
+        assert_eq!(game.enter(0, 2, 4), Err(MoveError::Given { cell: (0, 2) }));
+        assert_eq!(
+            game.toggle_pencil_mark(0, 0, 0),
+            Err(MoveError::InvalidDigit(0))
+        );
+        assert_eq!(
+            game.enter(0, 2, 4).unwrap_err().to_string(),
+            "r1c3 contains a given"
+        );
+        assert!(!game.can_undo());
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut game = Game::new(test_puzzle());
+        game.enter(0, 0, 8).unwrap();
+        assert_eq!(game.conflicts(), vec![(0, 0), (7, 0)]);
+        game.enter(0, 0, 4).unwrap();
+        assert_eq!(game.conflicts(), Vec::new());
+    }
+
+    #[test]
+    fn test_solved() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let mut game = Game::new(puzzle);
+        for y in 0..9 {
+            for x in 0..9 {
+                if puzzle[y][x] == 0 {
+                    assert!(!game.is_solved());
+                    game.enter(y, x, solution[y][x]).unwrap();
+                }
+            }
+        }
+        assert_eq!(game.remaining(), 0);
+        assert!(game.is_solved());
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut game = Game::new(test_puzzle());
+        game.enter(0, 0, 4).unwrap();
+        game.toggle_pencil_mark(0, 1, 8).unwrap();
+        game.toggle_pencil_mark(0, 1, 5).unwrap();
+        game.enter(0, 1, 8).unwrap();
+        game.undo().unwrap();
+        let saved = game.save();
+        assert_eq!(
+            saved,
+            concat!(
+                "sudoku game\n",
+                "003020600\n",
+                "900305001\n",
+                "001806400\n",
+                "008102900\n",
+                "700000008\n",
+                "006708200\n",
+                "002609500\n",
+                "800203009\n",
+                "005010300\n",
+                "move r1c1 4 -\n",
+                "move r1c2 . 8\n",
+                "move r1c2 . 58\n",
+                "move r1c2 8 58\n",
+                "undone 1\n",
+            )
+        );
+        let mut loaded = Game::load(&saved).unwrap();
+        assert_eq!(loaded, game);
+        assert_eq!(loaded.redo().unwrap().after.entry(), Some(8));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let givens = Game::new(test_puzzle()).save().replace("undone 0\n", "");
+        assert_eq!(Game::load("003020600\n"), Err(LoadGameError::BadHeader));
+        assert_eq!(
+            Game::load("sudoku game\n003020600\n"),
+            Err(LoadGameError::Truncated)
+        );
+        assert_eq!(
+            Game::load(&format!("{givens}move r1c10 4 -\n")),
+            Err(LoadGameError::BadMove { line: 11 })
+        );
+        assert_eq!(
+            Game::load(&format!("{givens}move r1c3 4 -\n")),
+            Err(LoadGameError::Move {
+                line: 11,
+                source: MoveError::Given { cell: (0, 2) }
+            })
+        );
+        assert_eq!(
+            Game::load(&format!("{givens}move r1c1 4 -\nundone 2\n")),
+            Err(LoadGameError::BadUndone { line: 12 })
+        );
+        assert_eq!(
+            Game::load(&format!("{givens}undone 0\nmove r1c1 4 -\n")),
+            Err(LoadGameError::Unexpected { line: 12 })
+        );
+        assert!(Game::load(&givens).is_ok());
+    }
+}
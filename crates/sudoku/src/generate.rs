@@ -0,0 +1,211 @@
+//! Generating new puzzles
+//!
+//! A [`Generator`] produces puzzles with unique solutions from a seed, so the
+//! same seed always produces the same sequence of puzzles.  Each puzzle is
+//! made by filling an empty grid at random and then removing givens in a
+//! random order for as long as the solution stays unique, so the resulting
+//! puzzles are minimal: removing any further given would make the solution
+//! ambiguous.
+//!
+//! The same removal process is available for existing puzzles via
+//! [`Puzzle::minimize()`].
+use crate::logic::{rate, Difficulty};
+use crate::search::Search;
+use crate::{Puzzle, Solution};
+
+/// A seeded generator of puzzles with unique solutions
+#[derive(Clone, Debug)]
+pub struct Generator {
+    rng: fastrand::Rng,
+    symmetric: bool,
+}
+
+impl Generator {
+    /// Construct a generator whose output is determined by `seed`
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            rng: fastrand::Rng::with_seed(seed),
+            symmetric: false,
+        }
+    }
+
+    /// Set whether the givens of generated puzzles should have 180° rotational
+    /// symmetry.  Symmetric puzzles are minimal only with respect to removing
+    /// pairs of symmetric givens.
+    pub fn with_symmetry(self, symmetric: bool) -> Generator {
+        Generator { symmetric, ..self }
+    }
+
+    /// Generate a puzzle with a unique solution, returning the puzzle along
+    /// with its solution
+    pub fn generate(&mut self) -> (Puzzle, Solution) {
+        let mut search = Search::empty();
+        let filled = search.fill_random(&mut self.rng);
+        debug_assert!(filled, "an empty grid should always be fillable");
+        let solution = search.grid();
+        let puzzle = remove_givens(
+            Puzzle::from_grid(solution),
+            &shuffled_cells(&mut self.rng),
+            self.symmetric,
+        );
+        (puzzle, Solution(solution))
+    }
+
+    /// Generate puzzles until one with the given difficulty (as determined by
+    /// [`rate()`]) is found, giving up after `max_attempts` puzzles
+    pub fn generate_rated(
+        &mut self,
+        difficulty: Difficulty,
+        max_attempts: usize,
+    ) -> Option<(Puzzle, Solution)> {
+        std::iter::repeat_with(|| self.generate())
+            .take(max_attempts)
+            .find(|(puzzle, _)| rate(puzzle).difficulty == difficulty)
+    }
+}
+
+impl Puzzle {
+    /// Remove givens from the puzzle, in a random order determined by `seed`,
+    /// for as long as its solution stays unique.  The result is minimal:
+    /// removing any of its remaining givens would make the solution
+    /// ambiguous.  Parity shading is kept.
+    ///
+    /// If `symmetric` is true, givens are removed together with their
+    /// partners under 180° rotation, and the result is minimal only with
+    /// respect to removing such pairs.  The result is symmetric if the
+    /// original puzzle is.
+    ///
+    /// Returns `None` if the puzzle does not have a unique solution.
+    pub fn minimize(&self, seed: u64, symmetric: bool) -> Option<Puzzle> {
+        if !self.has_unique_solution() {
+            return None;
+        }
+        let mut rng = fastrand::Rng::with_seed(seed);
+        Some(remove_givens(*self, &shuffled_cells(&mut rng), symmetric))
+    }
+}
+
+/// Returns the coordinates of all 81 cells in a random order
+fn shuffled_cells(rng: &mut fastrand::Rng) -> Vec<(usize, usize)> {
+    let mut cells = (0..81).map(|i| (i / 9, i % 9)).collect::<Vec<_>>();
+    rng.shuffle(&mut cells);
+    cells
+}
+
+/// Remove the givens of a puzzle with a unique solution, visiting the cells
+/// in the given order, for as long as the solution stays unique.  If
+/// `symmetric` is true, each cell's partner under 180° rotation is removed
+/// along with it.
+///
+/// As removing givens can only add solutions, a given that could not be
+/// removed when visited can never be removed later, and so a single pass
+/// suffices to reach a minimal puzzle.
+fn remove_givens(mut puzzle: Puzzle, cells: &[(usize, usize)], symmetric: bool) -> Puzzle {
+    for &(y, x) in cells {
+        if puzzle.grid[y][x] == 0 {
+            continue;
+        }
+        let mut trial = puzzle;
+        trial.grid[y][x] = 0;
+        if symmetric {
+            trial.grid[8 - y][8 - x] = 0;
+        }
+        if trial.has_unique_solution() {
+            puzzle = trial;
+        }
+    }
+    puzzle
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_generate() {
+        let (puzzle, solution) = Generator::new(42).generate();
+        assert!(puzzle.has_unique_solution());
+        assert_eq!(solution.verify(&puzzle), Ok(()));
+        // Minimality: removing any given makes the solution ambiguous.
+        for y in 0..9 {
+            for x in 0..9 {
+                if puzzle[y][x] != 0 {
+                    let mut grid = puzzle.grid;
+                    grid[y][x] = 0;
+                    assert!(!Puzzle::from_grid(grid).has_unique_solution());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_deterministic() {
+        let mut a = Generator::new(7);
+        let mut b = Generator::new(7);
+        assert_eq!(a.generate(), b.generate());
+        assert_eq!(a.generate(), b.generate());
+        assert_ne!(Generator::new(8).generate(), Generator::new(7).generate());
+    }
+
+    #[test]
+    fn test_generate_symmetric() {
+        let (puzzle, _) = Generator::new(1).with_symmetry(true).generate();
+        assert!(puzzle.has_unique_solution());
+        for y in 0..9 {
+            for x in 0..9 {
+                assert_eq!(puzzle[y][x] == 0, puzzle[8 - y][8 - x] == 0);
+            }
+        }
+    }
+
+    fn givens(puzzle: &Puzzle) -> usize {
+        puzzle.grid.iter().flatten().filter(|&&d| d != 0).count()
+    }
+
+    #[test]
+    fn test_minimize() {
+        // Start from the solution so that there is plenty to remove:
+        let full = Puzzle::from_grid(test_puzzle().solve().unwrap().0);
+        let minimal = full.minimize(5, false).unwrap();
+        assert!(givens(&minimal) < givens(&full));
+        assert_eq!(minimal.solve(), test_puzzle().solve());
+        for y in 0..9 {
+            for x in 0..9 {
+                if minimal[y][x] != 0 {
+                    assert_eq!(minimal[y][x], full[y][x]);
+                    let mut grid = minimal.grid;
+                    grid[y][x] = 0;
+                    assert!(!Puzzle::from_grid(grid).has_unique_solution());
+                }
+            }
+        }
+        assert_eq!(full.minimize(5, false), Some(minimal));
+        assert_eq!(minimal.minimize(6, false), Some(minimal));
+    }
+
+    #[test]
+    fn test_minimize_symmetric() {
+        let full = Puzzle::from_grid(test_puzzle().solve().unwrap().0);
+        let minimal = full.minimize(2, true).unwrap();
+        assert!(minimal.has_unique_solution());
+        for y in 0..9 {
+            for x in 0..9 {
+                assert_eq!(minimal[y][x] == 0, minimal[8 - y][8 - x] == 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimize_ambiguous() {
+        assert_eq!(Puzzle::from_grid([[0; 9]; 9]).minimize(0, false), None);
+    }
+
+    #[test]
+    fn test_generate_rated() {
+        let (puzzle, _) = Generator::new(3)
+            .generate_rated(Difficulty::Easy, 100)
+            .unwrap();
+        assert_eq!(rate(&puzzle).difficulty, Difficulty::Easy);
+    }
+}
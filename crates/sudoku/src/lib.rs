@@ -1,3 +1,27 @@
+pub mod analysis;
+mod canonical;
+pub mod cnf;
+pub mod comparison;
+pub mod constraints;
+pub mod formats;
+#[cfg(feature = "fpuzzles")]
+pub mod fpuzzles;
+pub mod game;
+pub mod generate;
+pub mod logic;
+pub mod multi;
+pub mod pdf;
+pub mod pencilmarks;
+#[cfg(feature = "sat")]
+pub mod sat;
+mod search;
+pub mod svg;
+pub mod terminal;
+pub mod transform;
+pub mod verify;
+use crate::constraints::Constraint;
+use crate::search::Search;
+use crate::verify::{Grid, Violation};
 use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
@@ -14,10 +38,42 @@ static DIVIDER: &str = "+-----+-----+-----+";
 ///
 /// As `Puzzle` implements `Deref<[[u8; 9]; 9]>`, it can be indexed to obtain
 /// the individual rows of the puzzle; "unfilled" cells are represented by 0.
+///
+/// Unfilled cells may additionally be shaded to indicate that they must
+/// contain an even or odd digit; see [`Puzzle::parity()`].
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Puzzle([[u8; 9]; 9]);
+pub struct Puzzle {
+    grid: [[u8; 9]; 9],
+    parities: [[Option<Parity>; 9]; 9],
+}
 
 impl Puzzle {
+    fn from_grid(grid: [[u8; 9]; 9]) -> Puzzle {
+        Puzzle {
+            grid,
+            parities: [[None; 9]; 9],
+        }
+    }
+
+    /// Return the parity that the digit in the given cell is required to
+    /// have, or `None` if the cell is unshaded
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn parity(&self, y: usize, x: usize) -> Option<Parity> {
+        self.parities[y][x]
+    }
+
+    /// Return a copy of the puzzle in which each cell is required to contain
+    /// a digit of the corresponding parity in `parities`, if any.
+    ///
+    /// If a filled cell's digit does not match its required parity, the
+    /// puzzle has no solution.
+    pub fn with_parities(self, parities: [[Option<Parity>; 9]; 9]) -> Puzzle {
+        Puzzle { parities, ..self }
+    }
+
     /// Solve the puzzle.
     ///
     /// If the puzzle has multiple solutions, one of them is returned, but
@@ -25,7 +81,39 @@ impl Puzzle {
     ///
     /// If the puzzle has no solutions, `None` is returned.
     pub fn solve(&self) -> Option<Solution> {
+        self.solve_with(&[])
+    }
+
+    /// Count the puzzle's solutions, stopping once `limit` have been found.
+    ///
+    /// Parity shading is taken into account, but variant constraints are not.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        Search::new(self).map_or(0, |mut search| search.count(limit))
+    }
+
+    /// Test whether the puzzle has exactly one solution
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    /// Solve the puzzle as a variant Sudoku in which the solution must also
+    /// satisfy the given [`Constraint`]s.
+    ///
+    /// The constraints are checked after each placement, so partial grids
+    /// that cannot satisfy them are abandoned early.
+    ///
+    /// If the puzzle has multiple solutions, one of them is returned, but
+    /// which one is unspecified.
+    ///
+    /// If the puzzle has no solutions, `None` is returned.
+    pub fn solve_with(&self, constraints: &[Box<dyn Constraint>]) -> Option<Solution> {
         let mut scratch = InProgress::new(self);
+        if !constraints.iter().all(|c| c.check(&scratch.puzzle)) {
+            return None;
+        }
+        // Givens that conflict with each other or with their cells' parities
+        // would otherwise only be detected after exhausting the search.
+        Search::new(self)?;
         let mut coords = Coords::new();
         'iloop: while let Some((i, j)) = coords.get() {
             if let Some(o) = scratch.obstructions[i][j] {
@@ -35,11 +123,16 @@ impl Puzzle {
                     scratch.puzzle[i][j] = 0;
                 }
                 next_test += 1;
+                let parity = self.parities[i][j];
                 while next_test <= 9 {
-                    if o.for_number(next_test) == 0 {
+                    if o.for_number(next_test) == 0 && parity.map_or(true, |p| p.allows(next_test))
+                    {
                         scratch.puzzle[i][j] = next_test;
-                        scratch.add_obstruction(i, j);
-                        break;
+                        if constraints.iter().all(|c| c.check(&scratch.puzzle)) {
+                            scratch.add_obstruction(i, j);
+                            break;
+                        }
+                        scratch.puzzle[i][j] = 0;
                     }
                     next_test += 1;
                 }
@@ -73,7 +166,7 @@ impl InProgress {
     fn new(p: &Puzzle) -> Self {
         let mut scratch = Self {
             obstructions: [[Some(Obstruction::new()); 9]; 9],
-            puzzle: p.0,
+            puzzle: p.grid,
         };
         for i in 0..9 {
             for j in 0..9 {
@@ -194,6 +287,45 @@ impl Coords {
     }
 }
 
+/// The parity required of the digit in a shaded cell of a [`Puzzle`]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Parity {
+    /// The cell must contain one of 2, 4, 6, or 8
+    Even,
+
+    /// The cell must contain one of 1, 3, 5, 7, or 9
+    Odd,
+}
+
+impl Parity {
+    /// Test whether the given digit has this parity
+    pub fn allows(self, digit: u8) -> bool {
+        match self {
+            Parity::Even => digit % 2 == 0,
+            Parity::Odd => digit % 2 == 1,
+        }
+    }
+
+    /// Return the character used to represent an unfilled cell of this
+    /// parity in the text format of a [`Puzzle`]
+    pub fn to_char(self) -> char {
+        match self {
+            Parity::Even => 'e',
+            Parity::Odd => 'o',
+        }
+    }
+}
+
+/// Display a parity as the word "even" or "odd"
+impl fmt::Display for Parity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Parity::Even => write!(f, "even"),
+            Parity::Odd => write!(f, "odd"),
+        }
+    }
+}
+
 /// Error type returned when trying to construct a [`Puzzle`] from invalid
 /// input
 #[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
@@ -230,7 +362,7 @@ impl TryFrom<[[u8; 9]; 9]> for Puzzle {
                 }
             }
         }
-        Ok(Puzzle(value))
+        Ok(Puzzle::from_grid(value))
     }
 }
 
@@ -278,6 +410,10 @@ impl<T: AsRef<[u8]>> TryFrom<Vec<T>> for Puzzle {
 /// representing an "unfilled" cell).  Horizontal whitespace and blank lines
 /// are ignored.
 ///
+/// As an exception, the letters `e` and `o` (in either case) represent
+/// "unfilled" cells that are shaded to require an even or odd digit,
+/// respectively.
+///
 /// For example, the following input:
 ///
 /// ```text
@@ -308,36 +444,212 @@ impl<T: AsRef<[u8]>> TryFrom<Vec<T>> for Puzzle {
 /// . . 4  . 9 2  . . .
 /// ```
 ///
+/// See [`Puzzle::from_str_strict()`] for a variant that rejects unexpected
+/// characters.
+///
 /// # Errors
 ///
-/// Fails if the input grid is not exactly 9×9.
+/// Fails if the input grid is not exactly 9×9.  The error reports the line &
+/// column at which the problem was found along with the text of the offending
+/// line.
 impl FromStr for Puzzle {
-    type Err = TryIntoPuzzleError;
+    type Err = ParsePuzzleError;
 
-    fn from_str(s: &str) -> Result<Puzzle, TryIntoPuzzleError> {
-        let mut grid = Vec::with_capacity(9);
-        for line in s.lines() {
-            let mut row = Vec::with_capacity(9);
-            for c in line.chars() {
-                if let Some(x) = c.to_digit(10) {
-                    row.push(u8::try_from(x).expect("base-10 digits should fit in a u8"));
-                } else if !c.is_whitespace() {
-                    row.push(0);
-                }
+    fn from_str(s: &str) -> Result<Puzzle, ParsePuzzleError> {
+        parse_puzzle(s, false)
+    }
+}
+
+impl Puzzle {
+    /// Parse a [`Puzzle`] from a string in the same format as that accepted
+    /// by the [`FromStr`] implementation, except that the only non-space,
+    /// non-digit characters allowed are `.` (representing an "unfilled" cell)
+    /// and the parity letters `e` and `o` (in either case).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input grid is not exactly 9×9 or if the input contains
+    /// any other characters.
+    pub fn from_str_strict(s: &str) -> Result<Puzzle, ParsePuzzleError> {
+        parse_puzzle(s, true)
+    }
+}
+
+fn parse_puzzle(s: &str, strict: bool) -> Result<Puzzle, ParsePuzzleError> {
+    let mut grid = [[0; 9]; 9];
+    let mut parities = [[None; 9]; 9];
+    let mut rows = 0;
+    let mut lineno = 0;
+    for (i, line) in s.lines().enumerate() {
+        lineno = i + 1;
+        let mut len = 0;
+        let mut overflow = None;
+        for (j, c) in line.chars().enumerate() {
+            if c.is_whitespace() {
+                continue;
             }
-            if !row.is_empty() {
-                grid.push(row);
+            let (value, parity) = match c {
+                '0'..='9' => (
+                    c.to_digit(10)
+                        .and_then(|d| u8::try_from(d).ok())
+                        .unwrap_or(0),
+                    None,
+                ),
+                'e' | 'E' => (0, Some(Parity::Even)),
+                'o' | 'O' => (0, Some(Parity::Odd)),
+                '.' => (0, None),
+                _ if strict => {
+                    return Err(ParsePuzzleError::new(
+                        ParsePuzzleErrorKind::UnexpectedChar(c),
+                        lineno,
+                        j + 1,
+                        line,
+                    ))
+                }
+                _ => (0, None),
+            };
+            if rows < 9 && len < 9 {
+                grid[rows][len] = value;
+                parities[rows][len] = parity;
+            } else if len == 9 && overflow.is_none() {
+                overflow = Some(j + 1);
             }
+            len += 1;
+        }
+        if len == 0 {
+            continue;
+        }
+        if len != 9 {
+            let column = overflow.unwrap_or_else(|| line.chars().count() + 1);
+            return Err(ParsePuzzleError::new(
+                ParsePuzzleErrorKind::BadRowSize(len),
+                lineno,
+                column,
+                line,
+            ));
         }
-        grid.try_into()
+        if rows == 9 {
+            return Err(ParsePuzzleError::new(
+                ParsePuzzleErrorKind::TooManyRows,
+                lineno,
+                1,
+                line,
+            ));
+        }
+        rows += 1;
+    }
+    if rows < 9 {
+        return Err(ParsePuzzleError::new(
+            ParsePuzzleErrorKind::TooFewRows(rows),
+            lineno + 1,
+            1,
+            "",
+        ));
+    }
+    Ok(Puzzle::from_grid(grid).with_parities(parities))
+}
+
+/// Error type returned when parsing an invalid [`Puzzle`] from a string
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ParsePuzzleError {
+    kind: ParsePuzzleErrorKind,
+    line: usize,
+    column: usize,
+    snippet: String,
+}
+
+impl ParsePuzzleError {
+    fn new(kind: ParsePuzzleErrorKind, line: usize, column: usize, snippet: &str) -> Self {
+        ParsePuzzleError {
+            kind,
+            line,
+            column,
+            snippet: snippet.trim_end().to_owned(),
+        }
+    }
+
+    /// Replace the snippet with the text of the corresponding line of `s`.
+    /// This is used by parsers that preprocess their input before passing it
+    /// to the [`Puzzle`] parser.
+    fn with_snippet_from(self, s: &str) -> Self {
+        let snippet = s
+            .lines()
+            .nth(self.line - 1)
+            .map_or_else(String::new, |ln| ln.trim_end().to_owned());
+        ParsePuzzleError { snippet, ..self }
+    }
+
+    /// Return the kind of problem encountered
+    pub fn kind(&self) -> ParsePuzzleErrorKind {
+        self.kind
+    }
+
+    /// Return the (1-based) line number at which the problem was found.  If
+    /// the input ended too soon, this is one more than the number of lines in
+    /// the input.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Return the (1-based) column, counted in characters, at which the
+    /// problem was found.  For a row that is too short, this is the column
+    /// just past the end of the line; for a row that is too long, this is the
+    /// column of the first extra cell.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Return the text of the offending line, without any trailing
+    /// whitespace.  If the input ended too soon, this is empty.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
     }
 }
 
+impl fmt::Display for ParsePuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )?;
+        if !self.snippet.is_empty() {
+            write!(f, ": {:?}", self.snippet)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParsePuzzleError {}
+
+/// The kinds of problems that can be encountered when parsing a [`Puzzle`]
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum ParsePuzzleErrorKind {
+    /// Returned when a row does not contain exactly 9 cells.  The argument is
+    /// the number of cells in the row.
+    #[error("row is {0} cells long, expected 9")]
+    BadRowSize(usize),
+
+    /// Returned when the input contains fewer than 9 rows.  The argument is
+    /// the number of rows found.
+    #[error("grid is only {0} rows long, expected 9")]
+    TooFewRows(usize),
+
+    /// Returned when the input contains more than 9 rows
+    #[error("grid is more than 9 rows long")]
+    TooManyRows,
+
+    /// Returned in strict mode when the input contains a character other
+    /// than a digit, whitespace, `.`, or a parity letter
+    #[error("unexpected character {0:?}")]
+    UnexpectedChar(char),
+}
+
 impl Deref for Puzzle {
     type Target = [[u8; 9]; 9];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.grid
     }
 }
 
@@ -378,6 +690,9 @@ impl Deref for Puzzle {
 /// +-----+-----+-----+
 /// ```
 ///
+/// In both forms, "unfilled" cells that are shaded to require an even or odd
+/// digit are instead represented by `e` or `o`, respectively.
+///
 /// Both forms lack a final terminating newline.
 impl fmt::Display for Puzzle {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -388,11 +703,13 @@ impl fmt::Display for Puzzle {
                 }
                 for x in 0..9 {
                     write!(f, "{}", if x % 3 == 0 { '|' } else { ' ' })?;
-                    let c = self.0[y][x];
-                    if c == 0 {
-                        write!(f, " ")?;
-                    } else {
+                    let c = self.grid[y][x];
+                    if c != 0 {
                         write!(f, "{c}")?;
+                    } else if let Some(p) = self.parities[y][x] {
+                        write!(f, "{}", p.to_char())?;
+                    } else {
+                        write!(f, " ")?;
                     }
                 }
                 writeln!(f, "|")?;
@@ -404,7 +721,10 @@ impl fmt::Display for Puzzle {
                     writeln!(f)?;
                 }
                 for x in 0..9 {
-                    write!(f, "{}", self.0[y][x])?;
+                    match (self.grid[y][x], self.parities[y][x]) {
+                        (0, Some(p)) => write!(f, "{}", p.to_char())?,
+                        (c, _) => write!(f, "{c}")?,
+                    }
                 }
             }
         }
@@ -414,7 +734,11 @@ impl fmt::Display for Puzzle {
 
 /// A solution to a Sudoku puzzle.
 ///
-/// `Solution` instances are returned by [`Puzzle::solve`].
+/// `Solution` instances are returned by [`Puzzle::solve`].  They can also be
+/// constructed by converting from a grid of `u8` values using
+/// [`TryFrom`]/[`TryInto`] or from a string using [`FromStr`]/[`str::parse()`],
+/// in which case the grid is checked to be a complete and valid Sudoku
+/// solution.
 ///
 /// As `Solution` implements `Deref<[[u8; 9]; 9]>`, it can be indexed to obtain
 /// the individual rows of the solution.  Alternatively, a `Solution` can be
@@ -436,6 +760,103 @@ impl From<Solution> for [[u8; 9]; 9] {
     }
 }
 
+/// Error type returned when trying to construct a [`Solution`] from invalid
+/// input
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum TryIntoSolutionError {
+    /// Returned when the input is not a 9×9 grid of values in `0..=9`
+    #[error(transparent)]
+    Grid(#[from] TryIntoPuzzleError),
+
+    /// Returned when parsing a string that is not a 9×9 grid
+    #[error(transparent)]
+    Parse(#[from] ParsePuzzleError),
+
+    /// Returned when the grid is incomplete or breaks the rules of Sudoku.
+    /// The argument lists every problem found, as returned by
+    /// [`Grid::is_valid_solution()`](verify::Grid::is_valid_solution).
+    #[error("grid is not a valid solution: {}", ViolationList(.0))]
+    Invalid(Vec<Violation>),
+}
+
+/// Helper for displaying a semicolon-separated list of violations
+struct ViolationList<'a>(&'a [Violation]);
+
+impl fmt::Display for ViolationList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, v) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{v}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Convert a 9×9 grid into a [`Solution`].
+///
+/// # Errors
+///
+/// Fails if any cell is unfilled (0) or has a value larger than 9, or if any
+/// row, column, or box contains a repeated digit.
+impl TryFrom<[[u8; 9]; 9]> for Solution {
+    type Error = TryIntoSolutionError;
+
+    fn try_from(value: [[u8; 9]; 9]) -> Result<Solution, TryIntoSolutionError> {
+        Grid::from(value)
+            .is_valid_solution()
+            .map_err(TryIntoSolutionError::Invalid)?;
+        Ok(Solution(value))
+    }
+}
+
+/// Convert a slice of `u8` arrays into a [`Solution`].
+///
+/// # Errors
+///
+/// Fails if the grid is not exactly 9×9, if any cell is unfilled (0) or has a
+/// value larger than 9, or if any row, column, or box contains a repeated
+/// digit.
+impl<T: AsRef<[u8]>> TryFrom<&[T]> for Solution {
+    type Error = TryIntoSolutionError;
+
+    fn try_from(value: &[T]) -> Result<Solution, TryIntoSolutionError> {
+        Solution::try_from(Puzzle::try_from(value)?.grid)
+    }
+}
+
+/// Convert a [`Vec`] of `u8` arrays into a [`Solution`].
+///
+/// # Errors
+///
+/// Fails if the grid is not exactly 9×9, if any cell is unfilled (0) or has a
+/// value larger than 9, or if any row, column, or box contains a repeated
+/// digit.
+impl<T: AsRef<[u8]>> TryFrom<Vec<T>> for Solution {
+    type Error = TryIntoSolutionError;
+
+    fn try_from(v: Vec<T>) -> Result<Solution, TryIntoSolutionError> {
+        Solution::try_from(&v[..])
+    }
+}
+
+/// Parse a [`Solution`] from a string in the same format accepted by
+/// [`Puzzle`]'s [`FromStr`] implementation.  Any `0`s or non-digit characters
+/// are treated as unfilled cells and thus rejected.
+///
+/// # Errors
+///
+/// Fails if the input grid is not exactly 9×9, if any cell is unfilled, or if
+/// any row, column, or box contains a repeated digit.
+impl FromStr for Solution {
+    type Err = TryIntoSolutionError;
+
+    fn from_str(s: &str) -> Result<Solution, TryIntoSolutionError> {
+        Solution::try_from(s.parse::<Puzzle>()?.grid)
+    }
+}
+
 /// Display a [`Solution`] as nine lines of nine cells.
 ///
 /// In the default representation, there is no horizontal whitespace, e.g.:
@@ -500,13 +921,43 @@ impl fmt::Display for Solution {
     }
 }
 
+/// The puzzle used as a fixture by tests throughout the crate
+#[cfg(test)]
+pub(crate) fn test_puzzle() -> Puzzle {
+    Puzzle::from_grid([
+        [0, 0, 3, 0, 2, 0, 6, 0, 0],
+        [9, 0, 0, 3, 0, 5, 0, 0, 1],
+        [0, 0, 1, 8, 0, 6, 4, 0, 0],
+        [0, 0, 8, 1, 0, 2, 9, 0, 0],
+        [7, 0, 0, 0, 0, 0, 0, 0, 8],
+        [0, 0, 6, 7, 0, 8, 2, 0, 0],
+        [0, 0, 2, 6, 0, 9, 5, 0, 0],
+        [8, 0, 0, 2, 0, 3, 0, 0, 9],
+        [0, 0, 5, 0, 1, 0, 3, 0, 0],
+    ])
+}
+
+/// The solution to [`test_puzzle()`]
+#[cfg(test)]
+pub(crate) static TEST_SOLUTION: [[u8; 9]; 9] = [
+    [4, 8, 3, 9, 2, 1, 6, 5, 7],
+    [9, 6, 7, 3, 4, 5, 8, 2, 1],
+    [2, 5, 1, 8, 7, 6, 4, 9, 3],
+    [5, 4, 8, 1, 3, 2, 9, 7, 6],
+    [7, 2, 9, 5, 6, 4, 1, 3, 8],
+    [1, 3, 6, 7, 9, 8, 2, 4, 5],
+    [3, 7, 2, 6, 8, 9, 5, 1, 4],
+    [8, 1, 4, 2, 5, 3, 7, 6, 9],
+    [6, 9, 5, 4, 1, 7, 3, 8, 2],
+];
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
     fn test_display_puzzle() {
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [0, 0, 3, 0, 2, 0, 6, 0, 0],
             [9, 0, 0, 3, 0, 5, 0, 0, 1],
             [0, 0, 1, 8, 0, 6, 4, 0, 0],
@@ -600,7 +1051,7 @@ mod test {
 
     #[test]
     fn test_solve01() {
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [0, 0, 3, 0, 2, 0, 6, 0, 0],
             [9, 0, 0, 3, 0, 5, 0, 0, 1],
             [0, 0, 1, 8, 0, 6, 4, 0, 0],
@@ -627,7 +1078,7 @@ mod test {
 
     #[test]
     fn test_solve02() {
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [2, 0, 0, 0, 8, 0, 3, 0, 0],
             [0, 6, 0, 0, 7, 0, 0, 8, 4],
             [0, 3, 0, 5, 0, 0, 2, 0, 9],
@@ -654,7 +1105,7 @@ mod test {
 
     #[test]
     fn test_solve03() {
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [0, 0, 0, 0, 0, 0, 9, 0, 7],
             [0, 0, 0, 4, 2, 0, 1, 8, 0],
             [0, 0, 0, 7, 0, 5, 0, 2, 6],
@@ -682,7 +1133,7 @@ mod test {
     #[test]
     fn test_solve_ambiguous() {
         // From <https://math.stackexchange.com/a/345255/10655>
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [1, 4, 5, 3, 2, 7, 6, 9, 8],
             [8, 3, 9, 6, 5, 4, 1, 2, 7],
             [6, 7, 2, 9, 1, 8, 5, 4, 3],
@@ -704,7 +1155,7 @@ mod test {
     #[test]
     fn test_solve_unsolvable() {
         // From <https://www.reddit.com/r/sudoku/comments/7q76ay/>
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [2, 0, 0, 9, 0, 0, 0, 0, 0],
             [0, 0, 0, 0, 0, 0, 0, 6, 0],
             [0, 0, 0, 0, 0, 1, 0, 0, 0],
@@ -718,6 +1169,14 @@ mod test {
         assert_eq!(puzzle.solve(), None);
     }
 
+    #[test]
+    fn test_solve_conflicting_givens() {
+        let mut grid = [[0; 9]; 9];
+        grid[0][0] = 1;
+        grid[0][1] = 1;
+        assert_eq!(Puzzle::from_grid(grid).solve(), None);
+    }
+
     #[test]
     fn test_try_from_array() {
         let p1 = Puzzle::try_from([
@@ -732,7 +1191,7 @@ mod test {
             [0, 0, 5, 0, 1, 0, 3, 0, 0],
         ])
         .unwrap();
-        let p2 = Puzzle([
+        let p2 = Puzzle::from_grid([
             [0, 0, 3, 0, 2, 0, 6, 0, 0],
             [9, 0, 0, 3, 0, 5, 0, 0, 1],
             [0, 0, 1, 8, 0, 6, 4, 0, 0],
@@ -760,7 +1219,7 @@ mod test {
             vec![0, 0, 5, 0, 1, 0, 3, 0, 0],
         ])
         .unwrap();
-        let p2 = Puzzle([
+        let p2 = Puzzle::from_grid([
             [0, 0, 3, 0, 2, 0, 6, 0, 0],
             [9, 0, 0, 3, 0, 5, 0, 0, 1],
             [0, 0, 1, 8, 0, 6, 4, 0, 0],
@@ -882,7 +1341,7 @@ mod test {
             "003017009\n",
             "004092000\n",
         );
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [0, 0, 0, 7, 8, 0, 5, 0, 0],
             [2, 0, 0, 6, 5, 0, 7, 0, 0],
             [0, 0, 0, 0, 0, 0, 6, 3, 0],
@@ -911,7 +1370,7 @@ mod test {
             "0 0 3  0 1 7  0 0 9\n",
             "0 0 4  0 9 2  0 0 0\n",
         );
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [0, 0, 0, 7, 8, 0, 5, 0, 0],
             [2, 0, 0, 6, 5, 0, 7, 0, 0],
             [0, 0, 0, 0, 0, 0, 6, 3, 0],
@@ -938,7 +1397,7 @@ mod test {
             "..3.17..9\n",
             "..4.92...\n",
         );
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [0, 0, 0, 7, 8, 0, 5, 0, 0],
             [2, 0, 0, 6, 5, 0, 7, 0, 0],
             [0, 0, 0, 0, 0, 0, 6, 3, 0],
@@ -954,7 +1413,7 @@ mod test {
 
     #[test]
     fn test_index_puzzle() {
-        let puzzle = Puzzle([
+        let puzzle = Puzzle::from_grid([
             [0, 0, 3, 0, 2, 0, 6, 0, 0],
             [9, 0, 0, 3, 0, 5, 0, 0, 1],
             [0, 0, 1, 8, 0, 6, 4, 0, 0],
@@ -1004,4 +1463,215 @@ mod test {
         let solution = Solution(grid);
         assert_eq!(<[[u8; 9]; 9]>::from(solution), grid);
     }
+
+    #[test]
+    fn test_parse_parity_puzzle() {
+        let s = concat!(
+            "e . 3  . 2 .  6 . o\n",
+            "9 . .  3 . 5  . . 1\n",
+            ". . 1  8 . 6  4 . .\n",
+            "\n",
+            ". . 8  1 . 2  9 . .\n",
+            "7 . .  . O .  . . 8\n",
+            ". . 6  7 . 8  2 . .\n",
+            "\n",
+            ". . 2  6 . 9  5 . .\n",
+            "8 . .  2 . 3  . . 9\n",
+            ". . 5  . 1 .  3 . E\n",
+        );
+        let puzzle = s.parse::<Puzzle>().unwrap();
+        assert_eq!(*puzzle, *test_puzzle());
+        assert_eq!(puzzle.parity(0, 0), Some(Parity::Even));
+        assert_eq!(puzzle.parity(0, 1), None);
+        assert_eq!(puzzle.parity(0, 8), Some(Parity::Odd));
+        assert_eq!(puzzle.parity(4, 4), Some(Parity::Odd));
+        assert_eq!(puzzle.parity(8, 8), Some(Parity::Even));
+        assert_eq!(
+            puzzle.to_string(),
+            concat!(
+                "e0302060o\n",
+                "900305001\n",
+                "001806400\n",
+                "008102900\n",
+                "7000o0008\n",
+                "006708200\n",
+                "002609500\n",
+                "800203009\n",
+                "00501030e",
+            )
+        );
+        assert_eq!(
+            format!("{puzzle:#}"),
+            concat!(
+                "+-----+-----+-----+\n",
+                "|e   3|  2  |6   o|\n",
+                "|9    |3   5|    1|\n",
+                "|    1|8   6|4    |\n",
+                "+-----+-----+-----+\n",
+                "|    8|1   2|9    |\n",
+                "|7    |  o  |    8|\n",
+                "|    6|7   8|2    |\n",
+                "+-----+-----+-----+\n",
+                "|    2|6   9|5    |\n",
+                "|8    |2   3|    9|\n",
+                "|    5|  1  |3   e|\n",
+                "+-----+-----+-----+",
+            ),
+        );
+        assert_eq!(puzzle.to_string().parse::<Puzzle>(), Ok(puzzle));
+    }
+
+    #[test]
+    fn test_solve_parity() {
+        let solution = TEST_SOLUTION;
+        // Shade the first three rows according to the solution and clear
+        // them
+        let mut grid = solution;
+        let mut parities = [[None; 9]; 9];
+        for y in 0..3 {
+            for x in 0..9 {
+                grid[y][x] = 0;
+                parities[y][x] = Some(if solution[y][x] % 2 == 0 {
+                    Parity::Even
+                } else {
+                    Parity::Odd
+                });
+            }
+        }
+        let puzzle = Puzzle::from_grid(grid).with_parities(parities);
+        let Solution(found) = puzzle.solve().unwrap();
+        for y in 0..3 {
+            for x in 0..9 {
+                assert_eq!(found[y][x] % 2, solution[y][x] % 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_parity_mismatched_given() {
+        let mut parities = [[None; 9]; 9];
+        parities[0][2] = Some(Parity::Even);
+        let puzzle = test_puzzle().with_parities(parities);
+        assert_eq!(puzzle.solve(), None);
+    }
+
+    #[test]
+    fn test_solution_try_from_array() {
+        let grid = TEST_SOLUTION;
+        let solution = Solution::try_from(grid).unwrap();
+        assert_eq!(<[[u8; 9]; 9]>::from(solution), grid);
+        assert_eq!(Solution::try_from(grid.to_vec()), Ok(solution));
+    }
+
+    #[test]
+    fn test_solution_try_from_incomplete() {
+        let mut grid = TEST_SOLUTION;
+        grid[4][4] = 0;
+        let err = Solution::try_from(grid).unwrap_err();
+        assert_eq!(
+            err,
+            TryIntoSolutionError::Invalid(vec![Violation::Unfilled { cell: (4, 4) }])
+        );
+        assert_eq!(
+            err.to_string(),
+            "grid is not a valid solution: r5c5 is unfilled"
+        );
+    }
+
+    #[test]
+    fn test_solution_try_from_repeated() {
+        let mut grid = TEST_SOLUTION;
+        grid[8][8] = 3;
+        let err = Solution::try_from(grid).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            concat!(
+                "grid is not a valid solution: ",
+                "3 is repeated in row 9 at r9c7, r9c9; ",
+                "3 is repeated in column 9 at r3c9, r9c9; ",
+                "3 is repeated in box 9 at r9c7, r9c9"
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_solution() {
+        let s = "483921657\n967345821\n251876493\n548132976\n729564138\n136798245\n372689514\n814253769\n695417382\n";
+        let solution = s.parse::<Solution>().unwrap();
+        assert_eq!(solution.to_string(), s.trim_end());
+        assert_eq!(
+            "483921657\n967345821\n".parse::<Solution>(),
+            Err(TryIntoSolutionError::Parse(ParsePuzzleError::new(
+                ParsePuzzleErrorKind::TooFewRows(2),
+                3,
+                1,
+                ""
+            )))
+        );
+        assert_eq!(
+            s.replace('7', ".").parse::<Solution>().unwrap_err(),
+            TryIntoSolutionError::Invalid(
+                (0..9)
+                    .map(|y| Violation::Unfilled {
+                        cell: (y, solution[y].iter().position(|&d| d == 7).unwrap())
+                    })
+                    .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_error_short_row() {
+        let s = "..3.2.6..\n9..3.5..1\n..18.64.\n..81.29..\n7.......8\n..67.82..\n..26.95..\n8..2.3..9\n..5.1.3..\n";
+        let e = s.parse::<Puzzle>().unwrap_err();
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::BadRowSize(8));
+        assert_eq!(e.line(), 3);
+        assert_eq!(e.column(), 9);
+        assert_eq!(e.snippet(), "..18.64.");
+        assert_eq!(
+            e.to_string(),
+            "line 3, column 9: row is 8 cells long, expected 9: \"..18.64.\""
+        );
+    }
+
+    #[test]
+    fn test_parse_error_long_row() {
+        let s = "..3 .2. 6..\n9.. 3.5 ..1 2\n";
+        let e = s.parse::<Puzzle>().unwrap_err();
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::BadRowSize(10));
+        assert_eq!((e.line(), e.column()), (2, 13));
+        assert_eq!(e.snippet(), "9.. 3.5 ..1 2");
+    }
+
+    #[test]
+    fn test_parse_error_row_count() {
+        let s = "\n..3.2.6..\n9..3.5..1\n\n";
+        let e = s.parse::<Puzzle>().unwrap_err();
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::TooFewRows(2));
+        assert_eq!((e.line(), e.column()), (5, 1));
+        assert_eq!(e.snippet(), "");
+        assert_eq!(
+            e.to_string(),
+            "line 5, column 1: grid is only 2 rows long, expected 9"
+        );
+        let s = "000000000\n".repeat(10);
+        let e = s.parse::<Puzzle>().unwrap_err();
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::TooManyRows);
+        assert_eq!((e.line(), e.column()), (10, 1));
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        let s = "..3.2.6..\n9..3.5..1\n..18.64..\n..81.29..\n7.......8\n..67.82..\n..26.95..\n8..2.3..9\n..5.1.3..\n";
+        assert_eq!(Puzzle::from_str_strict(s), s.parse::<Puzzle>());
+        let s = s.replace("7.......8", "7...x...8");
+        assert!(s.parse::<Puzzle>().is_ok());
+        let e = Puzzle::from_str_strict(&s).unwrap_err();
+        assert_eq!(e.kind(), ParsePuzzleErrorKind::UnexpectedChar('x'));
+        assert_eq!((e.line(), e.column()), (5, 5));
+        assert_eq!(
+            e.to_string(),
+            "line 5, column 5: unexpected character 'x': \"7...x...8\""
+        );
+    }
 }
@@ -0,0 +1,867 @@
+//! Solving puzzles step by step with human-style techniques
+//!
+//! Rather than searching for a solution by trial and error like
+//! [`Puzzle::solve()`], the functions in this module make one logical
+//! deduction at a time, as a person solving the puzzle with pencil marks
+//! would.  Each deduction is a [`Step`] that either places a digit or removes
+//! candidates from a [`Position`], and comes with an explanation of the
+//! reasoning behind it.
+//!
+//! The supported techniques are listed by [`Technique`], from easiest to
+//! hardest.  A puzzle's [`Difficulty`] is determined by the hardest technique
+//! needed to solve it.
+use crate::pencilmarks::{peers, Position};
+use crate::verify::{Rc, RcList, Unit};
+use crate::Puzzle;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A technique for making a deduction about a puzzle
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Technique {
+    /// A cell has only one remaining candidate
+    NakedSingle,
+
+    /// A digit has only one remaining place in a row, column, or box
+    HiddenSingle,
+
+    /// A digit's remaining places in a box all lie in one row or column (or
+    /// vice versa), so it can be removed from the rest of that row or column
+    /// (or box)
+    LockedCandidates,
+
+    /// Two cells in a unit have the same two remaining candidates
+    NakedPair,
+
+    /// Two digits have the same two remaining places in a unit
+    HiddenPair,
+
+    /// Three cells in a unit have only three candidates between them
+    NakedTriple,
+
+    /// Three digits have only three remaining places in a unit between them
+    HiddenTriple,
+
+    /// A digit's remaining places in two rows (or columns) lie in the same
+    /// two columns (or rows)
+    XWing,
+}
+
+impl Technique {
+    /// Return the difficulty of puzzles whose hardest required technique is
+    /// this one
+    pub fn difficulty(self) -> Difficulty {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle => Difficulty::Easy,
+            Technique::LockedCandidates => Difficulty::Medium,
+            Technique::NakedPair
+            | Technique::HiddenPair
+            | Technique::NakedTriple
+            | Technique::HiddenTriple => Difficulty::Hard,
+            Technique::XWing => Difficulty::Expert,
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Technique::NakedSingle => "Naked single",
+            Technique::HiddenSingle => "Hidden single",
+            Technique::LockedCandidates => "Locked candidates",
+            Technique::NakedPair => "Naked pair",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::NakedTriple => "Naked triple",
+            Technique::HiddenTriple => "Hidden triple",
+            Technique::XWing => "X-Wing",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// How hard a puzzle is to solve without guessing
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Difficulty {
+    /// The puzzle can be solved with singles alone
+    Easy,
+
+    /// The puzzle requires locked candidates
+    Medium,
+
+    /// The puzzle requires naked or hidden pairs or triples
+    Hard,
+
+    /// The puzzle requires X-Wings
+    Expert,
+
+    /// The puzzle cannot be solved with the supported techniques, and so
+    /// requires trial and error (or has no unique solution)
+    Extreme,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+            Difficulty::Extreme => "extreme",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Parse a difficulty from its name, ignoring case
+///
+/// # Errors
+///
+/// Fails if the name is not recognized.
+impl FromStr for Difficulty {
+    type Err = ParseDifficultyError;
+
+    fn from_str(s: &str) -> Result<Difficulty, ParseDifficultyError> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "expert" => Ok(Difficulty::Expert),
+            "extreme" => Ok(Difficulty::Extreme),
+            _ => Err(ParseDifficultyError),
+        }
+    }
+}
+
+/// Error type returned when parsing an unknown [`Difficulty`]
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+#[error("unknown difficulty; expected \"easy\", \"medium\", \"hard\", \"expert\", or \"extreme\"")]
+pub struct ParseDifficultyError;
+
+/// A single deduction about a position: either placing a digit in a cell or
+/// removing one or more candidates
+///
+/// The [`Display`](fmt::Display) implementation explains the deduction in
+/// English, e.g., "Hidden single: r1c3 is the only place for 4 in box 1, so
+/// r1c3 = 4".
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Step {
+    technique: Technique,
+    placement: Option<((usize, usize), u8)>,
+    eliminations: Vec<((usize, usize), u8)>,
+    reason: String,
+}
+
+impl Step {
+    /// Return the technique used to make the deduction
+    pub fn technique(&self) -> Technique {
+        self.technique
+    }
+
+    /// Return the cell & digit placed by the step, if any
+    pub fn placement(&self) -> Option<((usize, usize), u8)> {
+        self.placement
+    }
+
+    /// Return the candidates removed by the step as `(cell, digit)` pairs.
+    /// Steps that place a digit return an empty list, as the resulting
+    /// eliminations are implied by the placement.
+    pub fn eliminations(&self) -> &[((usize, usize), u8)] {
+        &self.eliminations
+    }
+
+    /// Apply the step to `position`.  Placing a digit also removes it from
+    /// the candidates of the cell's peers.
+    pub fn apply(&self, position: &mut Position) {
+        if let Some(((y, x), d)) = self.placement {
+            position.placed[y][x] = d;
+            for other in 1..=9 {
+                if other != d {
+                    position.candidates.remove(y, x, other);
+                }
+            }
+            for (py, px) in peers(y, x) {
+                position.candidates.remove(py, px, d);
+            }
+        }
+        for &((y, x), d) in &self.eliminations {
+            position.candidates.remove(y, x, d);
+        }
+    }
+
+    fn place(technique: Technique, cell: (usize, usize), digit: u8, why: String) -> Step {
+        Step {
+            technique,
+            placement: Some((cell, digit)),
+            eliminations: Vec::new(),
+            reason: format!("{why}, so {} = {digit}", Rc(cell)),
+        }
+    }
+
+    fn eliminate(
+        technique: Technique,
+        eliminations: Vec<((usize, usize), u8)>,
+        why: String,
+    ) -> Option<Step> {
+        if eliminations.is_empty() {
+            return None;
+        }
+        let mut removals = Vec::new();
+        for d in 1..=9 {
+            let cells = eliminations
+                .iter()
+                .filter(|&&(_, e)| e == d)
+                .map(|&(c, _)| c)
+                .collect::<Vec<_>>();
+            if !cells.is_empty() {
+                removals.push(format!("{d} from {}", RcList(&cells)));
+            }
+        }
+        Some(Step {
+            technique,
+            placement: None,
+            eliminations,
+            reason: format!("{why}, so remove {}", removals.join("; ")),
+        })
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.technique, self.reason)
+    }
+}
+
+/// The result of solving a puzzle step by step
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Explanation {
+    /// The steps taken, in order
+    pub steps: Vec<Step>,
+
+    /// The position after taking all of the steps
+    pub position: Position,
+
+    /// Whether the steps solved the puzzle.  If this is `false`, no further
+    /// deductions could be made with the supported techniques.
+    pub solved: bool,
+}
+
+/// A puzzle's difficulty rating
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Rating {
+    /// The puzzle's difficulty
+    pub difficulty: Difficulty,
+
+    /// The hardest technique used while solving the puzzle, or `None` if
+    /// the puzzle was already solved
+    pub hardest: Option<Technique>,
+
+    /// The number of steps taken while solving the puzzle
+    pub steps: usize,
+}
+
+/// Find the next deduction that can be made about `position`, using the
+/// easiest applicable technique.
+///
+/// Returns `None` if the position is solved, if it contains an unsolved cell
+/// with no candidates, or if no deduction can be made with the supported
+/// techniques.
+pub fn next_step(position: &Position) -> Option<Step> {
+    let state = State::new(position)?;
+    state
+        .naked_single()
+        .or_else(|| state.hidden_single())
+        .or_else(|| state.locked_candidates())
+        .or_else(|| state.naked_subset(2))
+        .or_else(|| state.hidden_subset(2))
+        .or_else(|| state.naked_subset(3))
+        .or_else(|| state.hidden_subset(3))
+        .or_else(|| state.x_wing())
+}
+
+/// Solve `puzzle` step by step, starting from its initial position, until it
+/// is solved or no further deductions can be made
+pub fn explain(puzzle: &Puzzle) -> Explanation {
+    explain_from(Position::new(*puzzle))
+}
+
+/// Solve a partially solved position step by step, such as one imported from
+/// another solver's pencil marks, until it is solved or no further
+/// deductions can be made
+pub fn explain_from(mut position: Position) -> Explanation {
+    let mut steps = Vec::new();
+    while let Some(step) = next_step(&position) {
+        step.apply(&mut position);
+        steps.push(step);
+    }
+    let solved = position.grid().iter().flatten().all(|&d| d != 0);
+    Explanation {
+        steps,
+        position,
+        solved,
+    }
+}
+
+/// Rate the difficulty of `puzzle` by solving it step by step
+pub fn rate(puzzle: &Puzzle) -> Rating {
+    let explanation = explain(puzzle);
+    let hardest = explanation.steps.iter().map(Step::technique).max();
+    let difficulty = if explanation.solved {
+        hardest.map_or(Difficulty::Easy, Technique::difficulty)
+    } else {
+        Difficulty::Extreme
+    };
+    Rating {
+        difficulty,
+        hardest,
+        steps: explanation.steps.len(),
+    }
+}
+
+/// Test whether `puzzle` can be solved using only naked & hidden singles
+pub(crate) fn solves_with_singles(puzzle: &Puzzle) -> bool {
+    let mut position = Position::new(*puzzle);
+    while let Some(state) = State::new(&position) {
+        match state.naked_single().or_else(|| state.hidden_single()) {
+            Some(step) => step.apply(&mut position),
+            None => return false,
+        }
+    }
+    // `State::new()` also fails on positions with contradictions:
+    position.grid().iter().flatten().all(|&d| d != 0)
+}
+
+/// The solved digits & candidate bitmasks of a position, with candidates
+/// seen by solved cells removed.  Solved cells have no candidates.
+struct State {
+    cands: [[u16; 9]; 9],
+}
+
+impl State {
+    fn new(position: &Position) -> Option<State> {
+        let grid = position.grid();
+        let mut cands = [[0; 9]; 9];
+        let mut unsolved = false;
+        for y in 0..9 {
+            for x in 0..9 {
+                if grid[y][x] == 0 {
+                    unsolved = true;
+                    let mut mask = 0;
+                    for d in position.candidates.digits(y, x) {
+                        mask |= 1 << d;
+                    }
+                    for (py, px) in peers(y, x) {
+                        mask &= !(1 << grid[py][px]);
+                    }
+                    if mask == 0 {
+                        return None;
+                    }
+                    cands[y][x] = mask;
+                }
+            }
+        }
+        unsolved.then_some(State { cands })
+    }
+
+    fn has(&self, (y, x): (usize, usize), d: u8) -> bool {
+        self.cands[y][x] & (1 << d) != 0
+    }
+
+    /// Returns the unsolved cells of `unit` that have `d` as a candidate
+    fn places(&self, unit: Unit, d: u8) -> Vec<(usize, usize)> {
+        unit.cells()
+            .into_iter()
+            .filter(|&c| self.has(c, d))
+            .collect()
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        for y in 0..9 {
+            for x in 0..9 {
+                let mask = self.cands[y][x];
+                if mask.is_power_of_two() {
+                    let d = digits(mask)[0];
+                    return Some(Step::place(
+                        Technique::NakedSingle,
+                        (y, x),
+                        d,
+                        format!("{} can only contain {d}", Rc((y, x))),
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for unit in boxes_first() {
+            for d in 1..=9 {
+                if let [cell] = self.places(unit, d)[..] {
+                    return Some(Step::place(
+                        Technique::HiddenSingle,
+                        cell,
+                        d,
+                        format!("{} is the only place for {d} in {unit}", Rc(cell)),
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    fn locked_candidates(&self) -> Option<Step> {
+        // Pointing: a digit's places in a box lie in one row/column.
+        for b in 0..9 {
+            let unit = Unit::Box(b);
+            for d in 1..=9 {
+                let places = self.places(unit, d);
+                if places.is_empty() {
+                    continue;
+                }
+                for line in [Unit::Row(places[0].0), Unit::Column(places[0].1)] {
+                    let lc = line.cells();
+                    if places.iter().all(|c| lc.contains(c)) {
+                        let elims = self.eliminations_outside(line, &places, &[d]);
+                        if let Some(step) = Step::eliminate(
+                            Technique::LockedCandidates,
+                            elims,
+                            format!("in {unit}, {d} can only go in {line}"),
+                        ) {
+                            return Some(step);
+                        }
+                    }
+                }
+            }
+        }
+        // Claiming: a digit's places in a row/column lie in one box.
+        for line in (0..9).map(Unit::Row).chain((0..9).map(Unit::Column)) {
+            for d in 1..=9 {
+                let places = self.places(line, d);
+                if places.is_empty() {
+                    continue;
+                }
+                let (y, x) = places[0];
+                let unit = Unit::Box((y / 3) * 3 + x / 3);
+                let bc = unit.cells();
+                if places.iter().all(|c| bc.contains(c)) {
+                    let elims = self.eliminations_outside(unit, &places, &[d]);
+                    if let Some(step) = Step::eliminate(
+                        Technique::LockedCandidates,
+                        elims,
+                        format!("in {line}, {d} can only go in {unit}"),
+                    ) {
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_subset(&self, n: usize) -> Option<Step> {
+        let technique = if n == 2 {
+            Technique::NakedPair
+        } else {
+            Technique::NakedTriple
+        };
+        for unit in Unit::all() {
+            let unsolved = unit
+                .cells()
+                .into_iter()
+                .filter(|&(y, x)| self.cands[y][x] != 0)
+                .collect::<Vec<_>>();
+            if unsolved.len() <= n {
+                continue;
+            }
+            for cells in combinations(&unsolved, n) {
+                let union = cells.iter().fold(0, |acc, &(y, x)| acc | self.cands[y][x]);
+                if union.count_ones() as usize == n {
+                    let ds = digits(union);
+                    let elims = self.eliminations_outside(unit, &cells, &ds);
+                    if let Some(step) = Step::eliminate(
+                        technique,
+                        elims,
+                        format!(
+                            "{} in {unit} can only contain {}",
+                            RcList(&cells),
+                            and_list(&ds)
+                        ),
+                    ) {
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn hidden_subset(&self, n: usize) -> Option<Step> {
+        let technique = if n == 2 {
+            Technique::HiddenPair
+        } else {
+            Technique::HiddenTriple
+        };
+        for unit in Unit::all() {
+            let open = (1..=9)
+                .filter(|&d| !self.places(unit, d).is_empty())
+                .collect::<Vec<u8>>();
+            if open.len() <= n {
+                continue;
+            }
+            for ds in combinations(&open, n) {
+                let mut cells = Vec::new();
+                for &d in &ds {
+                    for c in self.places(unit, d) {
+                        if !cells.contains(&c) {
+                            cells.push(c);
+                        }
+                    }
+                }
+                if cells.len() == n {
+                    cells.sort_unstable();
+                    let mut elims = Vec::new();
+                    for &c in &cells {
+                        for d in digits(self.cands[c.0][c.1]) {
+                            if !ds.contains(&d) {
+                                elims.push((c, d));
+                            }
+                        }
+                    }
+                    if let Some(step) = Step::eliminate(
+                        technique,
+                        elims,
+                        format!(
+                            "in {unit}, {} can only go in {}",
+                            and_list(&ds),
+                            RcList(&cells)
+                        ),
+                    ) {
+                        return Some(step);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn x_wing(&self) -> Option<Step> {
+        type Line = fn(usize) -> Unit;
+        // Each orientation also projects a cell onto the index of the cover
+        // line containing it
+        type Project = fn((usize, usize)) -> usize;
+        let orientations: [(Line, Line, Project, &str, &str); 2] = [
+            (Unit::Row, Unit::Column, |(_, x)| x, "rows", "columns"),
+            (Unit::Column, Unit::Row, |(y, _)| y, "columns", "rows"),
+        ];
+        for (base, cover, project, base_name, cover_name) in orientations {
+            for d in 1..=9 {
+                // For each base line, the indices of the cover lines in which
+                // `d` can go
+                let spots = (0..9)
+                    .map(|i| {
+                        self.places(base(i), d)
+                            .into_iter()
+                            .map(project)
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                for i in 0..9 {
+                    if spots[i].len() != 2 {
+                        continue;
+                    }
+                    for j in (i + 1)..9 {
+                        if spots[j] != spots[i] {
+                            continue;
+                        }
+                        let mut corners = Vec::new();
+                        for &k in &[i, j] {
+                            for c in self.places(base(k), d) {
+                                corners.push(c);
+                            }
+                        }
+                        let mut elims = Vec::new();
+                        for &k in &spots[i] {
+                            for c in self.places(cover(k), d) {
+                                if !corners.contains(&c) {
+                                    elims.push((c, d));
+                                }
+                            }
+                        }
+                        elims.sort_unstable();
+                        if let Some(step) = Step::eliminate(
+                            Technique::XWing,
+                            elims,
+                            format!(
+                                "in {base_name} {} and {}, {d} can only go in {cover_name} {} and {}",
+                                i + 1,
+                                j + 1,
+                                spots[i][0] + 1,
+                                spots[i][1] + 1
+                            ),
+                        ) {
+                            return Some(step);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the candidates among `ds` of the cells of `unit` other than
+    /// `cells`
+    fn eliminations_outside(
+        &self,
+        unit: Unit,
+        cells: &[(usize, usize)],
+        ds: &[u8],
+    ) -> Vec<((usize, usize), u8)> {
+        let mut elims = Vec::new();
+        for c in unit.cells() {
+            if !cells.contains(&c) {
+                for &d in ds {
+                    if self.has(c, d) {
+                        elims.push((c, d));
+                    }
+                }
+            }
+        }
+        elims
+    }
+}
+
+/// Returns all units with the boxes first, as hidden singles are easiest to
+/// spot in boxes
+fn boxes_first() -> impl Iterator<Item = Unit> {
+    (0..9)
+        .map(Unit::Box)
+        .chain((0..9).map(Unit::Row))
+        .chain((0..9).map(Unit::Column))
+}
+
+/// Returns the digits in a candidate bitmask in ascending order
+fn digits(mask: u16) -> Vec<u8> {
+    (1..=9).filter(|&d| mask & (1 << d) != 0).collect()
+}
+
+/// Returns all `n`-element combinations of `items`, preserving order
+fn combinations<T: Copy>(items: &[T], n: usize) -> Vec<Vec<T>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut combos = Vec::new();
+    for (i, &first) in items.iter().enumerate() {
+        for mut rest in combinations(&items[(i + 1)..], n - 1) {
+            rest.insert(0, first);
+            combos.push(rest);
+        }
+    }
+    combos
+}
+
+/// Format a list of digits in English, e.g., "1, 2, and 3"
+fn and_list(ds: &[u8]) -> String {
+    match ds {
+        [] => String::new(),
+        [a] => a.to_string(),
+        [a, b] => format!("{a} and {b}"),
+        [init @ .., last] => {
+            let mut s = String::new();
+            for d in init {
+                s.push_str(&d.to_string());
+                s.push_str(", ");
+            }
+            s.push_str("and ");
+            s.push_str(&last.to_string());
+            s
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_explain_easy() {
+        let puzzle = test_puzzle();
+        let explanation = explain(&puzzle);
+        assert!(explanation.solved);
+        assert_eq!(explanation.steps.len(), 49);
+        assert_eq!(
+            Some(explanation.position.grid()),
+            puzzle.solve().map(<[[u8; 9]; 9]>::from)
+        );
+        assert!(explanation
+            .steps
+            .iter()
+            .all(|s| s.technique().difficulty() == Difficulty::Easy));
+        assert_eq!(
+            rate(&puzzle),
+            Rating {
+                difficulty: Difficulty::Easy,
+                hardest: explanation.steps.iter().map(Step::technique).max(),
+                steps: 49,
+            }
+        );
+    }
+
+    #[test]
+    fn test_explain_from() {
+        let puzzle = test_puzzle();
+        let full = explain(&puzzle);
+        let mut position = Position::new(puzzle);
+        for step in &full.steps[..10] {
+            step.apply(&mut position);
+        }
+        let rest = explain_from(position);
+        assert!(rest.solved);
+        assert_eq!(rest.steps, full.steps[10..]);
+        assert_eq!(rest.position, full.position);
+    }
+
+    #[test]
+    fn test_first_step() {
+        let step = next_step(&Position::new(test_puzzle())).unwrap();
+        assert_eq!(step.technique(), Technique::NakedSingle);
+        assert_eq!(step.placement(), Some(((4, 5), 4)));
+        assert_eq!(
+            step.to_string(),
+            "Naked single: r5c6 can only contain 4, so r5c6 = 4"
+        );
+    }
+
+    #[test]
+    fn test_hidden_single() {
+        let puzzle: Puzzle = ".........\n...1.....\n......1..\n1........\n.........\n.........\n.1.......\n.........\n.........\n"
+            .parse()
+            .unwrap();
+        let step = State::new(&Position::new(puzzle))
+            .unwrap()
+            .hidden_single()
+            .unwrap();
+        assert_eq!(step.technique(), Technique::HiddenSingle);
+        assert_eq!(step.placement(), Some(((0, 2), 1)));
+        assert_eq!(
+            step.to_string(),
+            "Hidden single: r1c3 is the only place for 1 in box 1, so r1c3 = 1"
+        );
+    }
+
+    #[test]
+    fn test_locked_candidates() {
+        // In box 1, 1 can only go in row 1.
+        let puzzle: Puzzle = ".........\n234......\n567......\n.........\n.........\n.........\n.........\n.........\n.........\n"
+            .parse()
+            .unwrap();
+        let step = State::new(&Position::new(puzzle))
+            .unwrap()
+            .locked_candidates()
+            .unwrap();
+        assert_eq!(step.technique(), Technique::LockedCandidates);
+        assert_eq!(
+            step.eliminations(),
+            (3..9).map(|x| ((0, x), 1)).collect::<Vec<_>>().as_slice()
+        );
+        assert_eq!(
+            step.to_string(),
+            "Locked candidates: in box 1, 1 can only go in row 1, so remove 1 from r1c4, r1c5, r1c6, r1c7, r1c8, r1c9"
+        );
+    }
+
+    #[test]
+    fn test_naked_pair() {
+        let mut pos = Position::new(
+            ".........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n"
+                .parse()
+                .unwrap(),
+        );
+        for d in 3..=9 {
+            pos.candidates.remove(0, 0, d);
+            pos.candidates.remove(0, 1, d);
+        }
+        let step = State::new(&pos).unwrap().naked_subset(2).unwrap();
+        assert_eq!(step.technique(), Technique::NakedPair);
+        assert_eq!(step.eliminations().len(), 14);
+        assert!(step.to_string().starts_with(
+            "Naked pair: r1c1, r1c2 in row 1 can only contain 1 and 2, so remove 1 from r1c3"
+        ));
+    }
+
+    #[test]
+    fn test_hidden_pair() {
+        let mut pos = Position::new(
+            ".........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n"
+                .parse()
+                .unwrap(),
+        );
+        for x in 2..9 {
+            pos.candidates.remove(0, x, 1);
+            pos.candidates.remove(0, x, 2);
+        }
+        let step = State::new(&pos).unwrap().hidden_subset(2).unwrap();
+        assert_eq!(step.technique(), Technique::HiddenPair);
+        assert_eq!(
+            step.to_string(),
+            "Hidden pair: in row 1, 1 and 2 can only go in r1c1, r1c2, so remove 3 from r1c1, r1c2; 4 from r1c1, r1c2; 5 from r1c1, r1c2; 6 from r1c1, r1c2; 7 from r1c1, r1c2; 8 from r1c1, r1c2; 9 from r1c1, r1c2"
+        );
+    }
+
+    #[test]
+    fn test_x_wing() {
+        let mut pos = Position::new(
+            ".........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n"
+                .parse()
+                .unwrap(),
+        );
+        for x in [1, 2, 3, 4, 5, 6, 8] {
+            pos.candidates.remove(0, x, 5);
+            pos.candidates.remove(4, x, 5);
+        }
+        let step = State::new(&pos).unwrap().x_wing().unwrap();
+        assert_eq!(step.technique(), Technique::XWing);
+        assert_eq!(step.eliminations().len(), 14);
+        assert!(step
+            .eliminations()
+            .iter()
+            .all(|&((y, x), d)| d == 5 && (x == 0 || x == 7) && y != 0 && y != 4));
+        assert!(step
+            .to_string()
+            .starts_with("X-Wing: in rows 1 and 5, 5 can only go in columns 1 and 8, so remove 5 from r2c1, r2c8,"));
+    }
+
+    #[test]
+    fn test_apply_step() {
+        let mut pos = Position::new(test_puzzle());
+        let step = next_step(&pos).unwrap();
+        step.apply(&mut pos);
+        assert_eq!(pos.placed[4][5], 4);
+        assert_eq!(pos.candidates.digits(4, 5), vec![4]);
+        assert!(!pos.candidates.contains(4, 1, 4));
+    }
+
+    #[test]
+    fn test_rate_unsolvable_by_logic() {
+        let puzzle: Puzzle = "000000000\n000000000\n000000000\n000000000\n000000000\n000000000\n000000000\n000000000\n000000001\n"
+            .parse()
+            .unwrap();
+        let rating = rate(&puzzle);
+        assert_eq!(rating.difficulty, Difficulty::Extreme);
+        assert!(!explain(&puzzle).solved);
+    }
+
+    #[test]
+    fn test_difficulty_from_str() {
+        assert_eq!("Hard".parse::<Difficulty>(), Ok(Difficulty::Hard));
+        assert_eq!("trivial".parse::<Difficulty>(), Err(ParseDifficultyError));
+        assert_eq!(Difficulty::Expert.to_string(), "expert");
+    }
+
+    #[test]
+    fn test_and_list() {
+        assert_eq!(and_list(&[4]), "4");
+        assert_eq!(and_list(&[4, 8]), "4 and 8");
+        assert_eq!(and_list(&[1, 4, 8]), "1, 4, and 8");
+    }
+}
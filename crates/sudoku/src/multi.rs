@@ -0,0 +1,733 @@
+//! Overlapping multi-grid Sudoku puzzles, such as Samurai Sudoku
+//!
+//! A [`MultiPuzzle`] is made up of several 9×9 grids placed at offsets on a
+//! larger board.  Where grids overlap, the shared cells belong to every grid
+//! covering them, and the whole board is solved as a single linked problem.
+//!
+//! Grid offsets must be multiples of 3, so that the grids' boxes line up with
+//! one another.
+use crate::{Puzzle, Solution};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The offsets of the five grids in a Samurai Sudoku, in reading order
+pub static SAMURAI_OFFSETS: [(usize, usize); 5] = [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+
+/// An unsolved puzzle made up of several overlapping 9×9 grids.
+///
+/// `MultiPuzzle` instances can be constructed from a list of offset [`Puzzle`]s
+/// with [`MultiPuzzle::new()`] or [`MultiPuzzle::samurai()`], or from a string
+/// using [`FromStr`]/[`str::parse()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MultiPuzzle(Board);
+
+impl MultiPuzzle {
+    /// Construct a multi-grid puzzle from a list of grids, each paired with
+    /// the `(y, x)` offset of its top-left cell on the board.
+    ///
+    /// Wherever grids overlap, their cells must either agree or be unfilled in
+    /// all but one grid.
+    ///
+    /// Even & odd shading is not supported on multi-grid puzzles, so any
+    /// parities set on the given `Puzzle`s (see [`Puzzle::parity()`]) are
+    /// discarded.
+    ///
+    /// # Errors
+    ///
+    /// Fails if no grids are given, if an offset is not a multiple of 3, if
+    /// two grids are placed at the same offset, or if overlapping grids have
+    /// different digits in a shared cell.
+    pub fn new(grids: Vec<((usize, usize), Puzzle)>) -> Result<MultiPuzzle, MultiPuzzleError> {
+        let offsets = grids.iter().map(|&(off, _)| off).collect::<Vec<_>>();
+        let mut board = Board::new(offsets)?;
+        for ((oy, ox), puzzle) in grids {
+            for (y, row) in puzzle.iter().enumerate() {
+                for (x, &c) in row.iter().enumerate() {
+                    if let Some(cell) = board.cells[oy + y][ox + x].as_mut() {
+                        match (*cell, c) {
+                            (_, 0) => (),
+                            (0, _) => *cell = c,
+                            (a, b) if a != b => {
+                                return Err(MultiPuzzleError::Conflict(oy + y, ox + x));
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
+        }
+        Ok(MultiPuzzle(board))
+    }
+
+    /// Construct a Samurai Sudoku from its five grids, given in reading order:
+    /// top-left, top-right, center, bottom-left, bottom-right.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the corner boxes shared with the center grid have different
+    /// digits in the same cell.
+    pub fn samurai(grids: [Puzzle; 5]) -> Result<MultiPuzzle, MultiPuzzleError> {
+        MultiPuzzle::new(SAMURAI_OFFSETS.into_iter().zip(grids).collect())
+    }
+
+    /// Return the `(y, x)` offsets of the puzzle's grids, in reading order
+    pub fn offsets(&self) -> &[(usize, usize)] {
+        &self.0.offsets
+    }
+
+    /// Return the puzzle's individual grids, in the same order as
+    /// [`MultiPuzzle::offsets()`]
+    pub fn grids(&self) -> Vec<Puzzle> {
+        self.0.grids().into_iter().map(Puzzle::from_grid).collect()
+    }
+
+    /// Return the height & width of the board
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.0.dimensions()
+    }
+
+    /// Return the value of the cell at the given position on the board, or
+    /// `None` if the position is not covered by any grid.  "Unfilled" cells
+    /// are represented by 0.
+    pub fn get(&self, y: usize, x: usize) -> Option<u8> {
+        self.0.get(y, x)
+    }
+
+    /// Solve the puzzle, treating all of the grids as a single problem so that
+    /// shared cells take the same digit in every grid covering them.
+    ///
+    /// If the puzzle has multiple solutions, one of them is returned, but
+    /// which one is unspecified.
+    ///
+    /// If the puzzle has no solutions, `None` is returned.
+    pub fn solve(&self) -> Option<MultiSolution> {
+        let mut linked = Linked::new(&self.0);
+        if !linked.is_consistent() || !linked.search() {
+            return None;
+        }
+        let mut board = self.0.clone();
+        for (&(y, x), &v) in linked.positions.iter().zip(&linked.values) {
+            board.cells[y][x] = Some(v);
+        }
+        Some(MultiSolution(board))
+    }
+}
+
+/// Parse a [`MultiPuzzle`] from a string in which each line is a row of the
+/// board and each character is a column.  Cells are written as for
+/// [`Puzzle`]'s [`FromStr`] implementation — a digit in `0..=9` or any other
+/// non-space character for an "unfilled" cell — while spaces mark positions
+/// not covered by any grid.  Lines consisting entirely of whitespace are
+/// ignored.
+///
+/// The placement of the grids is inferred from the layout of the cells: the
+/// board is divided into 3×3 boxes, each of which must be either entirely
+/// cells or entirely spaces, and every 3×3 block of boxes consisting entirely
+/// of cells is taken to be a grid.
+///
+/// For example, a Samurai Sudoku is written as:
+///
+/// ```text
+/// ..3.2.6..   ..3.2.6..
+/// 9..3.5..1   9..3.5..1
+/// ..18.64..   ..18.64..
+/// ..81.29..   ..81.29..
+/// 7.......8   7.......8
+/// ..67.82..   ..67.82..
+/// ..26.95.......26.95..
+/// 8..2.3..9...8..2.3..9
+/// ..5.1.3.......5.1.3..
+///       .........
+///       .........
+///       .........
+/// ..3.2.6.......3.2.6..
+/// 9..3.5..1...9..3.5..1
+/// ..18.64.......18.64..
+/// ..81.29..   ..81.29..
+/// 7.......8   7.......8
+/// ..67.82..   ..67.82..
+/// ..26.95..   ..26.95..
+/// 8..2.3..9   8..2.3..9
+/// ..5.1.3..   ..5.1.3..
+/// ```
+///
+/// # Errors
+///
+/// Fails if a box is only partly made of cells, if a cell is not part of any
+/// complete grid, or if the board does not contain any complete grids.
+impl FromStr for MultiPuzzle {
+    type Err = MultiPuzzleError;
+
+    fn from_str(s: &str) -> Result<MultiPuzzle, MultiPuzzleError> {
+        let mut rows = Vec::new();
+        for line in s.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut row = Vec::new();
+            for c in line.trim_end().chars() {
+                if c == ' ' {
+                    row.push(None);
+                } else if let Some(x) = c.to_digit(10) {
+                    row.push(Some(
+                        u8::try_from(x).expect("base-10 digits should fit in a u8"),
+                    ));
+                } else {
+                    row.push(Some(0));
+                }
+            }
+            rows.push(row);
+        }
+        let height = rows.len();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let cell_at = |y: usize, x: usize| rows.get(y).and_then(|r| r.get(x).copied()).flatten();
+        let box_rows = (height + 2) / 3;
+        let box_cols = (width + 2) / 3;
+        let mut boxes = vec![vec![false; box_cols]; box_rows];
+        for (by, brow) in boxes.iter_mut().enumerate() {
+            for (bx, present) in brow.iter_mut().enumerate() {
+                let mut count = 0;
+                for y in by * 3..by * 3 + 3 {
+                    for x in bx * 3..bx * 3 + 3 {
+                        if cell_at(y, x).is_some() {
+                            count += 1;
+                        }
+                    }
+                }
+                match count {
+                    0 => (),
+                    9 => *present = true,
+                    _ => return Err(MultiPuzzleError::PartialBox(by * 3, bx * 3)),
+                }
+            }
+        }
+        let mut offsets = Vec::new();
+        for by in 0..box_rows.saturating_sub(2) {
+            for bx in 0..box_cols.saturating_sub(2) {
+                if (by..by + 3).all(|i| (bx..bx + 3).all(|j| boxes[i][j])) {
+                    offsets.push((by * 3, bx * 3));
+                }
+            }
+        }
+        let mut board = Board::new(offsets)?;
+        for y in 0..height {
+            for x in 0..width {
+                match (
+                    cell_at(y, x),
+                    board.cells.get_mut(y).and_then(|r| r.get_mut(x)),
+                ) {
+                    (Some(c), Some(Some(cell))) => *cell = c,
+                    (Some(_), _) => return Err(MultiPuzzleError::Uncovered(y, x)),
+                    (None, _) => (),
+                }
+            }
+        }
+        Ok(MultiPuzzle(board))
+    }
+}
+
+/// Display a [`MultiPuzzle`] as its rows of cells, with positions not covered
+/// by any grid shown as spaces and trailing spaces omitted.
+///
+/// In the default representation, "unfilled" cells are represented by `0`,
+/// and there is no additional horizontal whitespace, giving the same layout
+/// accepted by [`MultiPuzzle`]'s [`FromStr`] implementation.
+///
+/// In the alternate representation (selected with the `#` modifier), borders
+/// are drawn around every box, adjacent cells are separated with a space, and
+/// "unfilled" cells are represented by a space, in the same style as the
+/// alternate representation of [`Puzzle`].
+///
+/// Both forms lack a final terminating newline.
+impl fmt::Display for MultiPuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A solution to a [`MultiPuzzle`].
+///
+/// `MultiSolution` instances are returned by [`MultiPuzzle::solve()`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MultiSolution(Board);
+
+impl MultiSolution {
+    /// Return the `(y, x)` offsets of the solution's grids, in reading order
+    pub fn offsets(&self) -> &[(usize, usize)] {
+        &self.0.offsets
+    }
+
+    /// Return the solutions to the individual grids, in the same order as
+    /// [`MultiSolution::offsets()`]
+    pub fn grids(&self) -> Vec<Solution> {
+        self.0.grids().into_iter().map(Solution).collect()
+    }
+
+    /// Return the height & width of the board
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.0.dimensions()
+    }
+
+    /// Return the digit in the cell at the given position on the board, or
+    /// `None` if the position is not covered by any grid
+    pub fn get(&self, y: usize, x: usize) -> Option<u8> {
+        self.0.get(y, x)
+    }
+}
+
+/// Display a [`MultiSolution`] as its rows of cells, with positions not
+/// covered by any grid shown as spaces and trailing spaces omitted.
+///
+/// The default and alternate (`#`) representations are the same as for
+/// [`MultiPuzzle`].
+///
+/// Both forms lack a final terminating newline.
+impl fmt::Display for MultiSolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The cells of a multi-grid board along with the offsets of its grids
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Board {
+    offsets: Vec<(usize, usize)>,
+    // None = position not covered by any grid
+    cells: Vec<Vec<Option<u8>>>,
+}
+
+impl Board {
+    fn new(offsets: Vec<(usize, usize)>) -> Result<Board, MultiPuzzleError> {
+        if offsets.is_empty() {
+            return Err(MultiPuzzleError::NoGrids);
+        }
+        for (i, &(oy, ox)) in offsets.iter().enumerate() {
+            if oy % 3 != 0 || ox % 3 != 0 {
+                return Err(MultiPuzzleError::Misaligned(oy, ox));
+            }
+            if offsets[..i].contains(&(oy, ox)) {
+                return Err(MultiPuzzleError::Duplicate(oy, ox));
+            }
+        }
+        let height = offsets.iter().map(|&(oy, _)| oy + 9).max().unwrap_or(0);
+        let width = offsets.iter().map(|&(_, ox)| ox + 9).max().unwrap_or(0);
+        let mut cells = vec![vec![None; width]; height];
+        for &(oy, ox) in &offsets {
+            for row in &mut cells[oy..oy + 9] {
+                for c in &mut row[ox..ox + 9] {
+                    *c = Some(0);
+                }
+            }
+        }
+        Ok(Board { offsets, cells })
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (self.cells.len(), self.cells.first().map_or(0, Vec::len))
+    }
+
+    fn get(&self, y: usize, x: usize) -> Option<u8> {
+        self.cells.get(y)?.get(x).copied().flatten()
+    }
+
+    fn grids(&self) -> Vec<[[u8; 9]; 9]> {
+        self.offsets
+            .iter()
+            .map(|&(oy, ox)| {
+                let mut grid = [[0; 9]; 9];
+                for (y, row) in grid.iter_mut().enumerate() {
+                    for (x, c) in row.iter_mut().enumerate() {
+                        *c = self.cells[oy + y][ox + x].unwrap_or(0);
+                    }
+                }
+                grid
+            })
+            .collect()
+    }
+
+    fn is_box_present(&self, by: usize, bx: usize) -> bool {
+        self.get(by * 3, bx * 3).is_some()
+    }
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = Vec::new();
+        if f.alternate() {
+            let (height, width) = self.dimensions();
+            let (box_rows, box_cols) = (height / 3, width / 3);
+            let present = |by: Option<usize>, bx: Option<usize>| match (by, bx) {
+                (Some(by), Some(bx)) if by < box_rows && bx < box_cols => {
+                    self.is_box_present(by, bx)
+                }
+                _ => false,
+            };
+            for by in 0..=box_rows {
+                let mut divider = String::new();
+                for bx in 0..=box_cols {
+                    let corner = [by.checked_sub(1), Some(by)].into_iter().any(|i| {
+                        [bx.checked_sub(1), Some(bx)]
+                            .into_iter()
+                            .any(|j| present(i, j))
+                    });
+                    divider.push(if corner { '+' } else { ' ' });
+                    if bx < box_cols {
+                        let edge =
+                            present(by.checked_sub(1), Some(bx)) || present(Some(by), Some(bx));
+                        divider.push_str(if edge { "-----" } else { "     " });
+                    }
+                }
+                lines.push(divider);
+                if by == box_rows {
+                    break;
+                }
+                for y in by * 3..by * 3 + 3 {
+                    let mut line = String::new();
+                    for x in 0..=width {
+                        let bx = x / 3;
+                        if x % 3 == 0 {
+                            let edge =
+                                present(Some(by), bx.checked_sub(1)) || present(Some(by), Some(bx));
+                            line.push(if edge { '|' } else { ' ' });
+                        } else {
+                            line.push(' ');
+                        }
+                        if x < width {
+                            match self.get(y, x) {
+                                Some(0) | None => line.push(' '),
+                                Some(c) => line.push(char::from(b'0' + c)),
+                            }
+                        }
+                    }
+                    lines.push(line);
+                }
+            }
+        } else {
+            for row in &self.cells {
+                lines.push(
+                    row.iter()
+                        .map(|c| match c {
+                            Some(c) => char::from(b'0' + c),
+                            None => ' ',
+                        })
+                        .collect::<String>(),
+                );
+            }
+        }
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of a multi-grid board during solving, with the cells flattened
+/// into a list and each cell linked to every other cell it shares a row,
+/// column, or box with in any grid
+struct Linked {
+    positions: Vec<(usize, usize)>,
+    values: Vec<u8>,
+    peers: Vec<Vec<usize>>,
+}
+
+impl Linked {
+    fn new(board: &Board) -> Linked {
+        let mut positions = Vec::new();
+        let mut values = Vec::new();
+        let mut index =
+            vec![vec![None; board.cells.first().map_or(0, Vec::len)]; board.cells.len()];
+        for (y, row) in board.cells.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                if let Some(c) = c {
+                    index[y][x] = Some(positions.len());
+                    positions.push((y, x));
+                    values.push(c);
+                }
+            }
+        }
+        let mut peers = vec![Vec::new(); positions.len()];
+        for &(oy, ox) in &board.offsets {
+            let at = |y: usize, x: usize| {
+                index[oy + y][ox + x].expect("grid cells should be on the board")
+            };
+            let mut houses = Vec::with_capacity(27);
+            for i in 0..9 {
+                houses.push((0..9).map(|j| at(i, j)).collect::<Vec<_>>());
+                houses.push((0..9).map(|j| at(j, i)).collect::<Vec<_>>());
+                houses.push(
+                    (0..9)
+                        .map(|j| at(i / 3 * 3 + j / 3, i % 3 * 3 + j % 3))
+                        .collect::<Vec<_>>(),
+                );
+            }
+            for house in houses {
+                for &a in &house {
+                    peers[a].extend(house.iter().copied().filter(|&b| b != a));
+                }
+            }
+        }
+        for p in &mut peers {
+            p.sort_unstable();
+            p.dedup();
+        }
+        Linked {
+            positions,
+            values,
+            peers,
+        }
+    }
+
+    /// Test whether no two filled peers have the same digit
+    fn is_consistent(&self) -> bool {
+        self.values
+            .iter()
+            .enumerate()
+            .all(|(i, &v)| v == 0 || self.peers[i].iter().all(|&j| self.values[j] != v))
+    }
+
+    /// Returns a bitmask of the digits that can be placed in cell `i`
+    fn candidates(&self, i: usize) -> u16 {
+        let mut mask = 0b11_1111_1110;
+        for &j in &self.peers[i] {
+            mask &= !(1 << self.values[j]);
+        }
+        mask
+    }
+
+    /// Fill in all unfilled cells by backtracking, always branching on the
+    /// cell with the fewest candidates.  Returns `false` if no solution
+    /// exists.
+    fn search(&mut self) -> bool {
+        let mut best: Option<(usize, u16)> = None;
+        for i in 0..self.values.len() {
+            if self.values[i] == 0 {
+                let mask = self.candidates(i);
+                if best.map_or(true, |(_, m)| mask.count_ones() < m.count_ones()) {
+                    best = Some((i, mask));
+                    if mask.count_ones() <= 1 {
+                        break;
+                    }
+                }
+            }
+        }
+        let Some((i, mask)) = best else {
+            return true;
+        };
+        for d in 1..=9 {
+            if mask & (1 << d) != 0 {
+                self.values[i] = d;
+                if self.search() {
+                    return true;
+                }
+            }
+        }
+        self.values[i] = 0;
+        false
+    }
+}
+
+/// Error type returned when trying to construct a [`MultiPuzzle`] from invalid
+/// input
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum MultiPuzzleError {
+    /// Returned when a puzzle has no grids
+    #[error("puzzle has no grids")]
+    NoGrids,
+
+    /// Returned when a grid's offset is not a multiple of 3.  The arguments
+    /// are the offset in question.
+    #[error("grid offset ({0}, {1}) is not a multiple of 3")]
+    Misaligned(usize, usize),
+
+    /// Returned when two grids are placed at the same offset.  The arguments
+    /// are the offset in question.
+    #[error("multiple grids at offset ({0}, {1})")]
+    Duplicate(usize, usize),
+
+    /// Returned when overlapping grids have different digits in a shared
+    /// cell.  The arguments are the position of the cell on the board.
+    #[error("grids disagree on the value of cell ({0}, {1})")]
+    Conflict(usize, usize),
+
+    /// Returned when parsing input in which a 3×3 box is only partly made of
+    /// cells.  The arguments are the position of the box's top-left corner.
+    #[error("box at ({0}, {1}) is only partly filled in")]
+    PartialBox(usize, usize),
+
+    /// Returned when parsing input in which a cell is not part of any
+    /// complete grid.  The arguments are the position of the cell.
+    #[error("cell ({0}, {1}) is not part of any grid")]
+    Uncovered(usize, usize),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static SAMURAI: &str = concat!(
+        "..3.2.6..   ..3.2.6..\n",
+        "9..3.5..1   9..3.5..1\n",
+        "..18.64..   ..18.64..\n",
+        "..81.29..   ..81.29..\n",
+        "7.......8   7.......8\n",
+        "..67.82..   ..67.82..\n",
+        "..26.95.......26.95..\n",
+        "8..2.3..9...8..2.3..9\n",
+        "..5.1.3.......5.1.3..\n",
+        "      .........\n",
+        "      .........\n",
+        "      .........\n",
+        "..3.2.6.......3.2.6..\n",
+        "9..3.5..1...9..3.5..1\n",
+        "..18.64.......18.64..\n",
+        "..81.29..   ..81.29..\n",
+        "7.......8   7.......8\n",
+        "..67.82..   ..67.82..\n",
+        "..26.95..   ..26.95..\n",
+        "8..2.3..9   8..2.3..9\n",
+        "..5.1.3..   ..5.1.3..\n",
+    );
+
+    fn is_valid(grid: &[[u8; 9]; 9]) -> bool {
+        (0..9).all(|i| {
+            let mut row = [false; 10];
+            let mut col = [false; 10];
+            let mut bx = [false; 10];
+            for j in 0..9 {
+                row[usize::from(grid[i][j])] = true;
+                col[usize::from(grid[j][i])] = true;
+                bx[usize::from(grid[i / 3 * 3 + j / 3][i % 3 * 3 + j % 3])] = true;
+            }
+            row[1..].iter().chain(&col[1..]).chain(&bx[1..]).all(|&b| b)
+        })
+    }
+
+    #[test]
+    fn test_parse_samurai() {
+        let puzzle = SAMURAI.parse::<MultiPuzzle>().unwrap();
+        assert_eq!(puzzle.offsets(), SAMURAI_OFFSETS);
+        assert_eq!(puzzle.dimensions(), (21, 21));
+        let grids = puzzle.grids();
+        assert_eq!(grids[0][0], [0, 0, 3, 0, 2, 0, 6, 0, 0]);
+        assert_eq!(grids[2][0], [5, 0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(grids[2][2], [3, 0, 0, 0, 0, 0, 0, 0, 5]);
+        assert_eq!(puzzle.get(9, 0), None);
+        assert_eq!(puzzle.get(9, 6), Some(0));
+        assert_eq!(
+            puzzle.to_string(),
+            SAMURAI.replace('.', "0").trim_end_matches('\n')
+        );
+    }
+
+    #[test]
+    fn test_samurai_conflict() {
+        let mut grids = [Puzzle::from_grid([[0; 9]; 9]); 5];
+        grids[0].grid[8][8] = 1;
+        grids[2].grid[2][2] = 2;
+        assert_eq!(
+            MultiPuzzle::samurai(grids),
+            Err(MultiPuzzleError::Conflict(8, 8))
+        );
+        grids[2].grid[2][2] = 1;
+        let puzzle = MultiPuzzle::samurai(grids).unwrap();
+        assert_eq!(puzzle.get(8, 8), Some(1));
+    }
+
+    #[test]
+    fn test_new_misaligned() {
+        let p = Puzzle::from_grid([[0; 9]; 9]);
+        assert_eq!(
+            MultiPuzzle::new(vec![((0, 0), p), ((4, 4), p)]),
+            Err(MultiPuzzleError::Misaligned(4, 4))
+        );
+        assert_eq!(MultiPuzzle::new(Vec::new()), Err(MultiPuzzleError::NoGrids));
+    }
+
+    #[test]
+    fn test_parse_partial_box() {
+        let s = "123456789\n".repeat(8) + "12345678\n";
+        assert_eq!(
+            s.parse::<MultiPuzzle>(),
+            Err(MultiPuzzleError::PartialBox(6, 6))
+        );
+    }
+
+    #[test]
+    fn test_parse_box_below_grids() {
+        let s = "123456789\n".repeat(9) + "...\n...\n...\n";
+        assert_eq!(
+            s.parse::<MultiPuzzle>(),
+            Err(MultiPuzzleError::Uncovered(9, 0))
+        );
+    }
+
+    #[test]
+    fn test_solve_samurai() {
+        let puzzle = SAMURAI.parse::<MultiPuzzle>().unwrap();
+        let solution = puzzle.solve().unwrap();
+        for ((p, s), &(oy, ox)) in puzzle
+            .grids()
+            .iter()
+            .zip(solution.grids())
+            .zip(puzzle.offsets())
+        {
+            assert!(is_valid(&s), "grid at ({oy}, {ox}) is not valid");
+            for y in 0..9 {
+                for x in 0..9 {
+                    if p[y][x] != 0 {
+                        assert_eq!(p[y][x], s[y][x]);
+                    }
+                    assert_eq!(solution.get(oy + y, ox + x), Some(s[y][x]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_conflicting_center() {
+        let mut grids = [Puzzle::from_grid([[0; 9]; 9]); 5];
+        // The center grid's top-left box is shared with the top-left grid,
+        // whose top-right box shares row 6 with it.
+        grids[0].grid[6][8] = 5;
+        grids[2].grid[0][5] = 5;
+        let puzzle = MultiPuzzle::samurai(grids).unwrap();
+        assert_eq!(puzzle.solve(), None);
+    }
+
+    #[test]
+    fn test_display_pretty_twodoku() {
+        let mut p1 = Puzzle::from_grid([[0; 9]; 9]);
+        p1.grid[0][0] = 1;
+        let mut p2 = Puzzle::from_grid([[0; 9]; 9]);
+        p2.grid[8][8] = 9;
+        let puzzle = MultiPuzzle::new(vec![((0, 0), p1), ((6, 6), p2)]).unwrap();
+        assert_eq!(
+            format!("{puzzle:#}"),
+            concat!(
+                "+-----+-----+-----+\n",
+                "|1    |     |     |\n",
+                "|     |     |     |\n",
+                "|     |     |     |\n",
+                "+-----+-----+-----+\n",
+                "|     |     |     |\n",
+                "|     |     |     |\n",
+                "|     |     |     |\n",
+                "+-----+-----+-----+-----+-----+\n",
+                "|     |     |     |     |     |\n",
+                "|     |     |     |     |     |\n",
+                "|     |     |     |     |     |\n",
+                "+-----+-----+-----+-----+-----+\n",
+                "            |     |     |     |\n",
+                "            |     |     |     |\n",
+                "            |     |     |     |\n",
+                "            +-----+-----+-----+\n",
+                "            |     |     |     |\n",
+                "            |     |     |     |\n",
+                "            |     |     |    9|\n",
+                "            +-----+-----+-----+",
+            )
+        );
+    }
+}
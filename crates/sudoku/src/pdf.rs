@@ -0,0 +1,404 @@
+//! Printable PDF puzzle booklets
+//!
+//! A [`Booklet`] lays out a sequence of puzzles several to a page, numbering
+//! each one, and follows them with an answer key containing the solutions in
+//! the same order.  The PDF is written directly by this module using only the
+//! standard Helvetica fonts, so no external tools or font files are needed,
+//! and the output is deterministic.
+use crate::{Puzzle, Solution};
+use std::fmt::{self, Write};
+use std::str::FromStr;
+use thiserror::Error;
+
+// All lengths used in laying out pages are measured in hundredths of a point
+// so that the output can be computed exactly with integers.
+
+/// The margin around the edges of each page
+const PAGE_MARGIN: usize = 3600;
+
+/// The height of the heading above each grid
+const HEADING: usize = 2400;
+
+/// The width of a digit in Helvetica, in thousandths of the font size
+const DIGIT_WIDTH: usize = 556;
+
+/// The size of the pages of a [`Booklet`]
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum PageSize {
+    /// ISO A4 (210×297 mm)
+    A4,
+
+    /// US Letter (8.5×11 in)
+    #[default]
+    Letter,
+}
+
+impl PageSize {
+    /// Returns the width & height of the page in points
+    pub fn dimensions(self) -> (u32, u32) {
+        match self {
+            PageSize::A4 => (595, 842),
+            PageSize::Letter => (612, 792),
+        }
+    }
+}
+
+/// Parse a page size from its name (`a4` or `letter`), ignoring case
+///
+/// # Errors
+///
+/// Fails if the name is not recognized.
+impl FromStr for PageSize {
+    type Err = ParsePageSizeError;
+
+    fn from_str(s: &str) -> Result<PageSize, ParsePageSizeError> {
+        match s.to_ascii_lowercase().as_str() {
+            "a4" => Ok(PageSize::A4),
+            "letter" => Ok(PageSize::Letter),
+            _ => Err(ParsePageSizeError),
+        }
+    }
+}
+
+/// Error type returned when parsing an unknown [`PageSize`]
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+#[error("unknown page size; expected \"a4\" or \"letter\"")]
+pub struct ParsePageSizeError;
+
+/// A multi-page PDF booklet of puzzles followed by an answer key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Booklet {
+    page_size: PageSize,
+    per_page: usize,
+    difficulty: Option<String>,
+    entries: Vec<(Puzzle, Solution)>,
+}
+
+impl Booklet {
+    /// Construct an empty booklet with the given page size and four puzzles
+    /// per page
+    pub fn new(page_size: PageSize) -> Booklet {
+        Booklet {
+            page_size,
+            per_page: 4,
+            difficulty: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Set the number of puzzles (and solutions) on each page.  Values outside
+    /// the range `1..=9` are clamped to it.
+    pub fn with_per_page(self, per_page: usize) -> Booklet {
+        Booklet {
+            per_page: per_page.clamp(1, 9),
+            ..self
+        }
+    }
+
+    /// Set a difficulty label to print in the heading of each puzzle.  Any
+    /// non-ASCII characters are replaced with `?`.
+    pub fn with_difficulty(self, label: &str) -> Booklet {
+        Booklet {
+            difficulty: Some(label.to_owned()),
+            ..self
+        }
+    }
+
+    /// Append a puzzle and its solution to the booklet
+    pub fn push(&mut self, puzzle: Puzzle, solution: Solution) {
+        self.entries.push((puzzle, solution));
+    }
+
+    /// Return the number of puzzles in the booklet
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Test whether the booklet contains no puzzles
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Render the booklet as a PDF document
+    pub fn render(&self) -> Vec<u8> {
+        let mut pages = Vec::new();
+        for (chunk_no, chunk) in self.entries.chunks(self.per_page).enumerate() {
+            let mut content = String::new();
+            for (i, (puzzle, _)) in chunk.iter().enumerate() {
+                let number = chunk_no * self.per_page + i + 1;
+                let heading = match &self.difficulty {
+                    Some(d) => format!("Puzzle {number} - {d}"),
+                    None => format!("Puzzle {number}"),
+                };
+                self.draw_slot(&mut content, i, &heading, puzzle, None);
+            }
+            pages.push(content);
+        }
+        for (chunk_no, chunk) in self.entries.chunks(self.per_page).enumerate() {
+            let mut content = String::new();
+            if chunk_no == 0 {
+                let (_, height) = self.dims();
+                text(
+                    &mut content,
+                    "F2",
+                    1800,
+                    PAGE_MARGIN,
+                    height - PAGE_MARGIN + 800,
+                    "Answer Key",
+                );
+            }
+            for (i, (puzzle, solution)) in chunk.iter().enumerate() {
+                let number = chunk_no * self.per_page + i + 1;
+                let heading = format!("Solution {number}");
+                self.draw_slot(&mut content, i, &heading, puzzle, Some(solution));
+            }
+            pages.push(content);
+        }
+        let page_count = pages.len();
+        for (i, content) in pages.iter_mut().enumerate() {
+            let (width, _) = self.dims();
+            text(
+                content,
+                "F1",
+                1000,
+                width / 2 - 2000,
+                PAGE_MARGIN / 2,
+                &format!("Page {} of {page_count}", i + 1),
+            );
+        }
+        self.write_document(&pages)
+    }
+
+    /// Returns the width & height of a page in hundredths of a point
+    fn dims(&self) -> (usize, usize) {
+        let (w, h) = self.page_size.dimensions();
+        let scale = |n| usize::try_from(n).unwrap_or(usize::MAX).saturating_mul(100);
+        (scale(w), scale(h))
+    }
+
+    /// Returns the number of rows & columns of grids on each page
+    fn layout(&self) -> (usize, usize) {
+        let mut rows = 1;
+        while rows * rows < self.per_page {
+            rows += 1;
+        }
+        let cols = (self.per_page + rows - 1) / rows;
+        (rows, cols)
+    }
+
+    /// Draw the heading & grid for the `slot`-th entry on a page
+    fn draw_slot(
+        &self,
+        content: &mut String,
+        slot: usize,
+        heading: &str,
+        puzzle: &Puzzle,
+        solution: Option<&Solution>,
+    ) {
+        let (width, height) = self.dims();
+        let (rows, cols) = self.layout();
+        let slot_w = (width - 2 * PAGE_MARGIN) / cols;
+        let slot_h = (height - 2 * PAGE_MARGIN) / rows;
+        // Make the grid 85% of the available space, rounded down to a whole
+        // number of cells
+        let cell = slot_w.min(slot_h - HEADING) * 85 / 100 / 9;
+        let size = cell * 9;
+        let (row, col) = (slot / cols, slot % cols);
+        let left = PAGE_MARGIN + slot_w * col + (slot_w - size) / 2;
+        let top = height - PAGE_MARGIN - slot_h * row - HEADING;
+        let bottom = top - size;
+        text(content, "F2", 1200, left, top + 800, heading);
+        for i in 0..=9 {
+            let lw = Pt(if i % 3 == 0 { 200 } else { 50 });
+            let offset = cell * i;
+            let _ = writeln!(
+                content,
+                "{lw} w {} {} m {} {} l S",
+                Pt(left),
+                Pt(top - offset),
+                Pt(left + size),
+                Pt(top - offset),
+            );
+            let _ = writeln!(
+                content,
+                "{lw} w {} {} m {} {} l S",
+                Pt(left + offset),
+                Pt(top),
+                Pt(left + offset),
+                Pt(bottom),
+            );
+        }
+        let font_size = cell * 6 / 10;
+        for y in 0..9 {
+            for x in 0..9 {
+                let given = puzzle[y][x];
+                let (digit, font) = match (given, solution) {
+                    (0, Some(s)) => (s[y][x], "F1"),
+                    (0, None) => continue,
+                    (g, _) => (g, "F2"),
+                };
+                let cx = left + cell * x + cell / 2 - DIGIT_WIDTH * font_size / 2000;
+                let cy = top - cell * y - cell / 2 - font_size * 35 / 100;
+                text(content, font, font_size, cx, cy, &digit.to_string());
+            }
+        }
+    }
+
+    /// Assemble the PDF objects, cross-reference table, & trailer
+    fn write_document(&self, pages: &[String]) -> Vec<u8> {
+        let (width, height) = self.page_size.dimensions();
+        let mut objects = vec![
+            String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+            String::new(), // Filled in below once the page objects are known
+            String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>"),
+            String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold >>"),
+        ];
+        let mut kids = Vec::with_capacity(pages.len());
+        for content in pages {
+            let page_id = objects.len() + 1;
+            kids.push(format!("{page_id} 0 R"));
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width} {height}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                page_id + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{content}endstream",
+                content.len()
+            ));
+        }
+        objects[1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        );
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, obj) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            let _ = write!(out, "{} 0 obj\n{obj}\nendobj\n", i + 1);
+        }
+        let xref = out.len();
+        let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for off in offsets {
+            let _ = writeln!(out, "{off:010} 00000 n ");
+        }
+        let _ = write!(
+            out,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        );
+        out.into_bytes()
+    }
+}
+
+/// Append an instruction drawing `s` at the given position
+fn text(content: &mut String, font: &str, size: usize, x: usize, y: usize, s: &str) {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    let _ = writeln!(
+        content,
+        "BT /{font} {} Tf {} {} Td ({escaped}) Tj ET",
+        Pt(size),
+        Pt(x),
+        Pt(y)
+    );
+}
+
+/// A length in hundredths of a point, displayed in points
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Pt(usize);
+
+impl fmt::Display for Pt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.0 / 100, self.0 % 100)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+
+    fn entry() -> (Puzzle, Solution) {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        (puzzle, solution)
+    }
+
+    fn booklet(n: usize) -> Booklet {
+        let mut booklet = Booklet::new(PageSize::A4).with_difficulty("Easy (1)");
+        for _ in 0..n {
+            let (p, s) = entry();
+            booklet.push(p, s);
+        }
+        booklet
+    }
+
+    #[test]
+    fn test_page_size() {
+        assert_eq!("A4".parse::<PageSize>(), Ok(PageSize::A4));
+        assert_eq!("letter".parse::<PageSize>(), Ok(PageSize::Letter));
+        assert_eq!("legal".parse::<PageSize>(), Err(ParsePageSizeError));
+    }
+
+    #[test]
+    fn test_layout() {
+        for (n, layout) in [
+            (1, (1, 1)),
+            (2, (2, 1)),
+            (4, (2, 2)),
+            (6, (3, 2)),
+            (9, (3, 3)),
+        ] {
+            assert_eq!(booklet(0).with_per_page(n).layout(), layout);
+        }
+        assert_eq!(booklet(0).with_per_page(0).layout(), (1, 1));
+    }
+
+    #[test]
+    fn test_render() {
+        let booklet = booklet(5);
+        let pdf = String::from_utf8(booklet.render()).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/Count 4 >>"));
+        assert!(pdf.contains("/MediaBox [0 0 595 842]"));
+        assert!(pdf.contains("(Puzzle 5 - Easy \\(1\\)) Tj"));
+        assert!(pdf.contains("(Solution 5) Tj"));
+        assert!(pdf.contains("(Answer Key) Tj"));
+        assert!(pdf.contains("(Page 4 of 4) Tj"));
+        assert_eq!(booklet.render(), pdf.as_bytes());
+    }
+
+    #[test]
+    fn test_xref_offsets() {
+        let pdf = String::from_utf8(booklet(2).with_per_page(1).render()).unwrap();
+        let (body, tail) = pdf.split_once("xref\n").unwrap();
+        let startxref = tail
+            .split("startxref\n")
+            .nth(1)
+            .and_then(|s| s.lines().next())
+            .unwrap();
+        assert_eq!(startxref.parse::<usize>().unwrap(), body.len());
+        let offsets = tail
+            .lines()
+            .skip(2)
+            .take_while(|l| l.ends_with(" n "))
+            .map(|l| l[..10].parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(offsets.len(), 12);
+        for (i, off) in offsets.into_iter().enumerate() {
+            assert!(pdf[off..].starts_with(&format!("{} 0 obj\n", i + 1)));
+        }
+    }
+}
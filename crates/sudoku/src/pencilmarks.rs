@@ -0,0 +1,497 @@
+//! Partially-solved positions with pencil marks
+//!
+//! A [`Position`] pairs a [`Puzzle`] with a grid of [`Candidates`] recording
+//! which digits are still possible in each cell.  Positions can be exchanged
+//! with other solvers in two formats:
+//!
+//! - The pencil-mark grid exported by HoDoKu and SudokuWiki, in which each
+//!   cell is written as the list of its candidates, e.g.:
+//!
+//!   ```text
+//!   .------------------.-----------------.------------------.
+//!   | 45    578     3  | 49   2      147 | 6   5789   57    |
+//!   | 9     24678   47 | 3    47     5   | 78  278    1     |
+//!   | 25    257     1  | 8    79     6   | 4   2379   2357  |
+//!   :------------------+-----------------+------------------:
+//!   ...
+//!   ```
+//!
+//!   Any characters other than digits act as separators, so the borders may
+//!   be drawn in any style.
+//!
+//! - HoDoKu's savepoint/library line, in which the givens are followed by a
+//!   list of eliminated candidates, e.g.:
+//!
+//!   ```text
+//!   :0000:x:..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..:412 512 922::
+//!   ```
+use crate::{Parity, Puzzle};
+use thiserror::Error;
+
+/// The candidates still possible in each cell of a grid
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Candidates([[u16; 9]; 9]);
+
+impl Candidates {
+    /// Compute the candidates for each cell of a puzzle by eliminating the
+    /// digits in each unfilled cell's row, column, and box, as well as any
+    /// digits forbidden by the cell's parity.  Filled cells have their digit
+    /// as their only candidate.
+    pub fn from_puzzle(puzzle: &Puzzle) -> Candidates {
+        let mut grid = [[0; 9]; 9];
+        for (y, row) in grid.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = if puzzle[y][x] != 0 {
+                    1 << puzzle[y][x]
+                } else {
+                    let mut mask = 0b11_1111_1110;
+                    for (py, px) in peers(y, x) {
+                        mask &= !(1 << puzzle[py][px]);
+                    }
+                    match puzzle.parity(y, x) {
+                        Some(Parity::Even) => mask & 0b01_0101_0100,
+                        Some(Parity::Odd) => mask & 0b10_1010_1010,
+                        None => mask,
+                    }
+                };
+            }
+        }
+        Candidates(grid)
+    }
+
+    /// Test whether `digit` is a candidate for the given cell
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn contains(&self, y: usize, x: usize, digit: u8) -> bool {
+        (1..=9).contains(&digit) && self.0[y][x] & (1 << digit) != 0
+    }
+
+    /// Return the candidates for the given cell in ascending order
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn digits(&self, y: usize, x: usize) -> Vec<u8> {
+        (1..=9).filter(|&d| self.contains(y, x, d)).collect()
+    }
+
+    /// Remove `digit` from the candidates for the given cell.  Returns `true`
+    /// if it was a candidate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` or `x` is not less than 9.
+    pub fn remove(&mut self, y: usize, x: usize, digit: u8) -> bool {
+        let present = self.contains(y, x, digit);
+        if present {
+            self.0[y][x] &= !(1 << digit);
+        }
+        present
+    }
+}
+
+/// A puzzle together with the candidates remaining at some point while
+/// solving it
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Position {
+    /// The givens of the puzzle
+    pub puzzle: Puzzle,
+
+    /// The digits placed in unfilled cells of the puzzle while solving it,
+    /// with 0 representing a cell that is still unsolved
+    pub placed: [[u8; 9]; 9],
+
+    /// The candidates for each cell
+    pub candidates: Candidates,
+}
+
+impl Position {
+    /// Construct the starting position for a puzzle, in which no candidates
+    /// have been eliminated beyond those seen by the givens
+    pub fn new(puzzle: Puzzle) -> Position {
+        Position {
+            puzzle,
+            placed: [[0; 9]; 9],
+            candidates: Candidates::from_puzzle(&puzzle),
+        }
+    }
+
+    /// Return the grid of givens & placed digits, with 0 representing an
+    /// unsolved cell
+    pub fn grid(&self) -> [[u8; 9]; 9] {
+        let mut grid = self.puzzle.grid;
+        for (grow, prow) in grid.iter_mut().zip(&self.placed) {
+            for (g, &p) in grow.iter_mut().zip(prow) {
+                if *g == 0 {
+                    *g = p;
+                }
+            }
+        }
+        grid
+    }
+
+    /// Parse a pencil-mark grid as exported by HoDoKu or SudokuWiki.
+    ///
+    /// The input must contain exactly 81 runs of digits, giving the
+    /// candidates for each cell in row-major order.  As the format does not
+    /// distinguish givens from placed digits, every cell with a single
+    /// candidate is treated as a given.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the input does not contain exactly 81 cells or if a cell
+    /// contains a 0 or a repeated digit.
+    pub fn from_pencil_marks(s: &str) -> Result<Position, PencilMarkError> {
+        let cells = s
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>();
+        if cells.len() != 81 {
+            return Err(PencilMarkError::BadCellCount(cells.len()));
+        }
+        let mut grid = [[0; 9]; 9];
+        let mut candidates = [[0; 9]; 9];
+        for (i, cell) in cells.into_iter().enumerate() {
+            let (y, x) = (i / 9, i % 9);
+            let mut mask = 0u16;
+            for d in cell.bytes().map(|b| b - b'0') {
+                if d == 0 || mask & (1 << d) != 0 {
+                    return Err(PencilMarkError::InvalidCell(y, x));
+                }
+                mask |= 1 << d;
+            }
+            if cell.len() == 1 {
+                grid[y][x] = cell.as_bytes()[0] - b'0';
+            }
+            candidates[y][x] = mask;
+        }
+        Ok(Position {
+            puzzle: Puzzle::from_grid(grid),
+            placed: [[0; 9]; 9],
+            candidates: Candidates(candidates),
+        })
+    }
+
+    /// Render the position as a pencil-mark grid in HoDoKu's layout, with
+    /// each column padded to the width of its widest cell.  Givens & placed
+    /// digits are written as themselves regardless of the cells' candidates.
+    /// The output ends with a newline.
+    pub fn to_pencil_marks(&self) -> String {
+        let grid = self.grid();
+        let cells = (0..9)
+            .map(|y| {
+                (0..9)
+                    .map(|x| {
+                        let mut s = String::new();
+                        match grid[y][x] {
+                            0 => {
+                                for d in self.candidates.digits(y, x) {
+                                    s.push(char::from(b'0' + d));
+                                }
+                            }
+                            d => s.push(char::from(b'0' + d)),
+                        }
+                        s
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let widths = (0..9)
+            .map(|x| cells.iter().map(|row| row[x].len()).max().unwrap_or(0))
+            .collect::<Vec<_>>();
+        let segments = widths
+            .chunks(3)
+            .map(|ws| ws.iter().sum::<usize>() + 6)
+            .collect::<Vec<_>>();
+        let border = |left: char, mid: char, right: char| {
+            let mut s = String::from(left);
+            for (i, &n) in segments.iter().enumerate() {
+                if i > 0 {
+                    s.push(mid);
+                }
+                s.push_str(&"-".repeat(n));
+            }
+            s.push(right);
+            s.push('\n');
+            s
+        };
+        let mut out = border('.', '.', '.');
+        for (y, row) in cells.iter().enumerate() {
+            if y == 3 || y == 6 {
+                out.push_str(&border(':', '+', ':'));
+            }
+            for (x, cell) in row.iter().enumerate() {
+                out.push_str(if x % 3 == 0 { "| " } else { "  " });
+                out.push_str(cell);
+                out.push_str(&" ".repeat(widths[x] - cell.len()));
+                if x % 3 == 2 {
+                    out.push(' ');
+                }
+            }
+            out.push_str("|\n");
+        }
+        out.push_str(&border('\'', '\'', '\''));
+        out
+    }
+
+    /// Parse a HoDoKu savepoint/library line of the form
+    /// `:<technique>:<candidates>:<grid>:<eliminations>:...`.  A bare grid
+    /// without the surrounding fields is also accepted.
+    ///
+    /// The grid consists of 81 cells, each of which is either a given digit,
+    /// a digit placed while solving (preceded by `+`), or an unfilled cell
+    /// (`.` or `0`).  The eliminations are a space-separated list of
+    /// candidates that have been ruled out, each written as its digit,
+    /// 1-based row, and 1-based column.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the line has no grid field, if the grid does not contain
+    /// exactly 81 cells, or if an elimination is not three nonzero digits.
+    pub fn from_hodoku(s: &str) -> Result<Position, PencilMarkError> {
+        let s = s.trim();
+        let (grid_field, elims) = if s.starts_with(':') {
+            let mut fields = s.split(':').skip(3);
+            let grid = fields.next().ok_or(PencilMarkError::MissingGrid)?;
+            (grid, fields.next().unwrap_or(""))
+        } else {
+            (s, "")
+        };
+        let mut givens = [[0; 9]; 9];
+        let mut placed = [[0; 9]; 9];
+        let mut placing = false;
+        let mut i = 0;
+        for c in grid_field.chars() {
+            if c == '+' {
+                placing = true;
+                continue;
+            }
+            let d = match c {
+                '.' => 0,
+                _ => match u8::try_from(c) {
+                    Ok(b @ b'0'..=b'9') => b - b'0',
+                    _ => continue,
+                },
+            };
+            if i < 81 {
+                if placing {
+                    placed[i / 9][i % 9] = d;
+                } else {
+                    givens[i / 9][i % 9] = d;
+                }
+            }
+            placing = false;
+            i += 1;
+        }
+        if i != 81 {
+            return Err(PencilMarkError::BadCellCount(i));
+        }
+        let mut pos = Position {
+            puzzle: Puzzle::from_grid(givens),
+            placed,
+            candidates: Candidates([[0; 9]; 9]),
+        };
+        pos.candidates = Candidates::from_puzzle(&Puzzle::from_grid(pos.grid()));
+        for elim in elims.split_whitespace() {
+            let &[d, y, x] = elim.as_bytes() else {
+                return Err(PencilMarkError::InvalidElimination);
+            };
+            if ![d, y, x].iter().all(|b| (b'1'..=b'9').contains(b)) {
+                return Err(PencilMarkError::InvalidElimination);
+            }
+            pos.candidates
+                .remove(usize::from(y - b'1'), usize::from(x - b'1'), d - b'0');
+        }
+        Ok(pos)
+    }
+
+    /// Render the position as a HoDoKu savepoint/library line.  The output
+    /// does not end with a newline.
+    pub fn to_hodoku(&self) -> String {
+        let mut grid = String::new();
+        for (grow, prow) in self.puzzle.iter().zip(&self.placed) {
+            for (&g, &p) in grow.iter().zip(prow) {
+                if g != 0 {
+                    grid.push(char::from(b'0' + g));
+                } else if p != 0 {
+                    grid.push('+');
+                    grid.push(char::from(b'0' + p));
+                } else {
+                    grid.push('.');
+                }
+            }
+        }
+        let filled = self.grid();
+        let base = Candidates::from_puzzle(&Puzzle::from_grid(filled));
+        let mut elims = Vec::new();
+        for (y, row) in filled.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                if cell == 0 {
+                    for d in base.digits(y, x) {
+                        if !self.candidates.contains(y, x, d) {
+                            elims.push(format!("{d}{}{}", y + 1, x + 1));
+                        }
+                    }
+                }
+            }
+        }
+        format!(":0000:x:{grid}:{}::", elims.join(" "))
+    }
+}
+
+/// Error type returned when parsing an invalid pencil-mark grid or HoDoKu
+/// line
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum PencilMarkError {
+    /// Returned when the input does not contain exactly 81 cells.  The
+    /// argument is the number of cells found.
+    #[error("expected 81 cells, found {0}")]
+    BadCellCount(usize),
+
+    /// Returned when a cell of a pencil-mark grid contains a 0 or a repeated
+    /// digit.  The arguments are the cell's zero-based coordinates.
+    #[error("invalid candidates for cell ({0}, {1})")]
+    InvalidCell(usize, usize),
+
+    /// Returned when a HoDoKu line does not have a grid field
+    #[error("HoDoKu line is missing its grid field")]
+    MissingGrid,
+
+    /// Returned when an elimination in a HoDoKu line is not three nonzero
+    /// digits
+    #[error("invalid candidate elimination")]
+    InvalidElimination,
+}
+
+/// Returns the coordinates of the cells in the same row, column, or box as
+/// the given cell, possibly including some more than once
+pub(crate) fn peers(y: usize, x: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (by, bx) = (y - y % 3, x - x % 3);
+    (0..9)
+        .flat_map(move |i| [(y, i), (i, x), (by + i / 3, bx + i % 3)])
+        .filter(move |&c| c != (y, x))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+
+    static PENCIL_MARKS: &str = concat!(
+        ".------------------.-----------------.------------------.\n",
+        "| 45    578     3  | 49   2      147 | 6   5789   57    |\n",
+        "| 9     24678   47 | 3    47     5   | 78  278    1     |\n",
+        "| 25    257     1  | 8    79     6   | 4   2379   2357  |\n",
+        ":------------------+-----------------+------------------:\n",
+        "| 345   345     8  | 1    3456   2   | 9   34567  34567 |\n",
+        "| 7     123459  49 | 459  34569  4   | 1   13456  8     |\n",
+        "| 1345  13459   6  | 7    3459   8   | 2   1345   345   |\n",
+        ":------------------+-----------------+------------------:\n",
+        "| 134   1347    2  | 6    478    9   | 5   1478   47    |\n",
+        "| 8     1467    47 | 2    457    3   | 17  1467   9     |\n",
+        "| 46    4679    5  | 4    1      47  | 3   24678  2467  |\n",
+        "'------------------'-----------------'------------------'\n",
+    );
+
+    #[test]
+    fn test_from_puzzle() {
+        let candidates = Candidates::from_puzzle(&test_puzzle());
+        assert_eq!(candidates.digits(0, 0), [4, 5]);
+        assert_eq!(candidates.digits(0, 2), [3]);
+        assert_eq!(candidates.digits(4, 4), [3, 4, 5, 6, 9]);
+        assert!(!candidates.contains(4, 4, 0));
+        let even = test_puzzle().with_parities({
+            let mut p = [[None; 9]; 9];
+            p[4][4] = Some(Parity::Even);
+            p
+        });
+        assert_eq!(Candidates::from_puzzle(&even).digits(4, 4), [4, 6]);
+    }
+
+    #[test]
+    fn test_pencil_marks_roundtrip() {
+        let pos = Position::new(test_puzzle());
+        let pm = pos.to_pencil_marks();
+        assert_eq!(pm.lines().count(), 13);
+        assert!(pm.starts_with(".---"));
+        assert!(pm.contains("\n| 45    "));
+        let parsed = Position::from_pencil_marks(&pm).unwrap();
+        assert_eq!(parsed.candidates, pos.candidates);
+        assert_eq!(parsed.puzzle[4][5], 4);
+        assert_eq!(parsed.to_pencil_marks(), pm);
+    }
+
+    #[test]
+    fn test_parse_pencil_marks() {
+        let pos = Position::from_pencil_marks(PENCIL_MARKS).unwrap();
+        assert_eq!(pos.puzzle[0][2], 3);
+        assert_eq!(pos.puzzle[0][0], 0);
+        assert_eq!(pos.puzzle[4][6], 1);
+        assert_eq!(pos.candidates.digits(0, 1), [5, 7, 8]);
+        assert_eq!(pos.candidates.digits(2, 7), [2, 3, 7, 9]);
+        assert_eq!(pos.candidates.digits(4, 1), [1, 2, 3, 4, 5, 9]);
+        assert_eq!(pos.to_pencil_marks(), PENCIL_MARKS);
+        assert_eq!(
+            Position::from_pencil_marks(&PENCIL_MARKS.replacen("578", "578 1", 1)),
+            Err(PencilMarkError::BadCellCount(82))
+        );
+        assert_eq!(
+            Position::from_pencil_marks(&PENCIL_MARKS.replacen("578", "575", 1)),
+            Err(PencilMarkError::InvalidCell(0, 1))
+        );
+    }
+
+    #[test]
+    fn test_hodoku_roundtrip() {
+        let mut pos = Position::new(test_puzzle());
+        assert!(pos.candidates.remove(0, 1, 4));
+        assert!(pos.candidates.remove(2, 7, 5));
+        assert!(!pos.candidates.remove(2, 7, 6));
+        pos.placed[4][5] = 4;
+        pos.placed[4][6] = 1;
+        pos.placed[8][3] = 4;
+        let line = pos.to_hodoku();
+        assert_eq!(
+            line,
+            concat!(
+                ":0000:x:..3.2.6..9..3.5..1..18.64....81.29..7....+4+1.8",
+                "..67.82....26.95..8..2.3..9..5+41.3..:",
+                "412 538::"
+            )
+        );
+        let parsed = Position::from_hodoku(&line).unwrap();
+        assert_eq!(parsed.puzzle, test_puzzle());
+        assert_eq!(parsed.placed[4][5], 4);
+        assert_eq!(parsed.grid()[4][6], 1);
+        assert_eq!(parsed.candidates.digits(0, 1), [5, 7, 8]);
+        assert_eq!(parsed.candidates.digits(4, 6), [1]);
+        assert_eq!(parsed.to_hodoku(), line);
+    }
+
+    #[test]
+    fn test_parse_hodoku() {
+        let pos = Position::from_hodoku(concat!(
+            ":0000:x:..3.2.6..9..3.5..1..18.64....81.29..7.......8",
+            "..67.82....26.95..8..2.3..9..5.1.3..:412 512 922:\n",
+        ))
+        .unwrap();
+        assert_eq!(pos.puzzle, test_puzzle());
+        assert_eq!(pos.candidates.digits(0, 0), [4, 5]);
+        assert_eq!(pos.candidates.digits(0, 1), [7, 8]);
+        assert_eq!(pos.candidates.digits(1, 1), [2, 4, 6, 7, 8]);
+        assert_eq!(
+            Position::from_hodoku(":0000:x"),
+            Err(PencilMarkError::MissingGrid)
+        );
+        assert_eq!(
+            Position::from_hodoku("..3.2.6..9"),
+            Err(PencilMarkError::BadCellCount(10))
+        );
+        assert_eq!(
+            Position::from_hodoku(&format!(":0000:x:{}:012:", ".".repeat(81))),
+            Err(PencilMarkError::InvalidElimination)
+        );
+    }
+}
@@ -0,0 +1,403 @@
+//! A small built-in SAT solver for [`Cnf`] formulas
+//!
+//! The solver is a basic conflict-driven clause learning search with unit
+//! propagation over two watched literals per clause, branching on a positive
+//! literal from a shortest unsatisfied clause.  It lacks the restarts &
+//! activity-based heuristics of a dedicated SAT solver and is no match for
+//! one on hard problems, but Sudoku encodings are mostly settled by unit
+//! propagation, and it needs no external solver.
+//!
+//! This module is only available when the `sat` feature is enabled.
+use crate::cnf::{decode_model, Cnf, UnsupportedConstraint};
+use crate::constraints::Constraint;
+use crate::{Puzzle, Solution};
+
+impl Puzzle {
+    /// Solve the puzzle, with the given variant [`Constraint`]s, by encoding
+    /// it as a [`Cnf`] and passing it to the built-in SAT solver.
+    ///
+    /// If the puzzle has multiple solutions, one of them is returned, but
+    /// which one is unspecified.  If the puzzle has no solutions, `Ok(None)`
+    /// is returned.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the constraints do not support being encoded in CNF.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the SAT solver returns a model that does not decode to a
+    /// valid solution, which indicates a bug in the encoding or the solver.
+    pub fn solve_sat(
+        &self,
+        constraints: &[Box<dyn Constraint>],
+    ) -> Result<Option<Solution>, UnsupportedConstraint> {
+        let cnf = Cnf::new(self).with_constraints(constraints)?;
+        Ok(solve(&cnf).map(|model| {
+            decode_model(&model).expect("a satisfying model should decode to a valid solution")
+        }))
+    }
+}
+
+/// Find a satisfying assignment for `cnf`.  The assignment is returned as a
+/// list of literals, one for each variable in order, in the same form as the
+/// models printed by SAT solvers.  Returns `None` if the formula is
+/// unsatisfiable.
+pub fn solve(cnf: &Cnf) -> Option<Vec<i32>> {
+    let mut solver = Solver::new(cnf)?;
+    solver.search().then(|| solver.model())
+}
+
+/// Returns the variable of a literal
+fn var(lit: i32) -> usize {
+    // `u32` fits in `usize` on every platform that this crate supports.
+    usize::try_from(lit.unsigned_abs()).unwrap_or(usize::MAX)
+}
+
+/// Returns the index of a literal in the per-literal watch lists
+fn index(lit: i32) -> usize {
+    2 * var(lit) + usize::from(lit < 0)
+}
+
+#[derive(Clone, Debug)]
+struct Solver {
+    /// Clauses of two or more literals — first those of the formula, then
+    /// those learned from conflicts.  The first two literals of each are
+    /// watched.
+    clauses: Vec<Vec<i32>>,
+
+    /// The number of clauses that come from the formula
+    original: usize,
+
+    /// For each literal, the indices of the clauses in which it is watched
+    watches: Vec<Vec<usize>>,
+
+    /// The value of each variable, indexed by variable number
+    values: Vec<Option<bool>>,
+
+    /// The decision level at which each variable was assigned
+    levels: Vec<usize>,
+
+    /// For each variable assigned by unit propagation, the clause that
+    /// forced it
+    reasons: Vec<Option<usize>>,
+
+    /// The literals made true so far, in the order they were assigned
+    trail: Vec<i32>,
+
+    /// The length of the trail at the start of each decision level after
+    /// the first
+    trail_starts: Vec<usize>,
+
+    /// The number of literals on the trail that have been propagated
+    propagated: usize,
+}
+
+impl Solver {
+    /// Set up a solver for `cnf`, assigning the literals of its unit clauses.
+    /// Returns `None` if the formula contains an empty clause or
+    /// contradictory unit clauses.
+    fn new(cnf: &Cnf) -> Option<Solver> {
+        let mut solver = Solver {
+            clauses: Vec::new(),
+            original: 0,
+            watches: vec![Vec::new(); 2 * cnf.variables() + 2],
+            values: vec![None; cnf.variables() + 1],
+            levels: vec![0; cnf.variables() + 1],
+            reasons: vec![None; cnf.variables() + 1],
+            trail: Vec::new(),
+            trail_starts: Vec::new(),
+            propagated: 0,
+        };
+        for clause in cnf.clauses() {
+            match clause[..] {
+                [] => return None,
+                [lit] => {
+                    if solver.value(lit) == Some(false) {
+                        return None;
+                    }
+                    solver.assign(lit, None);
+                }
+                _ => {
+                    solver.add_clause(clause.clone());
+                }
+            }
+        }
+        solver.original = solver.clauses.len();
+        Some(solver)
+    }
+
+    fn value(&self, lit: i32) -> Option<bool> {
+        self.values[var(lit)].map(|v| v == (lit > 0))
+    }
+
+    /// Add a clause of two or more literals, watching its first two, and
+    /// return its index
+    fn add_clause(&mut self, clause: Vec<i32>) -> usize {
+        let i = self.clauses.len();
+        for &lit in clause.iter().take(2) {
+            self.watches[index(lit)].push(i);
+        }
+        self.clauses.push(clause);
+        i
+    }
+
+    /// Make the unassigned literal `lit` true at the current decision level
+    fn assign(&mut self, lit: i32, reason: Option<usize>) {
+        if self.value(lit).is_none() {
+            let v = var(lit);
+            self.values[v] = Some(lit > 0);
+            self.levels[v] = self.trail_starts.len();
+            self.reasons[v] = reason;
+            self.trail.push(lit);
+        }
+    }
+
+    /// Assign every literal implied by unit propagation.  Returns the index
+    /// of a clause made false if a conflict arises.
+    fn propagate(&mut self) -> Option<usize> {
+        while let Some(&lit) = self.trail.get(self.propagated) {
+            self.propagated += 1;
+            let falsified = -lit;
+            let watching = std::mem::take(&mut self.watches[index(falsified)]);
+            let mut keep = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (n, &ci) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    keep.extend_from_slice(&watching[n..]);
+                    break;
+                }
+                let clause = &mut self.clauses[ci];
+                // Keep the falsified watch in the second slot:
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                let first_value = self.values[var(first)].map(|v| v == (first > 0));
+                if first_value == Some(true) {
+                    keep.push(ci);
+                    continue;
+                }
+                let replacement = (2..clause.len()).find(|&k| {
+                    let l = clause[k];
+                    self.values[var(l)] != Some(l < 0)
+                });
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    let watched = clause[1];
+                    self.watches[index(watched)].push(ci);
+                } else {
+                    keep.push(ci);
+                    if first_value == Some(false) {
+                        conflict = Some(ci);
+                    } else {
+                        self.assign(first, Some(ci));
+                    }
+                }
+            }
+            self.watches[index(falsified)].extend(keep);
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Derive a clause from the conflict in clause `conflict` whose first
+    /// literal is the only one assigned at the current decision level (the
+    /// "first unique implication point"), and return it along with the
+    /// highest decision level among its other literals, to which the search
+    /// should jump back
+    fn analyze(&self, conflict: usize) -> (Vec<i32>, usize) {
+        let level = self.trail_starts.len();
+        let mut seen = vec![false; self.values.len()];
+        let mut learned = vec![0];
+        // The number of seen literals at the current level not yet resolved:
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut implied = None;
+        let mut i = self.trail.len();
+        loop {
+            for &q in &self.clauses[clause] {
+                let v = var(q);
+                if Some(q) == implied || seen[v] || self.levels[v] == 0 {
+                    continue;
+                }
+                seen[v] = true;
+                if self.levels[v] == level {
+                    pending += 1;
+                } else {
+                    learned.push(q);
+                }
+            }
+            // Resolve on the most recently assigned seen literal:
+            let lit = loop {
+                i -= 1;
+                if seen[var(self.trail[i])] {
+                    break self.trail[i];
+                }
+            };
+            pending -= 1;
+            match self.reasons[var(lit)] {
+                Some(reason) if pending > 0 => {
+                    clause = reason;
+                    implied = Some(lit);
+                }
+                _ => {
+                    learned[0] = -lit;
+                    break;
+                }
+            }
+        }
+        // Watch a literal from the backjump level second, so that the clause
+        // stays correctly watched after jumping back:
+        let mut backjump = 0;
+        for k in 1..learned.len() {
+            let l = self.levels[var(learned[k])];
+            if l > backjump {
+                backjump = l;
+                learned.swap(1, k);
+            }
+        }
+        (learned, backjump)
+    }
+
+    /// Choose the next literal to assume true: a positive unassigned literal
+    /// from a shortest unsatisfied clause of the formula among those that
+    /// have one (which, for Sudoku, picks a candidate of a cell or unit with
+    /// the fewest candidates left), or else any unassigned literal from an
+    /// unsatisfied clause.  Returns `None` if every clause of the formula is
+    /// satisfied.
+    fn choose(&self) -> Option<i32> {
+        let mut best: Option<(usize, i32)> = None;
+        let mut fallback = None;
+        for clause in &self.clauses[..self.original] {
+            if clause.iter().any(|&l| self.value(l) == Some(true)) {
+                continue;
+            }
+            let free = clause
+                .iter()
+                .copied()
+                .filter(|&l| self.value(l).is_none())
+                .collect::<Vec<_>>();
+            let Some(&positive) = free.iter().find(|&&l| l > 0) else {
+                fallback = fallback.or_else(|| free.first().copied());
+                continue;
+            };
+            if best.map_or(true, |(n, _)| free.len() < n) {
+                best = Some((free.len(), positive));
+                if free.len() == 2 {
+                    break;
+                }
+            }
+        }
+        best.map(|(_, lit)| lit).or(fallback)
+    }
+
+    /// Undo every assignment made above decision level `level`
+    fn backtrack_to(&mut self, level: usize) {
+        if let Some(&start) = self.trail_starts.get(level) {
+            for lit in self.trail.drain(start..) {
+                self.values[var(lit)] = None;
+            }
+            self.trail_starts.truncate(level);
+            self.propagated = start;
+        }
+    }
+
+    /// Search for a satisfying assignment.  Returns `false` if there is none.
+    fn search(&mut self) -> bool {
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.trail_starts.is_empty() {
+                    return false;
+                }
+                let (learned, backjump) = self.analyze(conflict);
+                self.backtrack_to(backjump);
+                let asserting = learned[0];
+                let reason = (learned.len() > 1).then(|| self.add_clause(learned));
+                self.assign(asserting, reason);
+            } else {
+                // Every clause of the formula being satisfied (rather than
+                // every variable being assigned) suffices, as learned clauses
+                // follow from the formula.
+                let Some(lit) = self.choose() else {
+                    return true;
+                };
+                self.trail_starts.push(self.trail.len());
+                self.assign(lit, None);
+            }
+        }
+    }
+
+    /// Return the current assignment as a list of literals.  Variables that
+    /// were never assigned (because they appear in no unsatisfied clause) are
+    /// reported as false.
+    fn model(&self) -> Vec<i32> {
+        (1..self.values.len())
+            .zip(1..)
+            .map(|(var, lit)| {
+                if self.values[var] == Some(true) {
+                    lit
+                } else {
+                    -lit
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constraints::{
+        Arrow, Diagonal, KillerCage, Marker, MarkerKind, Sandwich, Thermometer,
+    };
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_solve_sat() {
+        let puzzle = test_puzzle();
+        assert_eq!(puzzle.solve_sat(&[]), Ok(puzzle.solve()));
+    }
+
+    #[test]
+    fn test_solve_sat_hard() {
+        let puzzle = "4.....8.5\n.3.......\n...7.....\n.2.....6.\n....8.4..\n....1....\n...6.3.7.\n5..2.....\n1.4......\n"
+            .parse::<Puzzle>()
+            .unwrap();
+        assert_eq!(puzzle.solve_sat(&[]), Ok(puzzle.solve()));
+    }
+
+    #[test]
+    fn test_solve_sat_constraints() {
+        let puzzle = Puzzle::try_from([[0; 9]; 9]).unwrap();
+        let constraints: Vec<Box<dyn Constraint>> = vec![
+            Box::new(Thermometer::new(vec![(0, 0), (0, 1), (0, 2), (1, 2)]).unwrap()),
+            Box::new(KillerCage::new(vec![(8, 8), (8, 7)], Some(3)).unwrap()),
+            Box::new(Arrow::new((4, 4), vec![(4, 5), (5, 5)]).unwrap()),
+            Box::new(Sandwich::row(2, 15).unwrap()),
+            Box::new(Sandwich::column(6, 0).unwrap()),
+            Box::new(Diagonal::Negative),
+            Box::new(Marker::new(MarkerKind::BlackDot, (6, 0), (7, 0)).unwrap()),
+        ];
+        let solution = puzzle.solve_sat(&constraints).unwrap().unwrap();
+        assert!(constraints.iter().all(|c| c.check(&solution)));
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        let mut grid = [[0; 9]; 9];
+        grid[0][0] = 1;
+        grid[0][1] = 1;
+        let puzzle = Puzzle::from_grid(grid);
+        assert_eq!(puzzle.solve_sat(&[]), Ok(None));
+        let constraints: Vec<Box<dyn Constraint>> = vec![Box::new(
+            KillerCage::new(vec![(0, 0), (0, 1)], Some(2)).unwrap(),
+        )];
+        assert_eq!(
+            Puzzle::from_grid([[0; 9]; 9]).solve_sat(&constraints),
+            Ok(None)
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! A bitmask-based backtracking search used for counting solutions and for
+//! filling grids at random.
+//!
+//! Unlike [`Puzzle::solve()`], which visits cells in row-major order, this
+//! search always branches on the unfilled cell with the fewest candidates,
+//! which makes exhausting the search space (as required to prove that a
+//! solution is unique) fast enough to do repeatedly when generating puzzles.
+use crate::{Parity, Puzzle};
+
+/// The set of all digits, with bit `d` representing digit `d`
+const ALL: u16 = 0b11_1111_1110;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Search {
+    grid: [[u8; 9]; 9],
+    parities: [[Option<Parity>; 9]; 9],
+    rows: [u16; 9],
+    cols: [u16; 9],
+    boxes: [u16; 9],
+}
+
+impl Search {
+    /// Set up a search over all completed grids
+    pub(crate) fn empty() -> Search {
+        Search {
+            grid: [[0; 9]; 9],
+            parities: [[None; 9]; 9],
+            rows: [0; 9],
+            cols: [0; 9],
+            boxes: [0; 9],
+        }
+    }
+
+    /// Set up a search over the solutions of `puzzle`.  Returns `None` if the
+    /// puzzle's givens conflict with each other or with their parities, in
+    /// which case there are no solutions.
+    pub(crate) fn new(puzzle: &Puzzle) -> Option<Search> {
+        let mut search = Search {
+            parities: puzzle.parities,
+            ..Search::empty()
+        };
+        for y in 0..9 {
+            for x in 0..9 {
+                let d = puzzle.grid[y][x];
+                if d != 0 {
+                    if search.candidates(y, x) & (1 << d) == 0 {
+                        return None;
+                    }
+                    search.place(y, x, d);
+                }
+            }
+        }
+        Some(search)
+    }
+
+    /// Return the filled grid
+    pub(crate) fn grid(&self) -> [[u8; 9]; 9] {
+        self.grid
+    }
+
+    /// Count the solutions reachable from the current state, stopping once
+    /// `limit` have been found
+    pub(crate) fn count(&mut self, limit: usize) -> usize {
+        let mut found = 0;
+        self.count_into(limit, &mut found);
+        found
+    }
+
+    fn count_into(&mut self, limit: usize, found: &mut usize) {
+        let Some((y, x, mask)) = self.most_constrained() else {
+            *found += 1;
+            return;
+        };
+        for d in 1..=9 {
+            if *found >= limit {
+                return;
+            }
+            if mask & (1 << d) != 0 {
+                self.place(y, x, d);
+                self.count_into(limit, found);
+                self.unplace(y, x, d);
+            }
+        }
+    }
+
+    /// Fill in the grid with a solution chosen at random by trying the
+    /// candidates of each cell in a random order.  Returns `false` if there
+    /// is no solution.
+    pub(crate) fn fill_random(&mut self, rng: &mut fastrand::Rng) -> bool {
+        let Some((y, x, mask)) = self.most_constrained() else {
+            return true;
+        };
+        let mut digits = (1..=9)
+            .filter(|&d| mask & (1 << d) != 0)
+            .collect::<Vec<u8>>();
+        rng.shuffle(&mut digits);
+        for d in digits {
+            self.place(y, x, d);
+            if self.fill_random(rng) {
+                return true;
+            }
+            self.unplace(y, x, d);
+        }
+        false
+    }
+
+    /// Return the candidates for cell `(y, x)` as a bitmask
+    fn candidates(&self, y: usize, x: usize) -> u16 {
+        let mask = ALL & !(self.rows[y] | self.cols[x] | self.boxes[box_index(y, x)]);
+        match self.parities[y][x] {
+            Some(Parity::Even) => mask & 0b01_0101_0100,
+            Some(Parity::Odd) => mask & 0b10_1010_1010,
+            None => mask,
+        }
+    }
+
+    /// Return the unfilled cell with the fewest candidates along with its
+    /// candidates, or `None` if the grid is full
+    fn most_constrained(&self) -> Option<(usize, usize, u16)> {
+        let mut best: Option<(usize, usize, u16)> = None;
+        for y in 0..9 {
+            for x in 0..9 {
+                if self.grid[y][x] == 0 {
+                    let mask = self.candidates(y, x);
+                    if best.map_or(true, |(_, _, m)| mask.count_ones() < m.count_ones()) {
+                        best = Some((y, x, mask));
+                        if mask.count_ones() <= 1 {
+                            return best;
+                        }
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn place(&mut self, y: usize, x: usize, d: u8) {
+        self.grid[y][x] = d;
+        self.rows[y] |= 1 << d;
+        self.cols[x] |= 1 << d;
+        self.boxes[box_index(y, x)] |= 1 << d;
+    }
+
+    fn unplace(&mut self, y: usize, x: usize, d: u8) {
+        self.grid[y][x] = 0;
+        self.rows[y] &= !(1 << d);
+        self.cols[x] &= !(1 << d);
+        self.boxes[box_index(y, x)] &= !(1 << d);
+    }
+}
+
+fn box_index(y: usize, x: usize) -> usize {
+    (y / 3) * 3 + x / 3
+}
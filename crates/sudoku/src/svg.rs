@@ -0,0 +1,208 @@
+//! Rendering puzzles & solutions as SVG images
+//!
+//! An [`Svg`] is built from a [`Puzzle`] and, optionally, its [`Solution`]
+//! and/or the pencil marks of a position in progress, and is rendered to an
+//! SVG document by its [`Display`](fmt::Display) implementation.
+//!
+//! The output uses only integer coordinates and emits its elements in a fixed
+//! order, so rendering the same inputs always produces byte-identical output.
+use crate::pencilmarks::Candidates;
+use crate::{Parity, Puzzle, Solution};
+use std::fmt;
+
+/// The width & height of a cell, in user units
+const CELL: usize = 40;
+
+/// The space between the edge of the image and the outer border of the grid,
+/// leaving room for the thick border's stroke
+const MARGIN: usize = 2;
+
+/// The width & height of the image
+const SIZE: usize = 9 * CELL + 2 * MARGIN;
+
+/// The offsets within a cell of the columns/rows of pencil marks
+const PENCIL_OFFSETS: [usize; 3] = [8, 20, 32];
+
+/// An SVG rendering of a puzzle.
+///
+/// Givens are drawn in bold, digits filled in from a solution are drawn in a
+/// normal weight and a lighter color, and pencil marks are drawn in small type
+/// in a 3×3 arrangement within each unfilled cell.  Cells shaded to require
+/// an even or odd digit are marked with a gray square or circle,
+/// respectively.
+#[derive(Copy, Clone, Debug)]
+pub struct Svg<'a> {
+    puzzle: &'a Puzzle,
+    solution: Option<&'a Solution>,
+    candidates: Option<&'a Candidates>,
+}
+
+impl<'a> Svg<'a> {
+    /// Construct a rendering of the givens of `puzzle`
+    pub fn new(puzzle: &'a Puzzle) -> Self {
+        Svg {
+            puzzle,
+            solution: None,
+            candidates: None,
+        }
+    }
+
+    /// Also draw the digits of `solution` in the puzzle's unfilled cells
+    pub fn with_solution(self, solution: &'a Solution) -> Self {
+        Svg {
+            solution: Some(solution),
+            ..self
+        }
+    }
+
+    /// Draw the given candidates as pencil marks in cells that are not
+    /// otherwise filled
+    pub fn with_candidates(self, candidates: &'a Candidates) -> Self {
+        Svg {
+            candidates: Some(candidates),
+            ..self
+        }
+    }
+}
+
+/// Render the image as an SVG document ending with a newline
+impl fmt::Display for Svg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SIZE}" height="{SIZE}" viewBox="0 0 {SIZE} {SIZE}">"#
+        )?;
+        writeln!(
+            f,
+            r#"<rect x="0" y="0" width="{SIZE}" height="{SIZE}" fill="white"/>"#
+        )?;
+        writeln!(f, r##"<g fill="#ddd">"##)?;
+        for y in 0..9 {
+            for x in 0..9 {
+                let (cx, cy) = center(y, x);
+                match self.puzzle.parity(y, x) {
+                    Some(Parity::Even) => writeln!(
+                        f,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#,
+                        cx - CELL * 3 / 8,
+                        cy - CELL * 3 / 8,
+                        CELL * 3 / 4,
+                        CELL * 3 / 4,
+                    )?,
+                    Some(Parity::Odd) => {
+                        writeln!(f, r#"<circle cx="{cx}" cy="{cy}" r="{}"/>"#, CELL * 3 / 8)?;
+                    }
+                    None => (),
+                }
+            }
+        }
+        writeln!(f, "</g>")?;
+        writeln!(f, r#"<g stroke="black" stroke-linecap="square">"#)?;
+        for i in 0..=9 {
+            let pos = MARGIN + i * CELL;
+            let width = if i % 3 == 0 { 3 } else { 1 };
+            let (start, end) = (MARGIN, MARGIN + 9 * CELL);
+            writeln!(
+                f,
+                r#"<line x1="{start}" y1="{pos}" x2="{end}" y2="{pos}" stroke-width="{width}"/>"#
+            )?;
+            writeln!(
+                f,
+                r#"<line x1="{pos}" y1="{start}" x2="{pos}" y2="{end}" stroke-width="{width}"/>"#
+            )?;
+        }
+        writeln!(f, "</g>")?;
+        writeln!(
+            f,
+            r#"<g font-family="sans-serif" text-anchor="middle" dominant-baseline="central">"#
+        )?;
+        for y in 0..9 {
+            for x in 0..9 {
+                let (cx, cy) = center(y, x);
+                let given = self.puzzle[y][x];
+                let solved = self.solution.map_or(0, |s| s[y][x]);
+                if given != 0 {
+                    writeln!(
+                        f,
+                        r#"<text x="{cx}" y="{cy}" font-size="28" font-weight="bold">{given}</text>"#
+                    )?;
+                } else if solved != 0 {
+                    writeln!(
+                        f,
+                        r##"<text x="{cx}" y="{cy}" font-size="28" fill="#555">{solved}</text>"##
+                    )?;
+                } else if let Some(candidates) = self.candidates {
+                    for d in candidates.digits(y, x) {
+                        let i = usize::from(d - 1);
+                        let px = MARGIN + x * CELL + PENCIL_OFFSETS[i % 3];
+                        let py = MARGIN + y * CELL + PENCIL_OFFSETS[i / 3];
+                        writeln!(
+                            f,
+                            r##"<text x="{px}" y="{py}" font-size="10" fill="#555">{d}</text>"##
+                        )?;
+                    }
+                }
+            }
+        }
+        writeln!(f, "</g>")?;
+        writeln!(f, "</svg>")
+    }
+}
+
+/// Returns the coordinates of the center of the given cell
+fn center(y: usize, x: usize) -> (usize, usize) {
+    (MARGIN + x * CELL + CELL / 2, MARGIN + y * CELL + CELL / 2)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pencilmarks::Position;
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_svg_givens() {
+        let puzzle = test_puzzle();
+        let svg = Svg::new(&puzzle).to_string();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"364\""));
+        assert!(svg.ends_with("</g>\n</svg>\n"));
+        assert_eq!(svg.matches("font-weight=\"bold\"").count(), 32);
+        assert_eq!(svg.matches("<text").count(), 32);
+        assert_eq!(svg.matches("stroke-width=\"3\"").count(), 8);
+        assert_eq!(svg.matches("stroke-width=\"1\"").count(), 12);
+        assert!(svg
+            .contains("<text x=\"102\" y=\"22\" font-size=\"28\" font-weight=\"bold\">3</text>\n"));
+        assert_eq!(Svg::new(&puzzle).to_string(), svg);
+    }
+
+    #[test]
+    fn test_svg_solution() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let svg = Svg::new(&puzzle).with_solution(&solution).to_string();
+        assert_eq!(svg.matches("font-weight=\"bold\"").count(), 32);
+        assert_eq!(svg.matches("<text").count(), 81);
+        assert!(svg.contains("<text x=\"22\" y=\"22\" font-size=\"28\" fill=\"#555\">4</text>\n"));
+    }
+
+    #[test]
+    fn test_svg_pencil_marks() {
+        let puzzle = test_puzzle();
+        let pos = Position::new(puzzle);
+        let svg = Svg::new(&puzzle)
+            .with_candidates(&pos.candidates)
+            .to_string();
+        assert!(svg.contains("<text x=\"22\" y=\"22\" font-size=\"10\" fill=\"#555\">5</text>\n"));
+        assert!(svg.contains("<text x=\"10\" y=\"22\" font-size=\"10\" fill=\"#555\">4</text>\n"));
+    }
+
+    #[test]
+    fn test_svg_parity() {
+        let puzzle: Puzzle = "e........\no........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n"
+            .parse()
+            .unwrap();
+        let svg = Svg::new(&puzzle).to_string();
+        assert!(svg.contains("<rect x=\"7\" y=\"7\" width=\"30\" height=\"30\"/>\n"));
+        assert!(svg.contains("<circle cx=\"22\" cy=\"62\" r=\"15\"/>\n"));
+    }
+}
@@ -0,0 +1,433 @@
+//! Rendering puzzles & solutions for display in a terminal
+//!
+//! A [`Terminal`] draws a [`Puzzle`], optionally filled in with its
+//! [`Solution`], in one of three [`Style`]s, and can use ANSI escape
+//! sequences to distinguish the puzzle's givens from the digits filled in by
+//! the solver.
+use crate::{Parity, Puzzle, Solution};
+use std::fmt;
+use std::io::IsTerminal;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// ANSI escape sequence for displaying givens
+const GIVEN_COLOR: &str = "\x1B[1m";
+
+/// ANSI escape sequence for displaying digits filled in by the solver
+const SOLVED_COLOR: &str = "\x1B[36m";
+
+/// ANSI escape sequence for displaying a player's wrong entries
+const WRONG_COLOR: &str = "\x1B[1;31m";
+
+/// ANSI escape sequence for resetting the display attributes
+const RESET: &str = "\x1B[0m";
+
+/// The styles in which a [`Terminal`] can draw a grid
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Style {
+    /// Nine lines of nine digits with no spacing or borders, as produced by
+    /// the default [`Display`](fmt::Display) forms of [`Puzzle`] &
+    /// [`Solution`]
+    #[default]
+    Plain,
+
+    /// A border drawn around the grid and between boxes with ASCII characters,
+    /// as produced by the alternate `Display` forms of `Puzzle` & `Solution`
+    Ascii,
+
+    /// A border drawn around each cell with Unicode box-drawing characters,
+    /// using heavy lines on the edges of the boxes
+    Unicode,
+}
+
+/// Parse a style from its name (`plain`, `ascii`, or `unicode`), ignoring
+/// case
+///
+/// # Errors
+///
+/// Fails if the name is not recognized.
+impl FromStr for Style {
+    type Err = ParseStyleError;
+
+    fn from_str(s: &str) -> Result<Style, ParseStyleError> {
+        match s.to_ascii_lowercase().as_str() {
+            "plain" => Ok(Style::Plain),
+            "ascii" => Ok(Style::Ascii),
+            "unicode" => Ok(Style::Unicode),
+            _ => Err(ParseStyleError),
+        }
+    }
+}
+
+/// Error type returned when parsing an unknown [`Style`]
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+#[error("unknown style; expected \"plain\", \"ascii\", or \"unicode\"")]
+pub struct ParseStyleError;
+
+/// A terminal rendering of a puzzle.
+///
+/// A `Terminal` can show the puzzle's givens alone, the givens together with
+/// the digits filled in from a [`Solution`], or — in "diff mode," enabled by
+/// supplying both a solution and a player's attempt via
+/// [`Terminal::with_attempt()`] — the givens together with the player's
+/// entries, with those that disagree with the solution flagged as wrong.
+///
+/// Filled-in digits can be marked in two ways:
+///
+/// - When color is enabled, givens are drawn in bold, filled-in digits are
+///   drawn in cyan, and wrong entries are drawn in bold red.  Color is never
+///   used with [`Style::Plain`], which is intended for machine consumption.
+///
+/// - When brackets are enabled, filled-in digits are drawn surrounded by
+///   brackets (e.g., `[4]`), and wrong entries are drawn surrounded by
+///   exclamation points (e.g., `!4!`).  This widens every cell of the
+///   [`Style::Plain`] and [`Style::Ascii`] styles to three characters.
+///
+/// The [`Display`](fmt::Display) implementation draws the grid without a
+/// final terminating newline.
+#[derive(Copy, Clone, Debug)]
+pub struct Terminal<'a> {
+    puzzle: &'a Puzzle,
+    solution: Option<&'a Solution>,
+    attempt: Option<&'a Puzzle>,
+    style: Style,
+    color: bool,
+    brackets: bool,
+}
+
+impl<'a> Terminal<'a> {
+    /// Construct a plain, uncolored rendering of the givens of `puzzle`
+    pub fn new(puzzle: &'a Puzzle) -> Self {
+        Terminal {
+            puzzle,
+            solution: None,
+            attempt: None,
+            style: Style::Plain,
+            color: false,
+            brackets: false,
+        }
+    }
+
+    /// Also draw the digits of `solution` in the puzzle's unfilled cells
+    pub fn with_solution(self, solution: &'a Solution) -> Self {
+        Terminal {
+            solution: Some(solution),
+            ..self
+        }
+    }
+
+    /// Draw a player's attempt at solving the puzzle instead of the digits
+    /// of the solution.  If a solution has also been supplied, any entries in
+    /// `attempt` that disagree with it (including any that overwrite the
+    /// puzzle's givens) are flagged as wrong; unfilled cells in `attempt` are
+    /// drawn as unfilled.
+    pub fn with_attempt(self, attempt: &'a Puzzle) -> Self {
+        Terminal {
+            attempt: Some(attempt),
+            ..self
+        }
+    }
+
+    /// Set the style in which to draw the grid
+    pub fn with_style(self, style: Style) -> Self {
+        Terminal { style, ..self }
+    }
+
+    /// Set whether to use ANSI colors
+    pub fn with_color(self, color: bool) -> Self {
+        Terminal { color, ..self }
+    }
+
+    /// Enable ANSI colors if [`stdout_supports_color()`] returns `true`
+    pub fn with_auto_color(self) -> Self {
+        self.with_color(stdout_supports_color())
+    }
+
+    /// Set whether to surround filled-in digits & wrong entries with brackets
+    /// & exclamation points, respectively
+    pub fn with_brackets(self, brackets: bool) -> Self {
+        Terminal { brackets, ..self }
+    }
+
+    /// Returns the positions of the cells in the attempt that disagree with
+    /// the solution, in row-major order.  If no attempt or no solution has
+    /// been supplied, the result is empty.
+    pub fn wrong_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for y in 0..9 {
+            for x in 0..9 {
+                if let Cell::Wrong(_) = self.cell(y, x) {
+                    cells.push((y, x));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Determine how to display cell `(y, x)`
+    fn cell(&self, y: usize, x: usize) -> Cell {
+        let given = self.puzzle[y][x];
+        let solved = self.solution.map_or(0, |s| s[y][x]);
+        match (given, self.attempt.map(|a| a[y][x])) {
+            (0, None) if solved == 0 => self.blank(y, x),
+            (0, None) => Cell::Filled(solved),
+            (0, Some(0)) => self.blank(y, x),
+            (_, Some(d)) if d != 0 && d != given && solved != 0 && d != solved => Cell::Wrong(d),
+            (0, Some(d)) => Cell::Filled(d),
+            (d, _) => Cell::Given(d),
+        }
+    }
+
+    fn blank(&self, y: usize, x: usize) -> Cell {
+        let blank = match self.style {
+            Style::Plain => '0',
+            _ => ' ',
+        };
+        Cell::Blank(self.puzzle.parity(y, x).map_or(blank, Parity::to_char))
+    }
+
+    /// Returns true if each cell should be drawn three characters wide
+    fn wide(&self) -> bool {
+        self.brackets || self.style == Style::Unicode
+    }
+
+    /// Write the contents of cell `(y, x)`, colored & bracketed if
+    /// appropriate
+    fn write_cell(&self, f: &mut fmt::Formatter<'_>, y: usize, x: usize) -> fmt::Result {
+        let color = self.color && self.style != Style::Plain;
+        let (open, close) = match self.cell(y, x) {
+            Cell::Filled(_) if self.brackets => ('[', ']'),
+            Cell::Wrong(_) if self.brackets => ('!', '!'),
+            _ => (' ', ' '),
+        };
+        if self.wide() {
+            write!(f, "{open}")?;
+        }
+        match self.cell(y, x) {
+            Cell::Blank(c) => write!(f, "{c}")?,
+            Cell::Given(d) if color => write!(f, "{GIVEN_COLOR}{d}{RESET}")?,
+            Cell::Filled(d) if color => write!(f, "{SOLVED_COLOR}{d}{RESET}")?,
+            Cell::Wrong(d) if color => write!(f, "{WRONG_COLOR}{d}{RESET}")?,
+            Cell::Given(d) | Cell::Filled(d) | Cell::Wrong(d) => write!(f, "{d}")?,
+        }
+        if self.wide() {
+            write!(f, "{close}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The contents of a cell as displayed by a [`Terminal`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Cell {
+    /// An unfilled cell, drawn as the given character
+    Blank(char),
+    /// A given of the puzzle
+    Given(u8),
+    /// A digit filled in from the solution or by the player
+    Filled(u8),
+    /// A player's entry that disagrees with the solution
+    Wrong(u8),
+}
+
+impl fmt::Display for Terminal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Style::Plain => {
+                for y in 0..9 {
+                    if y > 0 {
+                        writeln!(f)?;
+                    }
+                    for x in 0..9 {
+                        self.write_cell(f, y, x)?;
+                    }
+                }
+            }
+            Style::Ascii => {
+                let divider = if self.wide() {
+                    "+---------+---------+---------+"
+                } else {
+                    "+-----+-----+-----+"
+                };
+                for y in 0..9 {
+                    if y % 3 == 0 {
+                        writeln!(f, "{divider}")?;
+                    }
+                    for x in 0..9 {
+                        if x % 3 == 0 {
+                            write!(f, "|")?;
+                        } else if !self.wide() {
+                            write!(f, " ")?;
+                        }
+                        self.write_cell(f, y, x)?;
+                    }
+                    writeln!(f, "|")?;
+                }
+                write!(f, "{divider}")?;
+            }
+            Style::Unicode => {
+                for y in 0..9 {
+                    match y {
+                        0 => writeln!(f, "{}", unicode_rule(['┏', '━', '┯', '┳', '┓']))?,
+                        3 | 6 => writeln!(f, "{}", unicode_rule(['┣', '━', '┿', '╋', '┫']))?,
+                        _ => writeln!(f, "{}", unicode_rule(['┠', '─', '┼', '╂', '┨']))?,
+                    }
+                    for x in 0..9 {
+                        write!(f, "{}", if x % 3 == 0 { '┃' } else { '│' })?;
+                        self.write_cell(f, y, x)?;
+                    }
+                    writeln!(f, "┃")?;
+                }
+                write!(f, "{}", unicode_rule(['┗', '━', '┷', '┻', '┛']))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Build a horizontal rule from the given left edge, line, light crossing,
+/// heavy crossing, and right edge characters
+fn unicode_rule([left, line, light, heavy, right]: [char; 5]) -> String {
+    let mut s = String::from(left);
+    for x in 0..9 {
+        if x > 0 {
+            s.push(if x % 3 == 0 { heavy } else { light });
+        }
+        for _ in 0..3 {
+            s.push(line);
+        }
+    }
+    s.push(right);
+    s
+}
+
+/// Returns `true` if standard output is a terminal and the `NO_COLOR`
+/// environment variable is not set to a nonempty value
+pub fn stdout_supports_color() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").map_or(true, |v| v.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_style_from_str() {
+        assert_eq!("Unicode".parse::<Style>(), Ok(Style::Unicode));
+        assert_eq!("fancy".parse::<Style>(), Err(ParseStyleError));
+    }
+
+    #[test]
+    fn test_plain_and_ascii_match_display() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let term = Terminal::new(&puzzle);
+        assert_eq!(term.to_string(), puzzle.to_string());
+        assert_eq!(
+            term.with_style(Style::Ascii).to_string(),
+            format!("{puzzle:#}")
+        );
+        let term = term.with_solution(&solution).with_color(true);
+        assert_eq!(term.to_string(), solution.to_string());
+        assert_eq!(
+            term.with_style(Style::Ascii).with_color(false).to_string(),
+            format!("{solution:#}")
+        );
+    }
+
+    #[test]
+    fn test_unicode() {
+        let puzzle = test_puzzle();
+        let term = Terminal::new(&puzzle).with_style(Style::Unicode);
+        let s = term.to_string();
+        let lines = s.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 19);
+        assert_eq!(lines[0], "┏━━━┯━━━┯━━━┳━━━┯━━━┯━━━┳━━━┯━━━┯━━━┓");
+        assert_eq!(lines[1], "┃   │   │ 3 ┃   │ 2 │   ┃ 6 │   │   ┃");
+        assert_eq!(lines[2], "┠───┼───┼───╂───┼───┼───╂───┼───┼───┨");
+        assert_eq!(lines[6], "┣━━━┿━━━┿━━━╋━━━┿━━━┿━━━╋━━━┿━━━┿━━━┫");
+        assert_eq!(lines[18], "┗━━━┷━━━┷━━━┻━━━┷━━━┷━━━┻━━━┷━━━┷━━━┛");
+        assert!(!s.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_color() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let s = Terminal::new(&puzzle)
+            .with_solution(&solution)
+            .with_style(Style::Unicode)
+            .with_color(true)
+            .to_string();
+        let line = s.lines().nth(1).unwrap();
+        assert!(line.starts_with("┃ \x1B[36m4\x1B[0m │ \x1B[36m8\x1B[0m │ \x1B[1m3\x1B[0m ┃"));
+        assert_eq!(s.matches("\x1B[1m").count(), 32);
+        assert_eq!(s.matches("\x1B[36m").count(), 49);
+    }
+
+    #[test]
+    fn test_brackets() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let term = Terminal::new(&puzzle)
+            .with_solution(&solution)
+            .with_brackets(true);
+        let s = term.to_string();
+        assert_eq!(s.lines().next().unwrap(), "[4][8] 3 [9] 2 [1] 6 [5][7]");
+        let s = term.with_style(Style::Ascii).to_string();
+        let lines = s.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "+---------+---------+---------+");
+        assert_eq!(lines[1], "|[4][8] 3 |[9] 2 [1]| 6 [5][7]|");
+        let s = term.with_style(Style::Unicode).to_string();
+        assert_eq!(
+            s.lines().nth(1).unwrap(),
+            "┃[4]│[8]│ 3 ┃[9]│ 2 │[1]┃ 6 │[5]│[7]┃"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let attempt: Puzzle = "4.3521657\n9..3.5..1\n..18.64..\n..81.29..\n7.......8\n..67.82..\n..26.95..\n8..2.3..9\n..5.1.3..\n"
+            .parse()
+            .unwrap();
+        let term = Terminal::new(&puzzle)
+            .with_solution(&solution)
+            .with_attempt(&attempt)
+            .with_brackets(true)
+            .with_style(Style::Ascii);
+        let s = term.to_string();
+        assert_eq!(s.lines().nth(1).unwrap(), "|[4]    3 |!5! 2 [1]| 6 [5][7]|");
+        assert_eq!(s.lines().nth(2).unwrap(), "| 9       | 3     5 |       1 |");
+        assert_eq!(term.wrong_cells(), vec![(0, 3)]);
+        let s = term.with_brackets(false).with_color(true).to_string();
+        assert!(s
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("|\x1B[36m4\x1B[0m   \x1B[1m3\x1B[0m|\x1B[1;31m5\x1B[0m "));
+    }
+
+    #[test]
+    fn test_diff_overwritten_given() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let attempt: Puzzle = "..4......\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n.........\n"
+            .parse()
+            .unwrap();
+        let term = Terminal::new(&puzzle)
+            .with_solution(&solution)
+            .with_attempt(&attempt);
+        assert_eq!(term.wrong_cells(), vec![(0, 2)]);
+        assert_eq!(
+            term.with_brackets(true).to_string().lines().next().unwrap(),
+            " 0  0 !4! 0  2  0  6  0  0 "
+        );
+        assert!(Terminal::new(&puzzle)
+            .with_attempt(&attempt)
+            .wrong_cells()
+            .is_empty());
+    }
+}
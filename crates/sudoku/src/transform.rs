@@ -0,0 +1,419 @@
+//! Rearranging puzzles without changing their logic
+//!
+//! A [`Transform`] is a combination of operations that turn any valid Sudoku
+//! grid into another valid grid: rotating, reflecting, or transposing the
+//! grid, relabeling the digits, swapping bands (groups of three rows) or
+//! stacks (groups of three columns), and swapping rows within a band or
+//! columns within a stack.  Applying the same transform to a puzzle and to
+//! its solution produces a new puzzle together with its solution, and the new
+//! puzzle is exactly as hard as the old one.
+//!
+//! Transforms are built from the individual operations with
+//! [`Transform::then()`]:
+//!
+//! ```
+//! use sudoku::transform::Transform;
+//!
+//! let t = Transform::rotate(1)
+//!     .then(Transform::swap_bands(0, 2).unwrap())
+//!     .then(Transform::permute_digits([2, 1, 3, 4, 5, 6, 7, 8, 9]).unwrap());
+//! ```
+use crate::{Puzzle, Solution};
+use thiserror::Error;
+
+/// A rearrangement of Sudoku grids that preserves validity.
+///
+/// Every transform can be described as optionally transposing the grid,
+/// then rearranging its rows & columns (keeping the rows of each band and the
+/// columns of each stack together), and then relabeling its digits.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Transform {
+    /// Whether to transpose the grid before rearranging rows & columns
+    transpose: bool,
+
+    /// Row `y` of the output is taken from row `rows[y]` of the (possibly
+    /// transposed) input
+    rows: [usize; 9],
+
+    /// Column `x` of the output is taken from column `cols[x]` of the
+    /// (possibly transposed) input
+    cols: [usize; 9],
+
+    /// Digit `d` of the input becomes digit `digits[d]` of the output;
+    /// `digits[0]` is always 0
+    digits: [u8; 10],
+}
+
+const IDENTITY: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+const REVERSED: [usize; 9] = [8, 7, 6, 5, 4, 3, 2, 1, 0];
+
+impl Transform {
+    /// The transform that leaves grids unchanged
+    pub fn identity() -> Transform {
+        Transform {
+            transpose: false,
+            rows: IDENTITY,
+            cols: IDENTITY,
+            digits: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        }
+    }
+
+    /// Rotate the grid clockwise by `quarter_turns` quarter turns
+    pub fn rotate(quarter_turns: u32) -> Transform {
+        match quarter_turns % 4 {
+            0 => Transform::identity(),
+            1 => Transform {
+                transpose: true,
+                cols: REVERSED,
+                ..Transform::identity()
+            },
+            2 => Transform {
+                rows: REVERSED,
+                cols: REVERSED,
+                ..Transform::identity()
+            },
+            _ => Transform {
+                transpose: true,
+                rows: REVERSED,
+                ..Transform::identity()
+            },
+        }
+    }
+
+    /// Reflect the grid left-to-right, reversing the order of the columns
+    pub fn reflect_horizontally() -> Transform {
+        Transform {
+            cols: REVERSED,
+            ..Transform::identity()
+        }
+    }
+
+    /// Reflect the grid top-to-bottom, reversing the order of the rows
+    pub fn reflect_vertically() -> Transform {
+        Transform {
+            rows: REVERSED,
+            ..Transform::identity()
+        }
+    }
+
+    /// Transpose the grid, swapping its rows with its columns
+    pub fn transpose() -> Transform {
+        Transform {
+            transpose: true,
+            ..Transform::identity()
+        }
+    }
+
+    /// Relabel the digits of the grid, changing each digit `d` to
+    /// `permutation[d - 1]`
+    ///
+    /// # Errors
+    ///
+    /// Fails if `permutation` does not contain each of the digits 1 through 9
+    /// exactly once.
+    pub fn permute_digits(permutation: [u8; 9]) -> Result<Transform, TransformError> {
+        let mut seen = [false; 10];
+        for &d in &permutation {
+            if !(1..=9).contains(&d) || std::mem::replace(&mut seen[usize::from(d)], true) {
+                return Err(TransformError::NotAPermutation);
+            }
+        }
+        let mut digits = [0; 10];
+        digits[1..].copy_from_slice(&permutation);
+        Ok(Transform {
+            digits,
+            ..Transform::identity()
+        })
+    }
+
+    /// Swap two bands (groups of three rows), numbered from 0 to 2 from top
+    /// to bottom
+    ///
+    /// # Errors
+    ///
+    /// Fails if either band number is greater than 2.
+    pub fn swap_bands(a: usize, b: usize) -> Result<Transform, TransformError> {
+        Ok(Transform {
+            rows: swap_groups(a, b)?,
+            ..Transform::identity()
+        })
+    }
+
+    /// Swap two stacks (groups of three columns), numbered from 0 to 2 from
+    /// left to right
+    ///
+    /// # Errors
+    ///
+    /// Fails if either stack number is greater than 2.
+    pub fn swap_stacks(a: usize, b: usize) -> Result<Transform, TransformError> {
+        Ok(Transform {
+            cols: swap_groups(a, b)?,
+            ..Transform::identity()
+        })
+    }
+
+    /// Swap two rows in the same band, numbered from 0 to 8 from top to
+    /// bottom
+    ///
+    /// # Errors
+    ///
+    /// Fails if either row number is greater than 8 or if the rows are in
+    /// different bands.
+    pub fn swap_rows(a: usize, b: usize) -> Result<Transform, TransformError> {
+        Ok(Transform {
+            rows: swap_lines(a, b)?,
+            ..Transform::identity()
+        })
+    }
+
+    /// Swap two columns in the same stack, numbered from 0 to 8 from left to
+    /// right
+    ///
+    /// # Errors
+    ///
+    /// Fails if either column number is greater than 8 or if the columns are
+    /// in different stacks.
+    pub fn swap_columns(a: usize, b: usize) -> Result<Transform, TransformError> {
+        Ok(Transform {
+            cols: swap_lines(a, b)?,
+            ..Transform::identity()
+        })
+    }
+
+    /// Choose a transform at random, determined by `seed`, from among all
+    /// 1,218,998,108,160 possible transforms
+    pub fn random(seed: u64) -> Transform {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let line_order = |rng: &mut fastrand::Rng| {
+            let mut groups = [0, 1, 2];
+            rng.shuffle(&mut groups);
+            let mut order = [0; 9];
+            for (i, g) in groups.into_iter().enumerate() {
+                let mut within = [0, 1, 2];
+                rng.shuffle(&mut within);
+                for (j, w) in within.into_iter().enumerate() {
+                    order[i * 3 + j] = g * 3 + w;
+                }
+            }
+            order
+        };
+        let transpose = rng.bool();
+        let rows = line_order(&mut rng);
+        let cols = line_order(&mut rng);
+        let mut digits = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        rng.shuffle(&mut digits[1..]);
+        Transform {
+            transpose,
+            rows,
+            cols,
+            digits,
+        }
+    }
+
+    /// Return the transform that applies this transform and then `next`
+    pub fn then(self, next: Transform) -> Transform {
+        // If `next` transposes, its row order is applied to our columns &
+        // vice versa:
+        let (rows, cols) = if next.transpose {
+            (self.cols, self.rows)
+        } else {
+            (self.rows, self.cols)
+        };
+        Transform {
+            transpose: self.transpose != next.transpose,
+            rows: next.rows.map(|r| rows[r]),
+            cols: next.cols.map(|c| cols[c]),
+            digits: self.digits.map(|d| next.digits[usize::from(d)]),
+        }
+    }
+
+    /// Returns `true` if the transform maps even digits to even digits and
+    /// odd digits to odd digits
+    pub fn preserves_parity(&self) -> bool {
+        self.digits
+            .iter()
+            .zip(0u8..)
+            .all(|(&new, old)| new % 2 == old % 2)
+    }
+
+    /// Apply the transform to a puzzle.
+    ///
+    /// Parity shading is moved along with the shaded cells if the transform
+    /// [preserves parity](Transform::preserves_parity); otherwise, it is
+    /// discarded, as the relabeled digits would no longer fit the shading.
+    pub fn apply_to_puzzle(&self, puzzle: &Puzzle) -> Puzzle {
+        let grid = self.apply_grid(&puzzle.grid, |d| self.digits[usize::from(d)]);
+        let parities = if self.preserves_parity() {
+            self.apply_grid(&puzzle.parities, |p| p)
+        } else {
+            [[None; 9]; 9]
+        };
+        Puzzle::from_grid(grid).with_parities(parities)
+    }
+
+    /// Apply the transform to a solution.  The result is the solution to the
+    /// result of applying the transform to the original puzzle.
+    pub fn apply_to_solution(&self, solution: &Solution) -> Solution {
+        Solution(self.apply_grid(&solution.0, |d| self.digits[usize::from(d)]))
+    }
+
+    /// Rearrange the cells of a grid and map their values through `f`
+    fn apply_grid<T: Copy, F: Fn(T) -> T>(&self, grid: &[[T; 9]; 9], f: F) -> [[T; 9]; 9] {
+        std::array::from_fn(|y| {
+            std::array::from_fn(|x| {
+                let (r, c) = (self.rows[y], self.cols[x]);
+                f(if self.transpose {
+                    grid[c][r]
+                } else {
+                    grid[r][c]
+                })
+            })
+        })
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+/// Error returned when constructing an invalid [`Transform`]
+#[derive(Copy, Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum TransformError {
+    /// Returned when a digit permutation does not contain each digit exactly
+    /// once
+    #[error("digit permutation must contain each of 1 through 9 exactly once")]
+    NotAPermutation,
+
+    /// Returned when a band, stack, row, or column number is out of range
+    #[error("index {0} is out of range")]
+    OutOfRange(usize),
+
+    /// Returned when trying to swap rows in different bands or columns in
+    /// different stacks
+    #[error("cannot swap lines in different bands or stacks")]
+    DifferentGroups,
+}
+
+/// Return the line order that swaps two groups of three lines
+fn swap_groups(a: usize, b: usize) -> Result<[usize; 9], TransformError> {
+    for i in [a, b] {
+        if i > 2 {
+            return Err(TransformError::OutOfRange(i));
+        }
+    }
+    let mut order = IDENTITY;
+    for i in 0..3 {
+        order.swap(a * 3 + i, b * 3 + i);
+    }
+    Ok(order)
+}
+
+/// Return the line order that swaps two lines in the same group
+fn swap_lines(a: usize, b: usize) -> Result<[usize; 9], TransformError> {
+    for i in [a, b] {
+        if i > 8 {
+            return Err(TransformError::OutOfRange(i));
+        }
+    }
+    if a / 3 != b / 3 {
+        return Err(TransformError::DifferentGroups);
+    }
+    let mut order = IDENTITY;
+    order.swap(a, b);
+    Ok(order)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+    use crate::Parity;
+
+    #[test]
+    fn test_rotate() {
+        let puzzle = test_puzzle();
+        let rotated = Transform::rotate(1).apply_to_puzzle(&puzzle);
+        // The first column, read from bottom to top, becomes the first row:
+        assert_eq!(rotated[0], [0, 8, 0, 0, 7, 0, 0, 9, 0]);
+        assert_eq!(rotated[8], [0, 9, 0, 0, 8, 0, 0, 1, 0]);
+        let twice = Transform::rotate(1).then(Transform::rotate(1));
+        assert_eq!(twice, Transform::rotate(2));
+        assert_eq!(
+            Transform::rotate(2).then(Transform::rotate(2)),
+            Transform::identity()
+        );
+        assert_eq!(Transform::rotate(3).apply_to_puzzle(&rotated), puzzle);
+    }
+
+    #[test]
+    fn test_compose_matches_sequential() {
+        let puzzle = test_puzzle();
+        let steps = [
+            Transform::transpose(),
+            Transform::swap_bands(0, 2).unwrap(),
+            Transform::reflect_horizontally(),
+            Transform::swap_rows(3, 5).unwrap(),
+            Transform::rotate(3),
+            Transform::swap_columns(7, 6).unwrap(),
+            Transform::permute_digits([9, 8, 7, 6, 5, 4, 3, 2, 1]).unwrap(),
+            Transform::swap_stacks(1, 2).unwrap(),
+            Transform::reflect_vertically(),
+        ];
+        let mut sequential = puzzle;
+        let mut composed = Transform::identity();
+        for t in steps {
+            sequential = t.apply_to_puzzle(&sequential);
+            composed = composed.then(t);
+        }
+        assert_eq!(composed.apply_to_puzzle(&puzzle), sequential);
+    }
+
+    #[test]
+    fn test_solution_stays_consistent() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        for seed in 0..10 {
+            let t = Transform::random(seed);
+            let new_puzzle = t.apply_to_puzzle(&puzzle);
+            let new_solution = t.apply_to_solution(&solution);
+            assert_eq!(new_solution.verify(&new_puzzle), Ok(()));
+            assert_eq!(new_puzzle.solve(), Some(new_solution));
+            assert!(new_puzzle.is_equivalent(&puzzle));
+        }
+        assert_eq!(Transform::random(42), Transform::random(42));
+    }
+
+    #[test]
+    fn test_parity() {
+        let mut parities = [[None; 9]; 9];
+        parities[0][0] = Some(Parity::Even);
+        let puzzle = test_puzzle().with_parities(parities);
+        let flipped = Transform::reflect_horizontally().apply_to_puzzle(&puzzle);
+        assert_eq!(flipped.parity(0, 8), Some(Parity::Even));
+        assert_eq!(flipped.parity(0, 0), None);
+        let swap = Transform::permute_digits([3, 2, 1, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert!(swap.preserves_parity());
+        let relabeled = Transform::permute_digits([2, 1, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert!(!relabeled.preserves_parity());
+        assert_eq!(relabeled.apply_to_puzzle(&puzzle).parity(0, 0), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Transform::permute_digits([1, 1, 3, 4, 5, 6, 7, 8, 9]),
+            Err(TransformError::NotAPermutation)
+        );
+        assert_eq!(
+            Transform::swap_bands(0, 3),
+            Err(TransformError::OutOfRange(3))
+        );
+        assert_eq!(
+            Transform::swap_rows(2, 3),
+            Err(TransformError::DifferentGroups)
+        );
+    }
+}
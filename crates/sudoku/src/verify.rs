@@ -0,0 +1,411 @@
+//! Checking completed grids against the rules of Sudoku
+//!
+//! A [`Grid`] is an arbitrary 9×9 grid of digits, such as a player's
+//! submitted answer to a puzzle, that can be checked for whether it is a valid
+//! Sudoku solution with [`Grid::is_valid_solution()`] or for whether it solves
+//! a given puzzle with [`Grid::verify()`].  Each problem found is reported as
+//! a [`Violation`].
+use crate::{Parity, Puzzle, Solution};
+use std::fmt;
+use std::ops::Deref;
+use thiserror::Error;
+
+/// A 9×9 grid of cell values, which is not necessarily complete or valid.
+///
+/// `Grid` instances can be constructed from a `[[u8; 9]; 9]` array (in which
+/// 0 represents an unfilled cell), from the digits of a [`Puzzle`], or from a
+/// [`Solution`] via the [`From`]/[`Into`] traits.
+///
+/// As `Grid` implements `Deref<[[u8; 9]; 9]>`, it can be indexed to obtain the
+/// individual rows of the grid.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Grid([[u8; 9]; 9]);
+
+impl Grid {
+    /// Check whether the grid is a complete and valid Sudoku solution, i.e.,
+    /// whether every cell contains a digit from 1 through 9 and every row,
+    /// column, and box contains each digit exactly once.
+    ///
+    /// # Errors
+    ///
+    /// If the grid is not a valid solution, a list of every problem found is
+    /// returned.  Unfilled cells and out-of-range values are listed first, in
+    /// row-major order, followed by repeated digits in rows, then columns,
+    /// then boxes.
+    pub fn is_valid_solution(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        for y in 0..9 {
+            for x in 0..9 {
+                match self.0[y][x] {
+                    0 => violations.push(Violation::Unfilled { cell: (y, x) }),
+                    1..=9 => (),
+                    value => violations.push(Violation::BadValue {
+                        cell: (y, x),
+                        value,
+                    }),
+                }
+            }
+        }
+        for unit in Unit::all() {
+            let cells = unit.cells();
+            for digit in 1..=9 {
+                let found = cells
+                    .iter()
+                    .copied()
+                    .filter(|&(y, x)| self.0[y][x] == digit)
+                    .collect::<Vec<_>>();
+                if found.len() > 1 {
+                    violations.push(Violation::Repeated {
+                        unit,
+                        digit,
+                        cells: found,
+                    });
+                }
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Check whether the grid is a valid solution to `puzzle`, i.e., whether
+    /// it is a valid Sudoku solution (see [`Grid::is_valid_solution()`]) that
+    /// preserves all of the puzzle's givens and respects the puzzle's parity
+    /// shading.
+    ///
+    /// # Errors
+    ///
+    /// If the grid is not a valid solution to `puzzle`, a list of every
+    /// problem found is returned.  Changed givens & parity mismatches are
+    /// listed first, in row-major order, followed by the problems reported by
+    /// `is_valid_solution()`.
+    pub fn verify(&self, puzzle: &Puzzle) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        for y in 0..9 {
+            for x in 0..9 {
+                let (given, found) = (puzzle[y][x], self.0[y][x]);
+                if given != 0 && given != found {
+                    violations.push(Violation::ChangedGiven {
+                        cell: (y, x),
+                        given,
+                        found,
+                    });
+                } else if let Some(parity) = puzzle.parity(y, x) {
+                    if found != 0 && !parity.allows(found) {
+                        violations.push(Violation::WrongParity {
+                            cell: (y, x),
+                            parity,
+                            found,
+                        });
+                    }
+                }
+            }
+        }
+        if let Err(vs) = self.is_valid_solution() {
+            violations.extend(vs);
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+impl Deref for Grid {
+    type Target = [[u8; 9]; 9];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<[[u8; 9]; 9]> for Grid {
+    fn from(value: [[u8; 9]; 9]) -> Grid {
+        Grid(value)
+    }
+}
+
+/// Convert the digits of a [`Puzzle`] into a [`Grid`], discarding any parity
+/// shading
+impl From<Puzzle> for Grid {
+    fn from(value: Puzzle) -> Grid {
+        Grid(value.grid)
+    }
+}
+
+impl From<Solution> for Grid {
+    fn from(value: Solution) -> Grid {
+        Grid(value.0)
+    }
+}
+
+impl From<Grid> for [[u8; 9]; 9] {
+    fn from(value: Grid) -> [[u8; 9]; 9] {
+        value.0
+    }
+}
+
+impl Solution {
+    /// Check whether the solution is a valid solution to `puzzle`.
+    ///
+    /// This is a shortcut for converting the solution to a [`Grid`] and
+    /// calling [`Grid::verify()`].
+    ///
+    /// # Errors
+    ///
+    /// If the solution does not solve `puzzle`, a list of every problem found
+    /// is returned.
+    pub fn verify(&self, puzzle: &Puzzle) -> Result<(), Vec<Violation>> {
+        Grid::from(*self).verify(puzzle)
+    }
+}
+
+/// A row, column, or box of a Sudoku grid.  The argument of each variant is
+/// a zero-based index; boxes are numbered in row-major order.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Unit {
+    /// A row, indexed from 0 (top) to 8 (bottom)
+    Row(usize),
+    /// A column, indexed from 0 (leftmost) to 8 (rightmost)
+    Column(usize),
+    /// A 3×3 box, indexed from 0 (top left) to 8 (bottom right) in row-major
+    /// order
+    Box(usize),
+}
+
+impl Unit {
+    /// Returns all 27 units, in the order rows, columns, boxes
+    pub fn all() -> impl Iterator<Item = Unit> {
+        (0..9)
+            .map(Unit::Row)
+            .chain((0..9).map(Unit::Column))
+            .chain((0..9).map(Unit::Box))
+    }
+
+    /// Returns the `(y, x)` coordinates of the cells in the unit, in
+    /// row-major order
+    pub fn cells(self) -> [(usize, usize); 9] {
+        let mut cells = [(0, 0); 9];
+        for (i, c) in cells.iter_mut().enumerate() {
+            *c = match self {
+                Unit::Row(y) => (y, i),
+                Unit::Column(x) => (i, x),
+                Unit::Box(b) => ((b / 3) * 3 + i / 3, (b % 3) * 3 + i % 3),
+            };
+        }
+        cells
+    }
+}
+
+/// Display a unit with a one-based index, e.g., "row 1"
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(i) => write!(f, "row {}", i + 1),
+            Unit::Column(i) => write!(f, "column {}", i + 1),
+            Unit::Box(i) => write!(f, "box {}", i + 1),
+        }
+    }
+}
+
+/// A problem found when checking a [`Grid`].
+///
+/// Cells are identified by zero-based `(y, x)` coordinates, but are
+/// displayed in one-based "r*Y*c*X*" notation.
+#[derive(Clone, Debug, Eq, Error, Hash, PartialEq)]
+pub enum Violation {
+    /// A cell was not filled in
+    #[error("{} is unfilled", Rc(*.cell))]
+    Unfilled {
+        /// The zero-based `(y, x)` coordinates of the cell
+        cell: (usize, usize),
+    },
+
+    /// A cell contains a value larger than 9
+    #[error("{} contains invalid value {value}", Rc(*.cell))]
+    BadValue {
+        /// The zero-based `(y, x)` coordinates of the cell
+        cell: (usize, usize),
+        /// The value found in the cell
+        value: u8,
+    },
+
+    /// A digit occurs more than once in a row, column, or box.  `cells` lists
+    /// the positions of every occurrence, in row-major order.
+    #[error("{digit} is repeated in {unit} at {}", RcList(.cells))]
+    Repeated {
+        /// The unit containing the repeated digit
+        unit: Unit,
+        /// The repeated digit, from 1 to 9
+        digit: u8,
+        /// The zero-based `(y, x)` coordinates of the cells containing the
+        /// digit
+        cells: Vec<(usize, usize)>,
+    },
+
+    /// A cell that contained a given in the puzzle contains a different value
+    #[error("{} should contain the given {given} but contains {found}", Rc(*.cell))]
+    ChangedGiven {
+        /// The zero-based `(y, x)` coordinates of the cell
+        cell: (usize, usize),
+        /// The digit given in the puzzle
+        given: u8,
+        /// The value found in the cell, or 0 if the cell is unfilled
+        found: u8,
+    },
+
+    /// A cell shaded to require an even or odd digit contains a digit of the
+    /// wrong parity
+    #[error("{} should contain an {parity} digit but contains {found}", Rc(*.cell))]
+    WrongParity {
+        /// The zero-based `(y, x)` coordinates of the cell
+        cell: (usize, usize),
+        /// The parity required by the cell's shading
+        parity: Parity,
+        /// The value found in the cell
+        found: u8,
+    },
+}
+
+/// Helper for displaying a cell in "r*Y*c*X*" notation
+pub(crate) struct Rc(pub(crate) (usize, usize));
+
+impl fmt::Display for Rc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (y, x) = self.0;
+        write!(f, "r{}c{}", y + 1, x + 1)
+    }
+}
+
+/// Helper for displaying a comma-separated list of cells
+pub(crate) struct RcList<'a>(pub(crate) &'a [(usize, usize)]);
+
+impl fmt::Display for RcList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, &cell) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", Rc(cell))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_puzzle;
+
+    #[test]
+    fn test_valid_solution() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        assert_eq!(solution.verify(&puzzle), Ok(()));
+        assert_eq!(Grid::from(solution).is_valid_solution(), Ok(()));
+    }
+
+    #[test]
+    fn test_unit_cells() {
+        assert_eq!(
+            Unit::Box(5).cells(),
+            [
+                (3, 6),
+                (3, 7),
+                (3, 8),
+                (4, 6),
+                (4, 7),
+                (4, 8),
+                (5, 6),
+                (5, 7),
+                (5, 8)
+            ]
+        );
+        assert_eq!(Unit::all().count(), 27);
+        assert_eq!(Unit::Column(0).to_string(), "column 1");
+    }
+
+    #[test]
+    fn test_swapped_digits() {
+        let puzzle = test_puzzle();
+        let mut grid: [[u8; 9]; 9] = puzzle.solve().unwrap().into();
+        // Swap the 4 & 8 at r1c1 & r1c2, which are not givens:
+        grid[0].swap(0, 1);
+        let grid = Grid::from(grid);
+        assert_eq!(
+            grid.is_valid_solution(),
+            Err(vec![
+                Violation::Repeated {
+                    unit: Unit::Column(0),
+                    digit: 8,
+                    cells: vec![(0, 0), (7, 0)],
+                },
+                Violation::Repeated {
+                    unit: Unit::Column(1),
+                    digit: 4,
+                    cells: vec![(0, 1), (3, 1)],
+                },
+            ])
+        );
+        let err = grid.verify(&puzzle).unwrap_err();
+        assert_eq!(err.len(), 2);
+        assert_eq!(
+            err[0].to_string(),
+            "8 is repeated in column 1 at r1c1, r8c1"
+        );
+    }
+
+    #[test]
+    fn test_changed_given_and_unfilled() {
+        let puzzle = test_puzzle();
+        let mut grid: [[u8; 9]; 9] = puzzle.solve().unwrap().into();
+        grid[0][2] = 0;
+        grid[8][8] = 12;
+        let err = Grid::from(grid).verify(&puzzle).unwrap_err();
+        assert_eq!(
+            err,
+            vec![
+                Violation::ChangedGiven {
+                    cell: (0, 2),
+                    given: 3,
+                    found: 0
+                },
+                Violation::Unfilled { cell: (0, 2) },
+                Violation::BadValue {
+                    cell: (8, 8),
+                    value: 12
+                },
+            ]
+        );
+        assert_eq!(
+            err[0].to_string(),
+            "r1c3 should contain the given 3 but contains 0"
+        );
+        assert_eq!(err[1].to_string(), "r1c3 is unfilled");
+    }
+
+    #[test]
+    fn test_wrong_parity() {
+        let puzzle = test_puzzle();
+        let solution = puzzle.solve().unwrap();
+        let mut parities = [[None; 9]; 9];
+        parities[0][0] = Some(Parity::Odd);
+        let shaded = puzzle.with_parities(parities);
+        let err = solution.verify(&shaded).unwrap_err();
+        assert_eq!(
+            err,
+            vec![Violation::WrongParity {
+                cell: (0, 0),
+                parity: Parity::Odd,
+                found: 4
+            }]
+        );
+        assert_eq!(
+            err[0].to_string(),
+            "r1c1 should contain an odd digit but contains 4"
+        );
+    }
+}
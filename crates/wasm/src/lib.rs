@@ -0,0 +1,359 @@
+//! JavaScript bindings for the Sudoku solver
+//!
+//! This crate exposes the [`sudoku`] crate's solver, hints, difficulty
+//! ratings, and generator to JavaScript via
+//! [wasm-bindgen](https://github.com/rustwasm/wasm-bindgen).  Build it for
+//! the `wasm32-unknown-unknown` target and run `wasm-bindgen` (or `wasm-pack`)
+//! on the output to produce a JavaScript module.
+//!
+//! Each function takes a puzzle in one of two forms:
+//!
+//! - a string of 81 characters listing the cells row by row, with `1` through
+//!   `9` for givens and `.` or `0` for unfilled cells.  Whitespace is ignored,
+//!   so a grid split across nine lines is also accepted.
+//!
+//! - a `Uint8Array` of 81 cells in the same order, with 0 for unfilled cells.
+//!   The functions taking this form have names ending in `Grid`.
+//!
+//! Grids are returned in the same form as they were given.  Cells are
+//! referred to by their index in the 81-cell order, from 0 to 80.  Invalid
+//! input is reported by throwing an `Error`.
+use sudoku::generate::Generator;
+use sudoku::logic::{self, Step};
+use sudoku::pencilmarks::Position;
+use sudoku::{Puzzle, Solution, TryIntoPuzzleError};
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+type Grid = [[u8; 9]; 9];
+
+/// Solve a puzzle given as a string, returning the solution as a string of 81
+/// digits.  If the puzzle has multiple solutions, which one is returned is
+/// unspecified.
+///
+/// # Errors
+///
+/// Fails if the puzzle is invalid or has no solution.
+#[wasm_bindgen]
+pub fn solve(puzzle: &str) -> Result<String, JsError> {
+    let solution = solve_puzzle(&parse_puzzle(puzzle)?)?;
+    Ok(grid_to_string(&solution))
+}
+
+/// Solve a puzzle given as a `Uint8Array`, returning the solution as a
+/// `Uint8Array`.  If the puzzle has multiple solutions, which one is returned
+/// is unspecified.
+///
+/// # Errors
+///
+/// Fails if the puzzle is invalid or has no solution.
+#[wasm_bindgen(js_name = solveGrid)]
+pub fn solve_grid(puzzle: &[u8]) -> Result<Vec<u8>, JsError> {
+    let solution = solve_puzzle(&grid_to_puzzle(puzzle)?)?;
+    Ok(flatten(&solution))
+}
+
+/// Find the easiest deduction that can be made about a puzzle given as a
+/// string.  Returns `undefined` if no deduction can be made, either because
+/// the puzzle is already solved or because it is too hard for the supported
+/// techniques.
+///
+/// # Errors
+///
+/// Fails if the puzzle is invalid.
+#[wasm_bindgen]
+pub fn hint(puzzle: &str) -> Result<Option<Hint>, JsError> {
+    Ok(next_hint(parse_puzzle(puzzle)?))
+}
+
+/// Find the easiest deduction that can be made about a puzzle given as a
+/// `Uint8Array`.  Returns `undefined` if no deduction can be made, either
+/// because the puzzle is already solved or because it is too hard for the
+/// supported techniques.
+///
+/// # Errors
+///
+/// Fails if the puzzle is invalid.
+#[wasm_bindgen(js_name = hintGrid)]
+pub fn hint_grid(puzzle: &[u8]) -> Result<Option<Hint>, JsError> {
+    Ok(next_hint(grid_to_puzzle(puzzle)?))
+}
+
+/// Rate the difficulty of a puzzle given as a string, returning one of
+/// `"easy"`, `"medium"`, `"hard"`, `"expert"`, or `"extreme"`
+///
+/// # Errors
+///
+/// Fails if the puzzle is invalid.
+#[wasm_bindgen]
+pub fn rate(puzzle: &str) -> Result<String, JsError> {
+    Ok(rate_puzzle(&parse_puzzle(puzzle)?))
+}
+
+/// Rate the difficulty of a puzzle given as a `Uint8Array`, returning one of
+/// `"easy"`, `"medium"`, `"hard"`, `"expert"`, or `"extreme"`
+///
+/// # Errors
+///
+/// Fails if the puzzle is invalid.
+#[wasm_bindgen(js_name = rateGrid)]
+pub fn rate_grid(puzzle: &[u8]) -> Result<String, JsError> {
+    Ok(rate_puzzle(&grid_to_puzzle(puzzle)?))
+}
+
+/// Generate a minimal puzzle with a unique solution from `seed`.  If
+/// `symmetric` is true, the givens have 180° rotational symmetry.  The same
+/// seed and symmetry always produce the same puzzle.
+#[wasm_bindgen]
+pub fn generate(seed: u32, symmetric: bool) -> Generated {
+    let (puzzle, solution) = Generator::new(u64::from(seed))
+        .with_symmetry(symmetric)
+        .generate();
+    Generated { puzzle, solution }
+}
+
+/// A deduction about a puzzle, as returned by [`hint()`]
+#[wasm_bindgen]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hint(Step);
+
+#[wasm_bindgen]
+impl Hint {
+    /// The name of the technique used, e.g., "Hidden single"
+    #[wasm_bindgen(getter)]
+    pub fn technique(&self) -> String {
+        self.0.technique().to_string()
+    }
+
+    /// An English explanation of the deduction
+    #[wasm_bindgen(getter)]
+    pub fn explanation(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// The index of the cell in which the deduction places a digit, or
+    /// `undefined` if the deduction only removes candidates
+    #[wasm_bindgen(getter)]
+    pub fn cell(&self) -> Option<usize> {
+        self.0.placement().map(|(cell, _)| cell_index(cell))
+    }
+
+    /// The digit placed by the deduction, or `undefined` if the deduction only
+    /// removes candidates
+    #[wasm_bindgen(getter)]
+    pub fn digit(&self) -> Option<u8> {
+        self.0.placement().map(|(_, digit)| digit)
+    }
+
+    /// The candidates removed by the deduction, as a flat array of
+    /// alternating cell indices & digits.  This is empty for deductions that
+    /// place a digit.
+    #[wasm_bindgen(getter)]
+    pub fn eliminations(&self) -> Vec<usize> {
+        self.0
+            .eliminations()
+            .iter()
+            .flat_map(|&(cell, digit)| [cell_index(cell), usize::from(digit)])
+            .collect()
+    }
+}
+
+/// A generated puzzle and its solution, as returned by [`generate()`]
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Generated {
+    puzzle: Puzzle,
+    solution: Solution,
+}
+
+#[wasm_bindgen]
+impl Generated {
+    /// The puzzle as a string of 81 characters, with `.` for unfilled cells
+    #[wasm_bindgen(getter)]
+    pub fn puzzle(&self) -> String {
+        grid_to_string(&self.puzzle)
+    }
+
+    /// The puzzle's solution as a string of 81 digits
+    #[wasm_bindgen(getter)]
+    pub fn solution(&self) -> String {
+        grid_to_string(&self.solution)
+    }
+
+    /// The puzzle as a `Uint8Array`, with 0 for unfilled cells
+    #[wasm_bindgen(getter, js_name = puzzleGrid)]
+    pub fn puzzle_grid(&self) -> Vec<u8> {
+        flatten(&self.puzzle)
+    }
+
+    /// The puzzle's solution as a `Uint8Array`
+    #[wasm_bindgen(getter, js_name = solutionGrid)]
+    pub fn solution_grid(&self) -> Vec<u8> {
+        flatten(&self.solution)
+    }
+}
+
+/// Error type for invalid input to the bindings
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+enum InputError {
+    #[error("puzzle must have 81 cells, but {0} were given")]
+    Length(usize),
+    #[error("invalid character {0:?} in puzzle")]
+    Character(char),
+    #[error(transparent)]
+    Grid(#[from] TryIntoPuzzleError),
+    #[error("puzzle has no solution")]
+    NoSolution,
+}
+
+fn parse_puzzle(s: &str) -> Result<Puzzle, InputError> {
+    let cells = s
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '.' => Ok(0),
+            _ => c
+                .to_digit(10)
+                .and_then(|d| u8::try_from(d).ok())
+                .ok_or(InputError::Character(c)),
+        })
+        .collect::<Result<Vec<u8>, InputError>>()?;
+    grid_to_puzzle(&cells)
+}
+
+fn grid_to_puzzle(cells: &[u8]) -> Result<Puzzle, InputError> {
+    if cells.len() != 81 {
+        return Err(InputError::Length(cells.len()));
+    }
+    Ok(Puzzle::try_from(cells.chunks(9).collect::<Vec<_>>())?)
+}
+
+fn grid_to_string(grid: &Grid) -> String {
+    grid.iter()
+        .flatten()
+        .map(|&d| if d == 0 { '.' } else { char::from(b'0' + d) })
+        .collect()
+}
+
+fn flatten(grid: &Grid) -> Vec<u8> {
+    grid.iter().flatten().copied().collect()
+}
+
+fn cell_index((y, x): (usize, usize)) -> usize {
+    9 * y + x
+}
+
+fn solve_puzzle(puzzle: &Puzzle) -> Result<Solution, InputError> {
+    puzzle.solve().ok_or(InputError::NoSolution)
+}
+
+fn next_hint(puzzle: Puzzle) -> Option<Hint> {
+    logic::next_step(&Position::new(puzzle)).map(Hint)
+}
+
+fn rate_puzzle(puzzle: &Puzzle) -> String {
+    logic::rate(puzzle).difficulty.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PUZZLE: &str = concat!(
+        "..3.2.6..",
+        "9..3.5..1",
+        "..18.64..",
+        "..81.29..",
+        "7.......8",
+        "..67.82..",
+        "..26.95..",
+        "8..2.3..9",
+        "..5.1.3..",
+    );
+
+    const SOLUTION: &str = concat!(
+        "483921657",
+        "967345821",
+        "251876493",
+        "548132976",
+        "729564138",
+        "136798245",
+        "372689514",
+        "814253769",
+        "695417382",
+    );
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(solve(PUZZLE).unwrap(), SOLUTION);
+        let grid = parse_puzzle(PUZZLE).unwrap();
+        let solution = solve_grid(&flatten(&grid)).unwrap();
+        assert_eq!(
+            grid_to_string(&Puzzle::try_from(solution.chunks(9).collect::<Vec<_>>()).unwrap()),
+            SOLUTION
+        );
+    }
+
+    #[test]
+    fn test_parse_multiline_with_zeroes() {
+        let text = PUZZLE
+            .as_bytes()
+            .chunks(9)
+            .map(|row| String::from_utf8_lossy(row).replace('.', "0") + "\n")
+            .collect::<String>();
+        assert_eq!(parse_puzzle(&text), parse_puzzle(PUZZLE));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_puzzle("123"), Err(InputError::Length(3)));
+        assert_eq!(
+            parse_puzzle(&PUZZLE.replacen('.', "x", 1)),
+            Err(InputError::Character('x'))
+        );
+        let mut grid = flatten(&parse_puzzle(PUZZLE).unwrap());
+        grid[0] = 10;
+        assert_eq!(
+            grid_to_puzzle(&grid),
+            Err(InputError::Grid(TryIntoPuzzleError::NumTooBig(10)))
+        );
+        let unsolvable = parse_puzzle(&PUZZLE.replacen('.', "3", 1)).unwrap();
+        assert_eq!(solve_puzzle(&unsolvable), Err(InputError::NoSolution));
+    }
+
+    #[test]
+    fn test_hint() {
+        let step = hint(PUZZLE).unwrap().unwrap();
+        assert!(!step.explanation().is_empty());
+        let (cell, digit) = (step.cell().unwrap(), step.digit().unwrap());
+        assert_eq!(SOLUTION.as_bytes()[cell], b'0' + digit);
+        assert!(step.eliminations().is_empty());
+        assert_eq!(
+            hint_grid(&flatten(&parse_puzzle(PUZZLE).unwrap())).unwrap(),
+            Some(step)
+        );
+        assert_eq!(hint(SOLUTION).unwrap(), None);
+    }
+
+    #[test]
+    fn test_rate() {
+        assert_eq!(rate(PUZZLE).unwrap(), "easy");
+        assert_eq!(rate_grid(&[0; 81]).unwrap(), "extreme");
+    }
+
+    #[test]
+    fn test_generate() {
+        let generated = generate(42, true);
+        assert_eq!(generated, generate(42, true));
+        assert_eq!(solve(&generated.puzzle()).unwrap(), generated.solution());
+        let puzzle = generated.puzzle_grid();
+        let solution = generated.solution_grid();
+        assert_eq!(puzzle.len(), 81);
+        for (i, &d) in puzzle.iter().enumerate() {
+            if d != 0 {
+                assert_eq!(d, solution[i]);
+                assert_ne!(puzzle[80 - i], 0);
+            }
+        }
+    }
+}
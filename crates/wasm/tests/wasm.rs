@@ -0,0 +1,68 @@
+// These tests exercise the bindings as compiled to WebAssembly, including the
+// errors thrown to JavaScript.  Run them with:
+//
+//     cargo test -p sudoku-wasm --target wasm32-unknown-unknown
+#![cfg(target_arch = "wasm32")]
+// Clippy only relaxes these for `#[test]` functions:
+#![allow(clippy::missing_assert_message, clippy::unwrap_used)]
+use sudoku_wasm::{generate, hint, hint_grid, rate, solve, solve_grid};
+use wasm_bindgen::{JsError, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+static PUZZLE: &str =
+    "..3.2.6..9..3.5..1..18.64....81.29..7.......8..67.82....26.95..8..2.3..9..5.1.3..";
+
+static SOLUTION: &str =
+    "483921657967345821251876493548132976729564138136798245372689514814253769695417382";
+
+fn message(e: JsError) -> String {
+    js_sys::Error::from(JsValue::from(e)).message().into()
+}
+
+#[wasm_bindgen_test]
+fn test_solve() {
+    assert_eq!(solve(PUZZLE).unwrap(), SOLUTION);
+    let grid = PUZZLE
+        .bytes()
+        .map(|c| if c == b'.' { 0 } else { c - b'0' })
+        .collect::<Vec<_>>();
+    let solution = SOLUTION.bytes().map(|c| c - b'0').collect::<Vec<_>>();
+    assert_eq!(solve_grid(&grid).unwrap(), solution);
+}
+
+#[wasm_bindgen_test]
+fn test_errors() {
+    assert_eq!(
+        message(solve("123").unwrap_err()),
+        "puzzle must have 81 cells, but 3 were given"
+    );
+    assert_eq!(
+        message(solve(&PUZZLE.replacen('.', "3", 1)).unwrap_err()),
+        "puzzle has no solution"
+    );
+    assert_eq!(
+        message(rate(&PUZZLE.replacen('.', "?", 1)).unwrap_err()),
+        "invalid character '?' in puzzle"
+    );
+    let mut grid = vec![0; 81];
+    grid[40] = 12;
+    assert_eq!(
+        message(hint_grid(&grid).unwrap_err()),
+        "cell value 12 is too large"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_hint_and_rate() {
+    let step = hint(PUZZLE).unwrap().unwrap();
+    let cell = step.cell().unwrap();
+    assert_eq!(SOLUTION.as_bytes()[cell], b'0' + step.digit().unwrap());
+    assert_eq!(rate(PUZZLE).unwrap(), "easy");
+}
+
+#[wasm_bindgen_test]
+fn test_generate() {
+    let generated = generate(7, false);
+    assert_eq!(solve(&generated.puzzle()).unwrap(), generated.solution());
+    assert_eq!(generate(7, false).puzzle_grid(), generated.puzzle_grid());
+}